@@ -0,0 +1,38 @@
+//! Measures the cost of a store/get cycle. Run with `cargo bench`.
+
+use dyn_box::dynbox;
+use std::hint::black_box;
+use std::time::Instant;
+
+trait Shape {
+	fn area(&self) -> u32;
+}
+
+struct Square(u32);
+
+impl Shape for Square {
+	fn area(&self) -> u32 {
+		self.0 * self.0
+	}
+}
+
+dynbox!(ShapeBox: Shape);
+
+fn main() {
+	const ITERATIONS: u32 = 10_000_000;
+
+	let mut dynbox = ShapeBox::<16>::new();
+	let mut sum = 0u32;
+	let start = Instant::now();
+	for i in 0..ITERATIONS {
+		dynbox.set(Square(black_box(i)));
+		sum = sum.wrapping_add(dynbox.get().unwrap().area());
+	}
+	let elapsed = start.elapsed();
+	black_box(sum);
+
+	println!(
+		"store/get cycle: {:.2} ns",
+		elapsed.as_nanos() as f64 / ITERATIONS as f64
+	);
+}
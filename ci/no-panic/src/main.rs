@@ -0,0 +1,73 @@
+#![no_std]
+#![no_main]
+
+use core::hint::black_box;
+use dyn_box::dynbox;
+
+pub trait Sensor {
+	fn read(&self) -> u32;
+	fn calibrate(&mut self, offset: u32);
+}
+
+#[derive(Clone)]
+struct Fixed(u32);
+#[derive(Clone)]
+struct Pair(u16, u16);
+
+impl Sensor for Fixed {
+	fn read(&self) -> u32 {
+		self.0
+	}
+	fn calibrate(&mut self, offset: u32) {
+		self.0 = self.0.wrapping_add(offset);
+	}
+}
+impl Sensor for Pair {
+	fn read(&self) -> u32 {
+		self.0 as u32 + self.1 as u32
+	}
+	fn calibrate(&mut self, offset: u32) {
+		self.0 = self.0.wrapping_add(offset as u16);
+	}
+}
+
+dynbox!(SensorBox: Sensor, no_panic, clone, generation, delegate { fn read(&self) -> u32 = 0; fn calibrate(&mut self, offset: u32) = (); });
+dynbox!(Job: FnOnce() -> u32, no_panic);
+
+fn exercise(boxes: &mut [SensorBox<8>], index: usize, offset: u32) -> u32 {
+	let mut total = 0;
+	if let Ok(handle) = SensorBox::set_at(boxes, index, Fixed(offset)) {
+		total += SensorBox::get_by_handle(boxes, handle).map_or(0, |sensor| sensor.read());
+	}
+	for dynbox in boxes.iter_mut() {
+		if dynbox.try_set(Pair(offset as u16, 1)).is_err() {
+			dynbox.set(Fixed(offset));
+		}
+		if let Some(mut occupied) = dynbox.occupied() {
+			occupied.get_mut().calibrate(offset);
+		}
+		let clone = dynbox.clone();
+		total += clone.read() + dynbox.get_mut().map_or(0, |sensor| sensor.read());
+		total += dynbox.set_scoped(Fixed(offset)).read();
+	}
+
+	let mut job = Job::<8>::new();
+	job.set(move || offset * 2);
+	total + job.call_once().unwrap_or(0)
+}
+
+#[no_mangle]
+pub extern "C" fn _start() -> ! {
+	let mut boxes = [SensorBox::<8>::new(), SensorBox::<8>::new()];
+	black_box(exercise(black_box(&mut boxes), black_box(1), black_box(3)));
+	loop {}
+}
+
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+	extern "Rust" {
+		// Deliberately undefined, so that linking fails if a panic is reachable.
+		fn reachable_panic_in_no_panic_dynbox() -> !;
+	}
+	unsafe { reachable_panic_in_no_panic_dynbox() }
+}
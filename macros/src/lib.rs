@@ -0,0 +1,334 @@
+//! Attribute macros for `dyn_box`, re-exported by it with the `macros` feature.
+
+use proc_macro::{Delimiter, Ident, Span, TokenStream, TokenTree};
+
+/// Generates a DynBox inside an inline module, whose default size and alignment
+/// are the largest of the module's items marked with `#[dynbox_impl(Name)]`,
+/// like `dynbox_for!`. Takes the name and trait of the DynBox, optionally
+/// followed by a `max_size` which no marked item may exceed, and by further
+/// `dynbox!` options after a `;`:
+/// `#[dynbox_registry(Name: Trait, max_size = 64; clone)]`.
+///
+/// The DynBox is defined inside the module, so the trait is resolved from
+/// there. Only the items directly inside the module are collected, as macro
+/// expansions cannot share state otherwise. The marked items must be structs,
+/// enums or unions without generic parameters. Expects the `dyn_box` crate
+/// under its own name.
+#[proc_macro_attribute]
+pub fn dynbox_registry(attr: TokenStream, item: TokenStream) -> TokenStream {
+	match Registry::parse(attr).and_then(|registry| registry.expand(item)) {
+		Ok(tokens) => tokens,
+		Err(error) => error.into_compile_error(),
+	}
+}
+
+/// Registers a struct, enum or union with the DynBox of the surrounding
+/// `#[dynbox_registry]` module, which is sized to hold it. Fails to compile
+/// outside such a module.
+#[proc_macro_attribute]
+pub fn dynbox_impl(_attr: TokenStream, item: TokenStream) -> TokenStream {
+	let mut tokens = Error::new(
+		Span::call_site(),
+		"`#[dynbox_impl]` must be used directly inside a `#[dynbox_registry]` module",
+	)
+	.into_compile_error();
+	tokens.extend(item);
+	tokens
+}
+
+struct Error {
+	span: Span,
+	message: String,
+}
+
+impl Error {
+	fn new(span: Span, message: impl Into<String>) -> Error {
+		Error {
+			span,
+			message: message.into(),
+		}
+	}
+
+	fn into_compile_error(self) -> TokenStream {
+		let tokens: TokenStream = format!("::core::compile_error!({:?});", self.message)
+			.parse()
+			.unwrap();
+		tokens
+			.into_iter()
+			.map(|mut token| {
+				token.set_span(self.span);
+				token
+			})
+			.collect()
+	}
+}
+
+/// The arguments of `#[dynbox_registry]`.
+struct Registry {
+	name: Ident,
+	trait_path: TokenStream,
+	max_size: Option<TokenStream>,
+	options: Option<TokenStream>,
+}
+
+impl Registry {
+	fn parse(attr: TokenStream) -> Result<Registry, Error> {
+		let mut tokens = attr.into_iter().peekable();
+		let name = match tokens.next() {
+			Some(TokenTree::Ident(name)) => name,
+			other => return Err(expected(other.as_ref(), "the name of the DynBox")),
+		};
+		match tokens.next() {
+			Some(TokenTree::Punct(colon)) if colon.as_char() == ':' => {}
+			other => return Err(expected(other.as_ref(), "`:`")),
+		}
+
+		// The trait ends at the first `,` or `;` outside of angle brackets, which
+		// are not grouped like other brackets.
+		let mut trait_path = TokenStream::new();
+		let mut depth = 0usize;
+		let mut arrow = false;
+		let mut separator = None;
+		for token in tokens.by_ref() {
+			if let TokenTree::Punct(punct) = &token {
+				match punct.as_char() {
+					',' | ';' if depth == 0 => {
+						separator = Some(punct.as_char());
+						break;
+					}
+					'<' => depth += 1,
+					'>' if !arrow => depth = depth.saturating_sub(1),
+					_ => {}
+				}
+				arrow = punct.as_char() == '-';
+			} else {
+				arrow = false;
+			}
+			trait_path.extend([token]);
+		}
+		if trait_path.is_empty() {
+			return Err(Error::new(name.span(), "expected the trait of the DynBox"));
+		}
+
+		let mut max_size = None;
+		if separator == Some(',') {
+			match tokens.next() {
+				Some(TokenTree::Ident(key)) if key.to_string() == "max_size" => {}
+				other => return Err(expected(other.as_ref(), "`max_size`")),
+			}
+			match tokens.next() {
+				Some(TokenTree::Punct(equals)) if equals.as_char() == '=' => {}
+				other => return Err(expected(other.as_ref(), "`=`")),
+			}
+			let mut size = TokenStream::new();
+			for token in tokens.by_ref() {
+				if matches!(&token, TokenTree::Punct(punct) if punct.as_char() == ';') {
+					separator = Some(';');
+					break;
+				}
+				size.extend([token]);
+			}
+			max_size = Some(size);
+		}
+
+		let options = (separator == Some(';')).then(|| tokens.collect());
+		Ok(Registry {
+			name,
+			trait_path,
+			max_size,
+			options,
+		})
+	}
+
+	fn expand(self, item: TokenStream) -> Result<TokenStream, Error> {
+		let mut tokens: Vec<TokenTree> = item.into_iter().collect();
+		let body = match tokens.last() {
+			Some(TokenTree::Group(body)) if body.delimiter() == Delimiter::Brace => body.clone(),
+			_ => {
+				return Err(Error::new(
+					Span::call_site(),
+					"`#[dynbox_registry]` must be used on an inline module",
+				))
+			}
+		};
+		let is_module = tokens
+			.iter()
+			.any(|token| matches!(token, TokenTree::Ident(ident) if ident.to_string() == "mod"));
+		if !is_module {
+			return Err(Error::new(
+				Span::call_site(),
+				"`#[dynbox_registry]` must be used on an inline module",
+			));
+		}
+
+		let (mut items, types) = self.collect(body.stream())?;
+		if types.is_empty() {
+			return Err(Error::new(
+				self.name.span(),
+				format!("no items are marked with `#[dynbox_impl({})]`", self.name),
+			));
+		}
+		items.extend(self.definition(&types));
+
+		let mut group = proc_macro::Group::new(Delimiter::Brace, items);
+		group.set_span(body.span());
+		*tokens.last_mut().unwrap() = TokenTree::Group(group);
+		Ok(tokens.into_iter().collect())
+	}
+
+	/// Returns the items of the module without the `#[dynbox_impl]` attributes,
+	/// and the names of the items they marked.
+	fn collect(&self, body: TokenStream) -> Result<(TokenStream, Vec<Ident>), Error> {
+		let mut items = TokenStream::new();
+		let mut types = Vec::new();
+		// The span of a `#[dynbox_impl]` whose item was not reached yet.
+		let mut marked: Option<Span> = None;
+		let mut tokens = body.into_iter().peekable();
+		while let Some(token) = tokens.next() {
+			match &token {
+				TokenTree::Punct(pound) if pound.as_char() == '#' => {
+					if let Some(TokenTree::Group(attribute)) = tokens.peek() {
+						if attribute.delimiter() == Delimiter::Bracket {
+							if let Some(span) = self.parse_marker(attribute.stream())? {
+								if marked.is_some() {
+									return Err(Error::new(span, "duplicate `#[dynbox_impl]`"));
+								}
+								marked = Some(span);
+								tokens.next();
+								continue;
+							}
+						}
+					}
+				}
+				TokenTree::Ident(keyword) if marked.is_some() => match keyword.to_string().as_str()
+				{
+					"struct" | "enum" | "union" => {
+						items.extend([token.clone()]);
+						let name = match tokens.next() {
+							Some(TokenTree::Ident(name)) => name,
+							other => return Err(expected(other.as_ref(), "the name of the item")),
+						};
+						if matches!(tokens.peek(), Some(TokenTree::Punct(punct)) if punct.as_char() == '<')
+						{
+							return Err(Error::new(
+								name.span(),
+								"items with generic parameters cannot be registered with a DynBox",
+							));
+						}
+						items.extend([TokenTree::Ident(name.clone())]);
+						types.push(name);
+						marked = None;
+						continue;
+					}
+					"pub" => {}
+					_ => {
+						return Err(Error::new(
+							marked.unwrap(),
+							"`#[dynbox_impl]` must be used on a struct, enum or union",
+						))
+					}
+				},
+				_ => {}
+			}
+			items.extend([token]);
+		}
+		match marked {
+			Some(span) => Err(Error::new(
+				span,
+				"`#[dynbox_impl]` must be followed by an item",
+			)),
+			None => Ok((items, types)),
+		}
+	}
+
+	/// Returns the span of the attribute if its content is `dynbox_impl(Name)`,
+	/// or None if it is another attribute.
+	fn parse_marker(&self, attribute: TokenStream) -> Result<Option<Span>, Error> {
+		// Also accept paths like `dyn_box::dynbox_impl`.
+		let mut tokens = attribute.into_iter().peekable();
+		let mut marker = None;
+		while let Some(TokenTree::Ident(_)) = tokens.peek() {
+			let Some(TokenTree::Ident(ident)) = tokens.next() else {
+				unreachable!()
+			};
+			match tokens.peek() {
+				Some(TokenTree::Punct(colon)) if colon.as_char() == ':' => {
+					tokens.next();
+					match tokens.next() {
+						Some(TokenTree::Punct(colon)) if colon.as_char() == ':' => {}
+						_ => return Ok(None),
+					}
+				}
+				_ => {
+					marker = Some(ident);
+					break;
+				}
+			}
+		}
+		let marker = match marker {
+			Some(marker) if marker.to_string() == "dynbox_impl" => marker,
+			_ => return Ok(None),
+		};
+		let arguments = match tokens.next() {
+			Some(TokenTree::Group(arguments))
+				if arguments.delimiter() == Delimiter::Parenthesis =>
+			{
+				arguments
+			}
+			other => return Err(expected(other.as_ref(), "`(`")),
+		};
+		let mut names = arguments.stream().into_iter();
+		match (names.next(), names.next()) {
+			(Some(TokenTree::Ident(name)), None) if name.to_string() == self.name.to_string() => {
+				Ok(Some(marker.span()))
+			}
+			(Some(name), _) => Err(Error::new(
+				name.span(),
+				format!("this module registers implementors of `{}` only", self.name),
+			)),
+			(None, _) => Err(Error::new(
+				arguments.span(),
+				"expected the name of the DynBox",
+			)),
+		}
+	}
+
+	/// Returns the definition of the DynBox, and the assertions checking
+	/// `max_size`.
+	fn definition(&self, types: &[Ident]) -> TokenStream {
+		let list = types
+			.iter()
+			.map(Ident::to_string)
+			.collect::<Vec<_>>()
+			.join(", ");
+		let options = self
+			.options
+			.as_ref()
+			.map_or(String::new(), |options| format!("; {options}"));
+		let mut definition = format!(
+			"::dyn_box::dynbox_for!({}: {}; {list}{options});",
+			self.name, self.trait_path
+		);
+		if let Some(max_size) = &self.max_size {
+			for name in types {
+				definition += &format!(
+					"const _: () = ::core::assert!(::core::mem::size_of::<{name}>() <= ({max_size}), {:?});",
+					format!("`{name}` exceeds the `max_size` of `{}`", self.name),
+				);
+			}
+		}
+		let tokens: TokenStream = definition.parse().unwrap();
+		tokens
+			.into_iter()
+			.map(|mut token| {
+				token.set_span(self.name.span());
+				token
+			})
+			.collect()
+	}
+}
+
+fn expected(token: Option<&TokenTree>, what: &str) -> Error {
+	let span = token.map_or(Span::call_site(), TokenTree::span);
+	Error::new(span, format!("expected {what}"))
+}
@@ -0,0 +1,26 @@
+/// Way of running code without being interrupted, used by the cells generated
+/// by [`dynbox_cell!`](crate::dynbox_cell). On a single-core microcontroller,
+/// this usually disables interrupts. With the `critical-section` feature,
+/// [`GlobalCriticalSection`] uses the `critical-section` crate; other backends
+/// implement this trait themselves.
+///
+/// # Safety
+/// `with()` must not run `f` concurrently with any other `f` it was given, be
+/// it on another thread, core or in an interrupt handler.
+pub unsafe trait CriticalSection {
+	/// Runs `f` in a critical section.
+	fn with<R>(f: impl FnOnce() -> R) -> R;
+}
+
+/// The critical section provided by the `critical-section` crate, i.e. by
+/// whichever implementation the final binary links in.
+#[cfg(feature = "critical-section")]
+pub struct GlobalCriticalSection;
+
+// SAFETY: `critical_section::with()` upholds the same guarantee.
+#[cfg(feature = "critical-section")]
+unsafe impl CriticalSection for GlobalCriticalSection {
+	fn with<R>(f: impl FnOnce() -> R) -> R {
+		critical_section::with(|_| f())
+	}
+}
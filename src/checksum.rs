@@ -0,0 +1,9 @@
+/// Computes a 32-bit FNV-1a hash over `vtable` and `bytes`.
+pub fn checksum(vtable: usize, bytes: &[u8]) -> u32 {
+	let mut hash: u32 = 0x811c_9dc5;
+	for byte in vtable.to_ne_bytes().iter().chain(bytes) {
+		hash ^= *byte as u32;
+		hash = hash.wrapping_mul(0x0100_0193);
+	}
+	hash
+}
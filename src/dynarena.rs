@@ -0,0 +1,259 @@
+#[allow(unused_macros)]
+#[macro_export]
+/// Generates a pool of slots holding values which implement a certain trait,
+/// addressed through generational [`Handle`](crate::Handle)s which detect uses
+/// after the value was removed.
+/// Example:
+/// ```
+/// use dyn_box::dynarena;
+/// trait Sprite { fn x(&self) -> u32; }
+/// impl Sprite for u32 { fn x(&self) -> u32 { *self } }
+/// dynarena!(Sprites: Sprite);
+/// let mut sprites = Sprites::<16, 8>::new();
+/// let handle = sprites.insert(3u32).unwrap();
+/// assert!(sprites.get(handle).unwrap().x() == 3);
+/// sprites.remove(handle);
+/// assert!(sprites.get(handle).is_none());
+/// ```
+macro_rules! dynarena {
+	($name:ident : $trait:path) => {
+		/// Pool of `SLOTS` slots for a given Trait, each able to hold an implementor
+		/// of the trait if it does not exceed `SLOT_SIZE` bytes or 16 bytes
+		/// alignment. Values are addressed through the handles returned by
+		/// `insert()`, which stay valid until the value is removed. Generated through
+		/// the `dynarena!` macro
+		pub struct $name<'a, const SLOT_SIZE: usize, const SLOTS: usize> {
+			store: [$crate::Storage<SLOT_SIZE>; SLOTS],
+			vtables: [Option<core::ptr::NonNull<()>>; SLOTS],
+			generations: [usize; SLOTS],
+			// Neither `Send` nor `Sync`, as the content may not be.
+			_content: core::marker::PhantomData<(&'a (), *const ())>,
+		}
+
+		// The vtables are obtained by transmuting `*const dyn $trait` to two words.
+		const _: () = assert!(
+			core::mem::size_of::<*const dyn $trait>() == 2 * core::mem::size_of::<usize>(),
+			"trait object pointers are not a data pointer and a vtable pointer"
+		);
+
+		impl<const SLOT_SIZE: usize, const SLOTS: usize> Drop for $name<'_, SLOT_SIZE, SLOTS> {
+			fn drop(&mut self) {
+				for slot in 0..SLOTS {
+					self.remove_slot(slot);
+				}
+			}
+		}
+
+		impl<const SLOT_SIZE: usize, const SLOTS: usize> Default for $name<'_, SLOT_SIZE, SLOTS> {
+			fn default() -> Self {
+				Self::new()
+			}
+		}
+
+		impl<'a, const SLOT_SIZE: usize, const SLOTS: usize> $name<'a, SLOT_SIZE, SLOTS> {
+			/// Creates a new pool with all slots free.
+			pub const fn new() -> $name<'a, SLOT_SIZE, SLOTS> {
+				$name {
+					store: [$crate::Storage::new(); SLOTS],
+					vtables: [None; SLOTS],
+					generations: [0; SLOTS],
+					_content: core::marker::PhantomData,
+				}
+			}
+
+			/// Returns the number of values in the pool.
+			pub fn len(&self) -> usize {
+				self.vtables
+					.iter()
+					.filter(|vtable| vtable.is_some())
+					.count()
+			}
+
+			/// Returns whether the pool holds no values.
+			pub fn is_empty(&self) -> bool {
+				self.len() == 0
+			}
+
+			/// Stores a value of some generic type which implements $trait in a free
+			/// slot and returns its handle, or gives the value back if there is no free
+			/// slot or it does not fit into one.
+			pub fn insert<T: $trait + 'a>(&mut self, content: T) -> Result<$crate::Handle, T> {
+				let free = self.vtables.iter().position(|vtable| vtable.is_none());
+				let slot = match free {
+					Some(slot)
+						if core::mem::size_of::<T>() <= SLOT_SIZE
+							&& core::mem::align_of::<T>()
+								<= core::mem::align_of::<$crate::Storage<SLOT_SIZE>>() =>
+					{
+						slot
+					}
+					_ => return Err(content),
+				};
+
+				let parts = unsafe {
+					core::mem::transmute::<*const dyn $trait, $crate::DynParts>(
+						&content as *const dyn $trait,
+					)
+				};
+				unsafe { (self.store[slot].as_mut_ptr() as *mut T).write(content) }
+				self.vtables[slot] = Some(parts.vtable);
+				Ok($crate::Handle::new(slot, self.generations[slot]))
+			}
+
+			/// Returns whether `handle` refers to a value in the pool, i.e. whether it
+			/// was not removed since.
+			pub fn contains(&self, handle: $crate::Handle) -> bool {
+				self.generations.get(handle.index()) == Some(&handle.generation())
+					&& self.vtables[handle.index()].is_some()
+			}
+
+			/// Returns a `&dyn Trait` reference to the value `handle` refers to, or None
+			/// if it was removed.
+			pub fn get(&self, handle: $crate::Handle) -> Option<&dyn $trait> {
+				if self.contains(handle) {
+					Some(unsafe { &*self.get_ptr(handle.index()) })
+				} else {
+					None
+				}
+			}
+
+			/// Returns a `&mut dyn Trait` reference to the value `handle` refers to, or
+			/// None if it was removed.
+			pub fn get_mut(&mut self, handle: $crate::Handle) -> Option<&mut dyn $trait> {
+				if self.contains(handle) {
+					Some(unsafe { &mut *self.get_ptr_mut(handle.index()) })
+				} else {
+					None
+				}
+			}
+
+			/// Drops the value `handle` refers to, invalidating all handles to it.
+			/// Returns false if it was removed already.
+			pub fn remove(&mut self, handle: $crate::Handle) -> bool {
+				if self.contains(handle) {
+					self.remove_slot(handle.index());
+					true
+				} else {
+					false
+				}
+			}
+
+			/// Returns an iterator over the handles and values in the pool, in slot
+			/// order.
+			pub fn iter(&self) -> impl Iterator<Item = ($crate::Handle, &dyn $trait)> {
+				(0..SLOTS)
+					.filter(move |&slot| self.vtables[slot].is_some())
+					.map(move |slot| {
+						let handle = $crate::Handle::new(slot, self.generations[slot]);
+						(handle, unsafe { &*self.get_ptr(slot) })
+					})
+			}
+
+			/// Drops the value in `slot`, if any, and starts a new generation for it.
+			fn remove_slot(&mut self, slot: usize) {
+				if self.vtables[slot].is_some() {
+					let content = unsafe { self.get_ptr_mut(slot) };
+					// Free the slot first, so a panicking destructor cannot cause a double
+					// drop.
+					self.vtables[slot] = None;
+					self.generations[slot] = self.generations[slot].wrapping_add(1);
+					unsafe { core::ptr::drop_in_place(content) }
+				}
+			}
+
+			/// Returns a pointer to the content of `slot`, which is only valid for
+			/// reads.
+			unsafe fn get_ptr(&self, slot: usize) -> *const (dyn $trait + 'a) {
+				let data = self.store[slot].as_ptr() as *mut u8;
+				core::mem::transmute::<$crate::DynParts, *mut (dyn $trait + 'a)>($crate::DynParts {
+					data,
+					vtable: self.vtables[slot].unwrap_unchecked(),
+				})
+			}
+
+			/// Returns a pointer to the content of `slot`, which is also valid for
+			/// writes.
+			unsafe fn get_ptr_mut(&mut self, slot: usize) -> *mut (dyn $trait + 'a) {
+				let data = self.store[slot].as_mut_ptr();
+				core::mem::transmute::<$crate::DynParts, *mut (dyn $trait + 'a)>($crate::DynParts {
+					data,
+					vtable: self.vtables[slot].unwrap_unchecked(),
+				})
+			}
+		}
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	use core::cell::Cell;
+
+	pub trait MyTrait {
+		fn foo(&self) -> u32;
+	}
+
+	struct B(u128);
+	struct Droppable<'a>(&'a Cell<bool>);
+
+	impl MyTrait for B {
+		fn foo(&self) -> u32 {
+			self.0 as u32
+		}
+	}
+	impl MyTrait for Droppable<'_> {
+		fn foo(&self) -> u32 {
+			2
+		}
+	}
+	impl Drop for Droppable<'_> {
+		fn drop(&mut self) {
+			self.0.set(true);
+		}
+	}
+
+	dynarena!(Arena: MyTrait);
+
+	#[test]
+	fn handles_address_inserted_values() {
+		let mut arena = Arena::<16, 2>::new();
+		assert!(arena.is_empty());
+		let first = arena.insert(B(1)).ok().unwrap();
+		let second = arena.insert(B(2)).ok().unwrap();
+		assert!(arena.insert(B(3)).err().unwrap().0 == 3);
+		assert!(arena.len() == 2);
+
+		assert!(arena.get(first).unwrap().foo() == 1);
+		assert!(arena.get_mut(second).unwrap().foo() == 2);
+		assert!(arena
+			.iter()
+			.map(|(handle, value)| (handle, value.foo()))
+			.eq([(first, 1), (second, 2)]));
+	}
+
+	#[test]
+	fn removed_handles_are_stale() {
+		let drop_was_called = Cell::new(false);
+		let mut arena = Arena::<16, 2>::new();
+		let handle = arena.insert(Droppable(&drop_was_called)).ok().unwrap();
+		assert!(arena.remove(handle));
+		assert!(drop_was_called.get());
+		assert!(!arena.remove(handle));
+
+		let reused = arena.insert(B(5)).ok().unwrap();
+		assert!(reused.index() == handle.index());
+		assert!(arena.get(handle).is_none());
+		assert!(arena.get(reused).unwrap().foo() == 5);
+	}
+
+	#[test]
+	fn drop_is_called_on_drop() {
+		let drop_was_called = Cell::new(false);
+		{
+			let mut arena = Arena::<16, 4>::new();
+			arena.insert(Droppable(&drop_was_called)).ok().unwrap();
+			assert!(!drop_was_called.get());
+		}
+
+		assert!(drop_was_called.get());
+	}
+}
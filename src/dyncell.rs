@@ -0,0 +1,165 @@
+#[allow(unused_macros)]
+#[macro_export]
+/// Generates a cell around a DynBox type generated by [`dynbox!`] with the
+/// `send` option, which can live in a `static` and be shared with interrupt
+/// handlers. Every access runs in a critical section of the
+/// [`CriticalSection`](crate::CriticalSection) implementation given as type
+/// parameter, e.g. `GlobalCriticalSection` with the `critical-section`
+/// feature. The trait must be the one of the DynBox.
+/// Example:
+/// ```
+/// use dyn_box::{dynbox, dynbox_cell, CriticalSection};
+/// trait Handler { fn handle(&mut self) -> u32; }
+/// impl Handler for u32 { fn handle(&mut self) -> u32 { *self += 1; *self } }
+/// dynbox!(HandlerBox: Handler, send);
+/// dynbox_cell!(HandlerCell: HandlerBox, Handler);
+/// # struct Cs;
+/// # static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+/// # unsafe impl CriticalSection for Cs {
+/// #     fn with<R>(f: impl FnOnce() -> R) -> R { let _lock = LOCK.lock(); f() }
+/// # }
+/// static HANDLER: HandlerCell<Cs, 16> = HandlerCell::new();
+/// HANDLER.set(41u32);
+/// assert!(HANDLER.with(|handler| handler.unwrap().handle()) == 42);
+/// ```
+macro_rules! dynbox_cell {
+	($name:ident : $box:ident, $trait:path) => {
+		/// Cell holding a DynBox, which can be shared with interrupt handlers, as
+		/// every access runs in a critical section of `CS`. Generated through the
+		/// `dynbox_cell!` macro
+		pub struct $name<CS, const SIZE: usize> {
+			dynbox: core::cell::UnsafeCell<$box<'static, SIZE>>,
+			borrowed: core::cell::Cell<bool>,
+			_critical_section: core::marker::PhantomData<fn() -> CS>,
+		}
+
+		// Accesses are serialized by the critical section, and the content is
+		// `Send`.
+		unsafe impl<CS: $crate::CriticalSection, const SIZE: usize> Sync for $name<CS, SIZE> where
+			$box<'static, SIZE>: Send
+		{
+		}
+
+		impl<CS: $crate::CriticalSection, const SIZE: usize> Default for $name<CS, SIZE> {
+			fn default() -> Self {
+				Self::new()
+			}
+		}
+
+		impl<CS: $crate::CriticalSection, const SIZE: usize> $name<CS, SIZE> {
+			/// Creates a new cell holding an empty DynBox. Can be evaluated at compile
+			/// time.
+			pub const fn new() -> $name<CS, SIZE> {
+				$name {
+					dynbox: core::cell::UnsafeCell::new($box::new()),
+					borrowed: core::cell::Cell::new(false),
+					_critical_section: core::marker::PhantomData,
+				}
+			}
+
+			/// Calls `f` with the DynBox in a critical section. Panics if called from
+			/// within `f`.
+			pub fn with_box<R>(&self, f: impl FnOnce(&mut $box<'static, SIZE>) -> R) -> R {
+				CS::with(|| {
+					assert!(
+						!self.borrowed.replace(true),
+						"DynBox cell accessed reentrantly"
+					);
+					// Releases the cell even if `f` panics.
+					struct Release<'c>(&'c core::cell::Cell<bool>);
+					impl Drop for Release<'_> {
+						fn drop(&mut self) {
+							self.0.set(false);
+						}
+					}
+					let _release = Release(&self.borrowed);
+					f(unsafe { &mut *self.dynbox.get() })
+				})
+			}
+
+			/// Calls `f` with a `&mut dyn Trait` reference to the content if not empty,
+			/// or None otherwise, in a critical section. Panics if called from within
+			/// `f`.
+			pub fn with<R>(&self, f: impl FnOnce(Option<&mut dyn $trait>) -> R) -> R {
+				self.with_box(|dynbox| f(dynbox.get_mut()))
+			}
+
+			/// Stores `content` in a critical section, like the DynBox's `set()`.
+			#[track_caller]
+			pub fn set<T: $trait + Send + 'static>(&self, content: T) {
+				self.with_box(|dynbox| dynbox.set(content))
+			}
+
+			/// Drops the content, if any, in a critical section.
+			pub fn clear(&self) {
+				self.with_box(|dynbox| dynbox.clear())
+			}
+		}
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	extern crate std;
+
+	use crate::{dynbox, CriticalSection};
+	use core::sync::atomic::{AtomicU32, Ordering};
+
+	pub trait Handler {
+		fn handle(&mut self) -> u32;
+	}
+
+	struct Counter(u32);
+
+	impl Handler for Counter {
+		fn handle(&mut self) -> u32 {
+			self.0 += 1;
+			self.0
+		}
+	}
+
+	struct Lock;
+
+	static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+	unsafe impl CriticalSection for Lock {
+		fn with<R>(f: impl FnOnce() -> R) -> R {
+			let _lock = LOCK.lock();
+			f()
+		}
+	}
+
+	dynbox!(HandlerBox: Handler, send);
+	dynbox_cell!(HandlerCell: HandlerBox, Handler);
+
+	#[test]
+	fn cell_in_static_is_shared_between_threads() {
+		static HANDLER: HandlerCell<Lock, 16> = HandlerCell::new();
+		static TOTAL: AtomicU32 = AtomicU32::new(0);
+		assert!(HANDLER.with(|handler| handler.is_none()));
+
+		HANDLER.set(Counter(0));
+		std::thread::scope(|scope| {
+			for _ in 0..4 {
+				scope.spawn(|| {
+					for _ in 0..100 {
+						let count = HANDLER.with(|handler| handler.unwrap().handle());
+						TOTAL.fetch_max(count, Ordering::Relaxed);
+					}
+				});
+			}
+		});
+		assert!(TOTAL.load(Ordering::Relaxed) == 400);
+
+		HANDLER.clear();
+		assert!(HANDLER.with_box(|dynbox| dynbox.empty()));
+	}
+
+	#[test]
+	#[cfg(feature = "critical-section")]
+	fn cell_uses_critical_section_crate() {
+		static HANDLER: HandlerCell<crate::GlobalCriticalSection, 16> = HandlerCell::new();
+		HANDLER.set(Counter(41));
+		assert!(HANDLER.with(|handler| handler.unwrap().handle()) == 42);
+	}
+}
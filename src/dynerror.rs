@@ -0,0 +1,101 @@
+crate::dynbox!(ErrorBox: core::error::Error);
+
+/// Error of any type implementing `core::error::Error`, stored inline if it
+/// does not exceed `SIZE` bytes or 16 bytes alignment, like a `Box<dyn Error>`
+/// without an allocator. Converts from such errors, so that `?` works:
+/// ```
+/// use dyn_box::{DynError, EmptyError};
+/// fn first(values: &[u32]) -> Result<u32, DynError<8>> {
+///     Ok(*values.first().ok_or(EmptyError)?)
+/// }
+/// assert!(first(&[]).unwrap_err().is::<EmptyError>());
+/// ```
+/// Like `anyhow::Error`, it does not implement `Error` itself, as that would
+/// conflict with converting from every `Error`.
+pub struct DynError<const SIZE: usize>(ErrorBox<'static, SIZE>);
+
+impl<const SIZE: usize> DynError<SIZE> {
+	/// Stores `error`. Fails to compile if it does not fit.
+	pub fn new<E: core::error::Error + 'static>(error: E) -> DynError<SIZE> {
+		let mut dynbox = ErrorBox::new();
+		dynbox.set_checked(error);
+		DynError(dynbox)
+	}
+
+	/// Returns the stored error.
+	pub fn get(&self) -> &(dyn core::error::Error + 'static) {
+		// Only `new()` creates a `DynError`, which always stores an error.
+		unsafe { &*self.0.as_dyn_ptr().unwrap_unchecked() }
+	}
+
+	/// Returns whether the stored error is an `E`.
+	pub fn is<E: core::error::Error + 'static>(&self) -> bool {
+		self.get().is::<E>()
+	}
+
+	/// Returns a reference to the stored error if it is an `E`, or None
+	/// otherwise.
+	pub fn downcast_ref<E: core::error::Error + 'static>(&self) -> Option<&E> {
+		self.get().downcast_ref::<E>()
+	}
+}
+
+impl<E: core::error::Error + 'static, const SIZE: usize> From<E> for DynError<SIZE> {
+	fn from(error: E) -> DynError<SIZE> {
+		DynError::new(error)
+	}
+}
+
+impl<const SIZE: usize> core::fmt::Display for DynError<SIZE> {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		core::fmt::Display::fmt(self.get(), f)
+	}
+}
+
+impl<const SIZE: usize> core::fmt::Debug for DynError<SIZE> {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		core::fmt::Debug::fmt(self.get(), f)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	extern crate std;
+
+	use super::DynError;
+	use crate::EmptyError;
+	use std::format;
+
+	#[derive(Debug)]
+	struct ParseError(u32);
+
+	impl core::fmt::Display for ParseError {
+		fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+			write!(f, "invalid digit at {}", self.0)
+		}
+	}
+
+	impl core::error::Error for ParseError {}
+
+	fn parse(input: &str) -> Result<u32, DynError<16>> {
+		match input.find(|c: char| !c.is_ascii_digit()) {
+			Some(position) => Err(ParseError(position as u32))?,
+			None if input.is_empty() => Err(EmptyError)?,
+			None => Ok(input.parse().unwrap()),
+		}
+	}
+
+	#[test]
+	fn question_mark_converts_errors() {
+		assert!(parse("42").unwrap() == 42);
+
+		let error = parse("4x").unwrap_err();
+		assert!(error.downcast_ref::<ParseError>().unwrap().0 == 1);
+		assert!(format!("{}", error) == "invalid digit at 1");
+		assert!(format!("{:?}", error) == "ParseError(1)");
+
+		let error = parse("").unwrap_err();
+		assert!(error.is::<EmptyError>() && !error.is::<ParseError>());
+		assert!(format!("{}", error.get()) == "container is empty");
+	}
+}
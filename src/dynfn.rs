@@ -0,0 +1,109 @@
+#[allow(unused_macros)]
+#[macro_export]
+/// Generates a DynBox for closures of a given signature, like [`dynbox!`], with
+/// a `call()` method taking the closure's arguments. For `Fn`, `call()` takes
+/// `&self`, for `FnMut` it takes `&mut self`; both return None if the DynBox is
+/// empty. `FnOnce() -> R` boxes get `call_once()` instead, which moves the
+/// closure out. Closures taking up to eight arguments are supported, and
+/// options may follow the signature like for `dynbox!`.
+/// Example:
+/// ```
+/// use dyn_box::dynfn;
+/// struct Context { calls: u32 }
+/// dynfn!(Callback: FnMut(u32, &mut Context) -> bool);
+/// let mut callback = Callback::<16>::new();
+/// let limit = 2;
+/// callback.set(move |value, context: &mut Context| {
+///     context.calls += 1;
+///     value < limit
+/// });
+/// let mut context = Context { calls: 0 };
+/// assert!(callback.call(1, &mut context) == Some(true));
+/// assert!(callback.call(3, &mut context) == Some(false));
+/// assert!(context.calls == 2);
+/// ```
+macro_rules! dynfn {
+	($name:ident : $kind:ident($($arg:ty),* $(,)?) $(, $($options:tt)*)?) => {
+		$crate::dynfn!($name: $kind($($arg),*) -> () $(, $($options)*)?);
+	};
+	($name:ident : FnOnce() -> $ret:ty $(, $($options:tt)*)?) => {
+		$crate::dynbox!($name: FnOnce() -> $ret $(, $($options)*)?);
+	};
+	($name:ident : Fn($($arg:ty),* $(,)?) -> $ret:ty $(, $($options:tt)*)?) => {
+		$crate::dynbox!($name: Fn($($arg),*) -> $ret $(, $($options)*)?);
+		$crate::dynfn!(@call $name, [], get, $ret, [] [$($arg),*] [arg0 arg1 arg2 arg3 arg4 arg5 arg6 arg7]);
+	};
+	($name:ident : FnMut($($arg:ty),* $(,)?) -> $ret:ty $(, $($options:tt)*)?) => {
+		$crate::dynbox!($name: FnMut($($arg),*) -> $ret $(, $($options)*)?);
+		$crate::dynfn!(@call $name, [mut], get_mut, $ret, [] [$($arg),*] [arg0 arg1 arg2 arg3 arg4 arg5 arg6 arg7]);
+	};
+	// Pairs each argument type with a name.
+	(
+		@call $name:ident, $mut:tt, $get:ident, $ret:ty, [$($done:tt)*] [$type:ty $(, $rest:ty)*]
+		[$arg:ident $($args:ident)*]
+	) => {
+		$crate::dynfn!(@call $name, $mut, $get, $ret, [$($done)* ($arg: $type)] [$($rest),*] [$($args)*]);
+	};
+	(@call $name:ident, [$($mut:tt)?], $get:ident, $ret:ty, [$(($arg:ident : $type:ty))*] [] [$($unused:ident)*]) => {
+		impl<'a, const SIZE: usize, const ALIGN: usize> $name<'a, SIZE, ALIGN>
+		where
+			$crate::Align<ALIGN>: $crate::Alignment,
+		{
+			/// Calls the closure with the given arguments, or returns None if the
+			/// DynBox is empty.
+			pub fn call(&$($mut)? self, $($arg: $type),*) -> Option<$ret> {
+				self.$get().map(|closure| closure($($arg),*))
+			}
+		}
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	use core::cell::Cell;
+
+	dynfn!(Callback: FnMut(u32, &mut u32) -> bool);
+	dynfn!(Getter: Fn() -> u32);
+	dynfn!(Notifier: Fn(&Cell<u32>), default_size = 16);
+	dynfn!(Once: FnOnce() -> u32);
+
+	#[test]
+	fn fn_mut_box_calls_closure_with_arguments() {
+		let mut callback = Callback::<16>::new();
+		assert!(callback.call(1, &mut 0).is_none());
+
+		let mut calls = 0;
+		callback.set(move |value, total: &mut u32| {
+			calls += 1;
+			*total += value * calls;
+			calls < 2
+		});
+		let mut total = 0;
+		assert!(callback.call(1, &mut total) == Some(true));
+		assert!(callback.call(1, &mut total) == Some(false));
+		assert!(total == 3);
+	}
+
+	#[test]
+	fn fn_box_calls_closure_through_shared_reference() {
+		let mut getter = Getter::<16>::new();
+		let value = 42u32;
+		getter.set(move || value);
+		let getter = &getter;
+		assert!(getter.call() == Some(42) && getter.call() == Some(42));
+
+		let mut notifier = Notifier::new_default_size();
+		notifier.set(|count: &Cell<u32>| count.set(count.get() + 1));
+		let count = Cell::new(0);
+		assert!(notifier.call(&count) == Some(()));
+		assert!(count.get() == 1);
+	}
+
+	#[test]
+	fn fn_once_box_consumes_closure() {
+		let mut once = Once::<16>::new();
+		once.set(|| 7);
+		assert!(once.call_once() == Some(7));
+		assert!(once.call_once().is_none());
+	}
+}
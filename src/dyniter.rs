@@ -0,0 +1,66 @@
+#[allow(unused_macros)]
+#[macro_export]
+/// Generates a DynBox for iterators yielding a given item type, like
+/// [`dynbox!`], which is an iterator itself: it forwards `next()` and
+/// `size_hint()` to the content, and yields nothing while empty. Options may
+/// follow the item type like for `dynbox!`.
+/// Example:
+/// ```
+/// use dyn_box::dyniter;
+/// dyniter!(Numbers: u32);
+/// fn numbers(odd: bool) -> Numbers<'static, 32> {
+///     let mut numbers = Numbers::new();
+///     if odd {
+///         numbers.set((0..10).filter(|n| n % 2 == 1));
+///     } else {
+///         numbers.set([0, 2, 4].into_iter());
+///     }
+///     numbers
+/// }
+/// assert!(numbers(true).sum::<u32>() == 25);
+/// assert!(numbers(false).size_hint() == (3, Some(3)));
+/// ```
+macro_rules! dyniter {
+	($name:ident : $item:ty $(, $($options:tt)*)?) => {
+		$crate::dynbox!($name: Iterator<Item = $item> $(, $($options)*)?);
+
+		impl<'a, const SIZE: usize, const ALIGN: usize> Iterator for $name<'a, SIZE, ALIGN>
+		where
+			$crate::Align<ALIGN>: $crate::Alignment,
+		{
+			type Item = $item;
+
+			fn next(&mut self) -> Option<$item> {
+				self.get_mut()?.next()
+			}
+
+			fn size_hint(&self) -> (usize, Option<usize>) {
+				self.get().map_or((0, Some(0)), |iter| iter.size_hint())
+			}
+		}
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	dyniter!(Numbers: u32);
+	dyniter!(Words: &'static str, default_size = 128);
+
+	#[test]
+	fn box_iterates_over_content() {
+		let mut numbers = Numbers::<32>::new();
+		assert!(numbers.next().is_none() && numbers.size_hint() == (0, Some(0)));
+
+		numbers.set([1, 2, 3].into_iter());
+		assert!(numbers.size_hint() == (3, Some(3)));
+		assert!(numbers.next() == Some(1));
+		assert!(numbers.sum::<u32>() == 5);
+	}
+
+	#[test]
+	fn options_follow_item_type() {
+		let mut words = Words::new_default_size();
+		words.set("a b c".split(' '));
+		assert!(words.eq(["a", "b", "c"]));
+	}
+}
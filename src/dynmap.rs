@@ -0,0 +1,261 @@
+#[allow(unused_macros)]
+#[macro_export]
+/// Generates a map from the variants of a field-less `Copy` enum to values
+/// which implement a certain trait, with one inline slot per variant. All
+/// variants of the enum have to be listed, in the order `iter()` visits them.
+/// Example:
+/// ```
+/// use dyn_box::dynmap;
+/// trait Driver { fn id(&self) -> u32; }
+/// impl Driver for u32 { fn id(&self) -> u32 { *self } }
+/// #[derive(Clone, Copy, PartialEq, Debug)]
+/// enum Port { Uart0, Uart1, Spi }
+/// dynmap!(Drivers: Driver, Port { Uart0, Uart1, Spi });
+/// let mut drivers = Drivers::<16>::new();
+/// drivers.set(Port::Spi, 3u32);
+/// assert!(drivers.get(Port::Spi).unwrap().id() == 3);
+/// assert!(drivers.get(Port::Uart0).is_none());
+/// ```
+macro_rules! dynmap {
+	($name:ident : $trait:path, $key:ident { $($variant:ident),+ $(,)? }) => {
+		$crate::dynmap!(@index $name, $trait, $key, [$($variant),+], [], 0, $($variant)+);
+	};
+	// Numbers the variants.
+	(
+		@index $name:ident, $trait:path, $key:ident, $variants:tt, [$($arms:tt)*], $index:expr,
+		$variant:ident $($rest:ident)*
+	) => {
+		$crate::dynmap!(
+			@index $name, $trait, $key, $variants, [$($arms)* $key::$variant => $index,], $index + 1,
+			$($rest)*
+		);
+	};
+	(@index $name:ident, $trait:path, $key:ident, [$($variant:ident),+], [$($arms:tt)*], $slots:expr,) => {
+		/// Map from the variants of a key enum to implementors of a given Trait,
+		/// each of which must not exceed `SIZE` bytes or 16 bytes alignment.
+		/// Generated through the `dynmap!` macro
+		pub struct $name<'a, const SIZE: usize> {
+			store: [$crate::Storage<SIZE>; $slots],
+			vtables: [Option<core::ptr::NonNull<()>>; $slots],
+			// Neither `Send` nor `Sync`, as the content may not be.
+			_content: core::marker::PhantomData<(&'a (), *const ())>,
+		}
+
+		// The vtables are obtained by transmuting `*const dyn $trait` to two words.
+		const _: () = assert!(
+			core::mem::size_of::<*const dyn $trait>() == 2 * core::mem::size_of::<usize>(),
+			"trait object pointers are not a data pointer and a vtable pointer"
+		);
+
+		impl<const SIZE: usize> Drop for $name<'_, SIZE> {
+			fn drop(&mut self) {
+				$(self.clear($key::$variant);)+
+			}
+		}
+
+		impl<const SIZE: usize> Default for $name<'_, SIZE> {
+			fn default() -> Self {
+				Self::new()
+			}
+		}
+
+		impl<'a, const SIZE: usize> $name<'a, SIZE> {
+			/// Creates a new map with all slots empty.
+			pub const fn new() -> $name<'a, SIZE> {
+				$name {
+					store: [$crate::Storage::new(); $slots],
+					vtables: [None; $slots],
+					_content: core::marker::PhantomData,
+				}
+			}
+
+			/// Returns the slot of `key`.
+			fn slot(key: $key) -> usize {
+				match key {
+					$($arms)*
+				}
+			}
+
+			/// Returns the number of occupied slots.
+			pub fn len(&self) -> usize {
+				self.vtables
+					.iter()
+					.filter(|vtable| vtable.is_some())
+					.count()
+			}
+
+			/// Returns whether all slots are empty.
+			pub fn is_empty(&self) -> bool {
+				self.len() == 0
+			}
+
+			/// Stores a value of some generic type which implements $trait under
+			/// `key`. Panics if it does not fit into a slot. Clears (and drops) the
+			/// previous value of that key, if present.
+			#[track_caller]
+			pub fn set<T: $trait + 'a>(&mut self, key: $key, content: T) {
+				if let Err(error) = self.try_set(key, content) {
+					panic!("{}", error);
+				}
+			}
+
+			/// Stores a value like `set()`, but returns an error giving it back instead
+			/// of panicking if it does not fit, leaving the map unchanged.
+			pub fn try_set<T: $trait + 'a>(
+				&mut self,
+				key: $key,
+				content: T,
+			) -> Result<(), $crate::SetError<T>> {
+				let align = core::mem::align_of::<$crate::Storage<SIZE>>();
+				if core::mem::size_of::<T>() > SIZE || core::mem::align_of::<T>() > align {
+					return Err($crate::SetError::new(content, SIZE, align));
+				}
+
+				self.clear(key);
+				let slot = Self::slot(key);
+				let parts = unsafe {
+					core::mem::transmute::<*const dyn $trait, $crate::DynParts>(
+						&content as *const dyn $trait,
+					)
+				};
+				unsafe { (self.store[slot].as_mut_ptr() as *mut T).write(content) }
+				self.vtables[slot] = Some(parts.vtable);
+				Ok(())
+			}
+
+			/// Returns a `&dyn Trait` reference to the value of `key`, or None if
+			/// there is none.
+			pub fn get(&self, key: $key) -> Option<&dyn $trait> {
+				let slot = Self::slot(key);
+				if self.vtables[slot].is_some() {
+					Some(unsafe { &*self.get_ptr(slot) })
+				} else {
+					None
+				}
+			}
+
+			/// Returns a `&mut dyn Trait` reference to the value of `key`, or None if
+			/// there is none.
+			pub fn get_mut(&mut self, key: $key) -> Option<&mut dyn $trait> {
+				let slot = Self::slot(key);
+				if self.vtables[slot].is_some() {
+					Some(unsafe { &mut *self.get_ptr_mut(slot) })
+				} else {
+					None
+				}
+			}
+
+			/// Drops the value of `key`, if any.
+			pub fn clear(&mut self, key: $key) {
+				let slot = Self::slot(key);
+				if self.vtables[slot].is_some() {
+					let content = unsafe { self.get_ptr_mut(slot) };
+					// Free the slot first, so a panicking destructor cannot cause a double
+					// drop.
+					self.vtables[slot] = None;
+					unsafe { core::ptr::drop_in_place(content) }
+				}
+			}
+
+			/// Returns an iterator over the keys and values of the occupied slots, in
+			/// the order the variants were listed.
+			pub fn iter(&self) -> impl Iterator<Item = ($key, &dyn $trait)> {
+				[$($key::$variant),+]
+					.into_iter()
+					.filter_map(move |key| self.get(key).map(|content| (key, content)))
+			}
+
+			/// Returns a pointer to the content of `slot`, which is only valid for
+			/// reads.
+			unsafe fn get_ptr(&self, slot: usize) -> *const (dyn $trait + 'a) {
+				let data = self.store[slot].as_ptr() as *mut u8;
+				core::mem::transmute::<$crate::DynParts, *mut (dyn $trait + 'a)>($crate::DynParts {
+					data,
+					vtable: self.vtables[slot].unwrap_unchecked(),
+				})
+			}
+
+			/// Returns a pointer to the content of `slot`, which is also valid for
+			/// writes.
+			unsafe fn get_ptr_mut(&mut self, slot: usize) -> *mut (dyn $trait + 'a) {
+				let data = self.store[slot].as_mut_ptr();
+				core::mem::transmute::<$crate::DynParts, *mut (dyn $trait + 'a)>($crate::DynParts {
+					data,
+					vtable: self.vtables[slot].unwrap_unchecked(),
+				})
+			}
+		}
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	use core::cell::Cell;
+
+	pub trait Driver {
+		fn id(&self) -> u32;
+	}
+
+	struct B(u128);
+	struct Droppable<'a>(&'a Cell<bool>);
+
+	impl Driver for B {
+		fn id(&self) -> u32 {
+			self.0 as u32
+		}
+	}
+	impl Driver for Droppable<'_> {
+		fn id(&self) -> u32 {
+			2
+		}
+	}
+	impl Drop for Droppable<'_> {
+		fn drop(&mut self) {
+			self.0.set(true);
+		}
+	}
+
+	#[derive(Clone, Copy, PartialEq, Debug)]
+	pub enum Port {
+		Uart0 = 3,
+		Uart1 = 1,
+		Spi,
+	}
+
+	dynmap!(Drivers: Driver, Port { Uart0, Uart1, Spi });
+
+	#[test]
+	fn values_are_stored_per_key() {
+		let mut drivers = Drivers::<16>::new();
+		assert!(drivers.is_empty() && drivers.get(Port::Uart0).is_none());
+		drivers.set(Port::Spi, B(3));
+		drivers.set(Port::Uart0, B(1));
+		drivers.get_mut(Port::Uart0).unwrap();
+		assert!(drivers.len() == 2);
+		assert!(drivers
+			.iter()
+			.map(|(key, driver)| (key, driver.id()))
+			.eq([(Port::Uart0, 1), (Port::Spi, 3)]));
+
+		let mut small = Drivers::<8>::new();
+		assert!(small.try_set(Port::Uart1, B(1)).err().unwrap().size() == 16);
+		assert!(small.get(Port::Uart1).is_none());
+	}
+
+	#[test]
+	fn drop_is_called_on_set_clear_and_drop() {
+		let drop_was_called = Cell::new(false);
+		let mut drivers = Drivers::<16>::default();
+		drivers.set(Port::Uart1, Droppable(&drop_was_called));
+		drivers.set(Port::Uart1, B(1));
+		assert!(drop_was_called.replace(false));
+
+		drivers.set(Port::Spi, Droppable(&drop_was_called));
+		drivers.clear(Port::Spi);
+		assert!(drop_was_called.replace(false));
+
+		drivers.set(Port::Spi, Droppable(&drop_was_called));
+		drop(drivers);
+		assert!(drop_was_called.get());
+	}
+}
@@ -0,0 +1,247 @@
+#[allow(unused_macros)]
+#[macro_export]
+/// Generates a container like [`dynbox!`] whose content can be viewed through
+/// two traits, recording the vtable of each when it is stored. `get_a()` and
+/// `get_b()` return the content as the first and second trait object,
+/// respectively.
+/// Example:
+/// ```
+/// use dyn_box::dynbox_multi;
+/// trait Driver { fn read(&mut self) -> u8; }
+/// trait Diagnostics { fn errors(&self) -> u32; }
+/// struct Uart(u8);
+/// impl Driver for Uart { fn read(&mut self) -> u8 { self.0 } }
+/// impl Diagnostics for Uart { fn errors(&self) -> u32 { 0 } }
+/// dynbox_multi!(DriverBox: Driver, Diagnostics);
+/// let mut driver = DriverBox::<16>::new();
+/// driver.set(Uart(7));
+/// assert!(driver.get_a_mut().unwrap().read() == 7);
+/// assert!(driver.get_b().unwrap().errors() == 0);
+/// ```
+macro_rules! dynbox_multi {
+	($name:ident : $trait_a:path, $trait_b:path) => {
+		/// Container for two given Traits, storing a value implementing both if it
+		/// does not exceed `SIZE` bytes or 16 bytes alignment. Generated through the
+		/// `dynbox_multi!` macro
+		pub struct $name<'a, const SIZE: usize> {
+			store: $crate::Storage<SIZE>,
+			vtables: Option<(core::ptr::NonNull<()>, core::ptr::NonNull<()>)>,
+			// Neither `Send` nor `Sync`, as the content may not be.
+			_content: core::marker::PhantomData<(&'a (), *const ())>,
+		}
+
+		// The vtables are obtained by transmuting trait object pointers to two words.
+		const _: () = assert!(
+			core::mem::size_of::<*const dyn $trait_a>() == 2 * core::mem::size_of::<usize>()
+				&& core::mem::size_of::<*const dyn $trait_b>() == 2 * core::mem::size_of::<usize>(),
+			"trait object pointers are not a data pointer and a vtable pointer"
+		);
+
+		impl<const SIZE: usize> Drop for $name<'_, SIZE> {
+			fn drop(&mut self) {
+				self.clear();
+			}
+		}
+
+		impl<const SIZE: usize> Default for $name<'_, SIZE> {
+			fn default() -> Self {
+				Self::new()
+			}
+		}
+
+		impl<'a, const SIZE: usize> $name<'a, SIZE> {
+			/// Creates a new empty container. Can be evaluated at compile time.
+			pub const fn new() -> $name<'a, SIZE> {
+				$name {
+					store: $crate::Storage::new(),
+					vtables: None,
+					_content: core::marker::PhantomData,
+				}
+			}
+
+			/// Returns whether a `T` fits into this container's size and alignment.
+			pub const fn can_hold<T>() -> bool {
+				core::mem::size_of::<T>() <= SIZE
+					&& core::mem::align_of::<T>() <= core::mem::align_of::<$crate::Storage<SIZE>>()
+			}
+
+			/// Stores a value of some generic type which implements both traits. Panics
+			/// if it does not fit. Clears (and drops) the previous value, if present.
+			#[track_caller]
+			pub fn set<T: $trait_a + $trait_b + 'a>(&mut self, content: T) {
+				if let Err(error) = self.try_set(content) {
+					panic!("{}", error);
+				}
+			}
+
+			/// Stores a value like `set()`, but returns an error giving it back instead
+			/// of panicking if it does not fit, leaving the container unchanged.
+			pub fn try_set<T: $trait_a + $trait_b + 'a>(
+				&mut self,
+				content: T,
+			) -> Result<(), $crate::SetError<T>> {
+				if !Self::can_hold::<T>() {
+					return Err($crate::SetError::new(
+						content,
+						SIZE,
+						core::mem::align_of::<$crate::Storage<SIZE>>(),
+					));
+				}
+
+				self.clear();
+				let (a, b) = unsafe {
+					(
+						core::mem::transmute::<*const dyn $trait_a, $crate::DynParts>(
+							&content as *const dyn $trait_a,
+						),
+						core::mem::transmute::<*const dyn $trait_b, $crate::DynParts>(
+							&content as *const dyn $trait_b,
+						),
+					)
+				};
+				unsafe { (self.store.as_mut_ptr() as *mut T).write(content) }
+				self.vtables = Some((a.vtable, b.vtable));
+				Ok(())
+			}
+
+			/// Makes the container empty again by dropping the previous content, if
+			/// any.
+			pub fn clear(&mut self) {
+				if let Some(content) = self.get_a_mut() {
+					let content = content as *mut (dyn $trait_a + '_);
+					// Mark as empty first, so a panicking destructor cannot cause a double
+					// drop.
+					self.vtables = None;
+					unsafe { core::ptr::drop_in_place(content) }
+				}
+			}
+
+			/// Returns whether the container currently contains any value.
+			pub fn empty(&self) -> bool {
+				self.vtables.is_none()
+			}
+
+			/// Returns the content as a `&dyn TraitA` reference if not empty, or None
+			/// otherwise.
+			pub fn get_a(&self) -> Option<&(dyn $trait_a + 'a)> {
+				let (vtable, _) = self.vtables?;
+				let data = self.store.as_ptr() as *mut u8;
+				Some(unsafe {
+					&*core::mem::transmute::<$crate::DynParts, *mut (dyn $trait_a + 'a)>(
+						$crate::DynParts { data, vtable },
+					)
+				})
+			}
+
+			/// Returns the content as a `&mut dyn TraitA` reference if not empty, or
+			/// None otherwise.
+			pub fn get_a_mut(&mut self) -> Option<&mut (dyn $trait_a + 'a)> {
+				let (vtable, _) = self.vtables?;
+				let data = self.store.as_mut_ptr();
+				Some(unsafe {
+					&mut *core::mem::transmute::<$crate::DynParts, *mut (dyn $trait_a + 'a)>(
+						$crate::DynParts { data, vtable },
+					)
+				})
+			}
+
+			/// Returns the content as a `&dyn TraitB` reference if not empty, or None
+			/// otherwise.
+			pub fn get_b(&self) -> Option<&(dyn $trait_b + 'a)> {
+				let (_, vtable) = self.vtables?;
+				let data = self.store.as_ptr() as *mut u8;
+				Some(unsafe {
+					&*core::mem::transmute::<$crate::DynParts, *mut (dyn $trait_b + 'a)>(
+						$crate::DynParts { data, vtable },
+					)
+				})
+			}
+
+			/// Returns the content as a `&mut dyn TraitB` reference if not empty, or
+			/// None otherwise.
+			pub fn get_b_mut(&mut self) -> Option<&mut (dyn $trait_b + 'a)> {
+				let (_, vtable) = self.vtables?;
+				let data = self.store.as_mut_ptr();
+				Some(unsafe {
+					&mut *core::mem::transmute::<$crate::DynParts, *mut (dyn $trait_b + 'a)>(
+						$crate::DynParts { data, vtable },
+					)
+				})
+			}
+		}
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	use core::cell::Cell;
+
+	pub trait Driver {
+		fn read(&mut self) -> u32;
+	}
+
+	pub trait Diagnostics {
+		fn errors(&self) -> u32;
+	}
+
+	struct Uart(u32, u32);
+	struct Droppable<'a>(&'a Cell<bool>);
+
+	impl Driver for Uart {
+		fn read(&mut self) -> u32 {
+			self.1 += 1;
+			self.0
+		}
+	}
+	impl Diagnostics for Uart {
+		fn errors(&self) -> u32 {
+			self.1
+		}
+	}
+	impl Driver for Droppable<'_> {
+		fn read(&mut self) -> u32 {
+			0
+		}
+	}
+	impl Diagnostics for Droppable<'_> {
+		fn errors(&self) -> u32 {
+			0
+		}
+	}
+	impl Drop for Droppable<'_> {
+		fn drop(&mut self) {
+			self.0.set(true);
+		}
+	}
+
+	dynbox_multi!(DriverBox: Driver, Diagnostics);
+
+	#[test]
+	fn content_is_viewed_through_both_traits() {
+		let mut dynbox = DriverBox::<16>::new();
+		assert!(dynbox.empty() && dynbox.get_a().is_none() && dynbox.get_b_mut().is_none());
+
+		dynbox.set(Uart(42, 0));
+		assert!(dynbox.get_a_mut().unwrap().read() == 42);
+		assert!(dynbox.get_b().unwrap().errors() == 1);
+		assert!(
+			dynbox.get_a().unwrap() as *const dyn Driver as *const u8
+				== dynbox.get_b().unwrap() as *const dyn Diagnostics as *const u8
+		);
+		assert!(DriverBox::<4>::new().try_set(Uart(1, 0)).is_err());
+	}
+
+	#[test]
+	fn drop_is_called_on_clear_and_drop() {
+		let drop_was_called = Cell::new(false);
+		let mut dynbox = DriverBox::<16>::new();
+		dynbox.set(Droppable(&drop_was_called));
+		dynbox.clear();
+		assert!(drop_was_called.get() && dynbox.empty());
+
+		drop_was_called.set(false);
+		dynbox.set(Droppable(&drop_was_called));
+		drop(dynbox);
+		assert!(drop_was_called.get());
+	}
+}
@@ -0,0 +1,295 @@
+#[allow(unused_macros)]
+#[macro_export]
+/// Generates a fixed-capacity single-producer single-consumer queue of values
+/// implementing a certain trait, which may be of different types. Pushing and
+/// popping are lock-free and only need atomic loads and stores, so the two ends
+/// can be used from an interrupt handler and the main loop, respectively. The
+/// trait must be at least as visible as the queue.
+/// Example:
+/// ```
+/// use dyn_box::dynqueue;
+/// pub trait Event { fn code(&self) -> u32; }
+/// impl Event for u8 { fn code(&self) -> u32 { *self as u32 } }
+/// dynqueue!(EventQueue: Event);
+/// let mut queue = EventQueue::<16, 4>::new();
+/// let (mut producer, mut consumer) = queue.split();
+/// producer.push(7u8).unwrap();
+/// assert!(consumer.pop_with(|event| event.code()) == Some(7));
+/// producer.push(8u8).unwrap();
+/// let event = consumer.pop().unwrap();
+/// assert!(event.code() == 8);
+/// ```
+/// The queue is invariant in the lifetime of its values, so that a producer for
+/// long-lived values cannot push ones borrowing shorter-lived data:
+/// ```compile_fail
+/// use dyn_box::{dynqueue, Producer};
+/// pub trait Event {}
+/// dynqueue!(EventQueue: Event);
+/// fn shorten<'q, 's>(
+///     producer: Producer<'q, EventQueue<'static, 16, 4>>,
+/// ) -> Producer<'q, EventQueue<'s, 16, 4>> {
+///     producer
+/// }
+/// ```
+macro_rules! dynqueue {
+	($name:ident : $trait:path) => {
+		/// Single-producer single-consumer queue for a given Trait, holding up to `N`
+		/// `Send` implementors of the trait which do not exceed `SLOT_SIZE` bytes or
+		/// 16 bytes alignment. Values are pushed and popped through the `Producer`
+		/// and `Consumer` returned by `split()`. Generated through the `dynqueue!`
+		/// macro
+		pub struct $name<'a, const SLOT_SIZE: usize, const N: usize> {
+			slots: [core::cell::UnsafeCell<$crate::Storage<SLOT_SIZE>>; N],
+			vtables: [core::cell::UnsafeCell<core::ptr::NonNull<()>>; N],
+			// Positions count modulo `2 * N`, so that a full queue can be told apart
+			// from an empty one.
+			head: core::sync::atomic::AtomicUsize,
+			tail: core::sync::atomic::AtomicUsize,
+			// Invariant, as values are pushed through a shared reference.
+			_content: core::marker::PhantomData<fn(&'a ()) -> &'a ()>,
+		}
+
+		// The vtables are obtained by transmuting `*const dyn $trait` to two words.
+		const _: () = assert!(
+			core::mem::size_of::<*const dyn $trait>() == 2 * core::mem::size_of::<usize>(),
+			"trait object pointers are not a data pointer and a vtable pointer"
+		);
+
+		// The producer only writes slots the consumer is done with and vice versa,
+		// and all values are `Send`.
+		unsafe impl<const SLOT_SIZE: usize, const N: usize> Send for $name<'_, SLOT_SIZE, N> {}
+		unsafe impl<const SLOT_SIZE: usize, const N: usize> Sync for $name<'_, SLOT_SIZE, N> {}
+
+		impl<const SLOT_SIZE: usize, const N: usize> Drop for $name<'_, SLOT_SIZE, N> {
+			fn drop(&mut self) {
+				while unsafe { $crate::QueuePop::front(self) }.is_some() {
+					unsafe { $crate::QueuePop::release_front(self) }
+				}
+			}
+		}
+
+		impl<const SLOT_SIZE: usize, const N: usize> Default for $name<'_, SLOT_SIZE, N> {
+			fn default() -> Self {
+				Self::new()
+			}
+		}
+
+		impl<'a, const SLOT_SIZE: usize, const N: usize> $name<'a, SLOT_SIZE, N> {
+			/// Creates a new empty queue.
+			pub const fn new() -> $name<'a, SLOT_SIZE, N> {
+				const { assert!(N > 0, "DynQueue needs at least one slot") }
+				$name {
+					slots: [const { core::cell::UnsafeCell::new($crate::Storage::new()) }; N],
+					vtables: [const { core::cell::UnsafeCell::new(core::ptr::NonNull::dangling()) };
+						N],
+					head: core::sync::atomic::AtomicUsize::new(0),
+					tail: core::sync::atomic::AtomicUsize::new(0),
+					_content: core::marker::PhantomData,
+				}
+			}
+
+			/// Returns the number of queued values. If the other end is in use
+			/// concurrently, this may be outdated already.
+			pub fn len(&self) -> usize {
+				let head = self.head.load(core::sync::atomic::Ordering::Acquire);
+				let tail = self.tail.load(core::sync::atomic::Ordering::Acquire);
+				(head + 2 * N - tail) % (2 * N)
+			}
+
+			/// Returns whether no values are queued. If the other end is in use
+			/// concurrently, this may be outdated already.
+			pub fn is_empty(&self) -> bool {
+				self.len() == 0
+			}
+
+			/// Returns the producing and the consuming end of the queue, which may be
+			/// moved to different contexts.
+			pub fn split(&mut self) -> ($crate::Producer<'_, Self>, $crate::Consumer<'_, Self>) {
+				// The exclusive borrow ensures these are the only ends.
+				unsafe { ($crate::Producer::new(self), $crate::Consumer::new(self)) }
+			}
+		}
+
+		impl<'a, T: $trait + Send + 'a, const SLOT_SIZE: usize, const N: usize> $crate::QueuePush<T>
+			for $name<'a, SLOT_SIZE, N>
+		{
+			unsafe fn push(&self, value: T) -> Result<(), T> {
+				let head = self.head.load(core::sync::atomic::Ordering::Relaxed);
+				let tail = self.tail.load(core::sync::atomic::Ordering::Acquire);
+				if (head + 2 * N - tail) % (2 * N) == N
+					|| core::mem::size_of::<T>() > SLOT_SIZE
+					|| core::mem::align_of::<T>()
+						> core::mem::align_of::<$crate::Storage<SLOT_SIZE>>()
+				{
+					return Err(value);
+				}
+
+				let parts = core::mem::transmute::<*const dyn $trait, $crate::DynParts>(
+					&value as *const dyn $trait,
+				);
+				let slot = head % N;
+				((*self.slots[slot].get()).as_mut_ptr() as *mut T).write(value);
+				*self.vtables[slot].get() = parts.vtable;
+				self.head
+					.store((head + 1) % (2 * N), core::sync::atomic::Ordering::Release);
+				Ok(())
+			}
+		}
+
+		impl<'a, const SLOT_SIZE: usize, const N: usize> $crate::QueuePop
+			for $name<'a, SLOT_SIZE, N>
+		{
+			type Content = dyn $trait + 'a;
+
+			unsafe fn front(&self) -> Option<*mut Self::Content> {
+				let tail = self.tail.load(core::sync::atomic::Ordering::Relaxed);
+				let head = self.head.load(core::sync::atomic::Ordering::Acquire);
+				if head == tail {
+					return None;
+				}
+				Some(self.slot_ptr(tail % N))
+			}
+
+			unsafe fn release_front(&self) {
+				let tail = self.tail.load(core::sync::atomic::Ordering::Relaxed);
+				let content = self.slot_ptr(tail % N);
+
+				// Releases the slot even if the destructor panics, so that the value is
+				// not dropped twice.
+				struct Release<'t>(&'t core::sync::atomic::AtomicUsize, usize);
+				impl Drop for Release<'_> {
+					fn drop(&mut self) {
+						self.0.store(self.1, core::sync::atomic::Ordering::Release);
+					}
+				}
+				let _release = Release(&self.tail, (tail + 1) % (2 * N));
+				core::ptr::drop_in_place(content);
+			}
+		}
+
+		impl<'a, const SLOT_SIZE: usize, const N: usize> $name<'a, SLOT_SIZE, N> {
+			/// Returns a pointer to the value in `slot`, which must be occupied.
+			unsafe fn slot_ptr(&self, slot: usize) -> *mut (dyn $trait + 'a) {
+				core::mem::transmute::<$crate::DynParts, *mut (dyn $trait + 'a)>($crate::DynParts {
+					data: (*self.slots[slot].get()).as_mut_ptr(),
+					vtable: *self.vtables[slot].get(),
+				})
+			}
+		}
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	extern crate std;
+
+	use core::sync::atomic::{AtomicU32, Ordering};
+
+	pub trait Event {
+		fn code(&self) -> u32;
+	}
+
+	struct B(u128);
+	struct Counted<'a>(u32, &'a AtomicU32);
+
+	impl Event for B {
+		fn code(&self) -> u32 {
+			self.0 as u32
+		}
+	}
+	impl Event for u8 {
+		fn code(&self) -> u32 {
+			*self as u32
+		}
+	}
+	impl Event for Counted<'_> {
+		fn code(&self) -> u32 {
+			self.0
+		}
+	}
+	impl Drop for Counted<'_> {
+		fn drop(&mut self) {
+			self.1.fetch_add(1, Ordering::Relaxed);
+		}
+	}
+
+	dynqueue!(Queue: Event);
+
+	#[test]
+	fn values_are_popped_in_order() {
+		let mut queue = Queue::<16, 2>::new();
+		let (mut producer, mut consumer) = queue.split();
+		assert!(consumer.pop_with(|event| event.code()).is_none());
+		for round in 0..3 {
+			producer.push(round as u8).unwrap();
+			producer.push(B(round + 10)).ok().unwrap();
+			assert!(consumer.pop_with(|event| event.code()) == Some(round as u32));
+			assert!(consumer.pop_with(|event| event.code()) == Some(round as u32 + 10));
+		}
+		assert!(queue.is_empty());
+	}
+
+	#[test]
+	fn push_gives_back_value_without_room() {
+		let mut queue = Queue::<8, 2>::new();
+		let (mut producer, _) = queue.split();
+		assert!(producer.push(B(1)).err().unwrap().0 == 1);
+		producer.push(1u8).unwrap();
+		producer.push(2u8).unwrap();
+		assert!(producer.push(3u8).err() == Some(3));
+		assert!(queue.len() == 2);
+	}
+
+	#[test]
+	fn values_are_dropped_when_popped_and_on_drop() {
+		let counter = AtomicU32::new(0);
+		{
+			let mut queue = Queue::<32, 4>::new();
+			let (mut producer, mut consumer) = queue.split();
+			producer.push(Counted(1, &counter)).ok().unwrap();
+			producer.push(Counted(2, &counter)).ok().unwrap();
+			assert!(consumer.pop_with(|event| event.code()) == Some(1));
+			assert!(counter.load(Ordering::Relaxed) == 1);
+		}
+		assert!(counter.load(Ordering::Relaxed) == 2);
+	}
+
+	#[test]
+	fn popped_value_is_dropped_with_guard() {
+		let counter = AtomicU32::new(0);
+		let mut queue = Queue::<32, 2>::new();
+		let (mut producer, mut consumer) = queue.split();
+		producer.push(Counted(1, &counter)).ok().unwrap();
+		producer.push(Counted(2, &counter)).ok().unwrap();
+
+		let first = consumer.pop().unwrap();
+		assert!(first.code() == 1 && counter.load(Ordering::Relaxed) == 0);
+		drop(first);
+		assert!(counter.load(Ordering::Relaxed) == 1);
+		assert!(consumer.pop().unwrap().code() == 2);
+		assert!(consumer.pop().is_none() && counter.load(Ordering::Relaxed) == 2);
+	}
+
+	#[test]
+	fn ends_work_from_different_threads() {
+		let mut queue = Queue::<16, 4>::new();
+		let (mut producer, mut consumer) = queue.split();
+		std::thread::scope(|scope| {
+			scope.spawn(move || {
+				for code in 0..1000 {
+					let mut event = B(code);
+					while let Err(rejected) = producer.push(event) {
+						event = rejected;
+					}
+				}
+			});
+			let mut expected = 0;
+			while expected < 1000 {
+				if let Some(code) = consumer.pop_with(|event| event.code()) {
+					assert!(code == expected);
+					expected += 1;
+				}
+			}
+		});
+	}
+}
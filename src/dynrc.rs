@@ -0,0 +1,273 @@
+#[allow(unused_macros)]
+#[macro_export]
+/// Generates a pool of inline slots holding values which implement a certain
+/// trait, and a reference-counted handle type for them, like `Rc<dyn Trait>`
+/// without an allocator. Handles are `Clone`, dereference to `&dyn Trait`, and
+/// drop the value once the last handle to it is dropped. The trait must be at
+/// least as visible as the handle type.
+/// Example:
+/// ```
+/// use dyn_box::dynrc;
+/// pub trait Config { fn rate(&self) -> u32; }
+/// impl Config for u32 { fn rate(&self) -> u32 { *self } }
+/// dynrc!(ConfigRc, ConfigPool: Config);
+/// let pool = ConfigPool::<16, 4>::new();
+/// let config = pool.insert(9600u32).ok().unwrap();
+/// let shared = config.clone();
+/// assert!(shared.rate() == 9600 && ConfigRc::strong_count(&config) == 2);
+/// ```
+/// The pool is invariant in the lifetime of its values, so that a pool for
+/// long-lived values cannot be shortened to accept ones borrowing shorter-lived
+/// data, which it might drop after that is gone if a handle was leaked:
+/// ```compile_fail
+/// use dyn_box::dynrc;
+/// pub trait Config {}
+/// dynrc!(ConfigRc, ConfigPool: Config);
+/// fn shorten<'p, 's>(pool: &'p ConfigPool<'static, 16, 4>) -> &'p ConfigPool<'s, 16, 4> {
+///     pool
+/// }
+/// ```
+macro_rules! dynrc {
+	($rc:ident, $pool:ident : $trait:path) => {
+		/// Pool of `N` slots for a given Trait, each able to hold an implementor of
+		/// the trait if it does not exceed `SLOT_SIZE` bytes or 16 bytes alignment.
+		/// Values are shared through the reference-counted handles returned by
+		/// `insert()`. Generated through the `dynrc!` macro
+		pub struct $pool<'a, const SLOT_SIZE: usize, const N: usize> {
+			store: [core::cell::UnsafeCell<$crate::Storage<SLOT_SIZE>>; N],
+			vtables: [core::cell::Cell<Option<core::ptr::NonNull<()>>>; N],
+			counts: [core::cell::Cell<usize>; N],
+			// Neither `Send` nor `Sync`, as the counts are not atomic, and invariant, as
+			// values are inserted through a shared reference.
+			_content: core::marker::PhantomData<(fn(&'a ()) -> &'a (), *const ())>,
+		}
+
+		/// Reference-counted handle to a value in a pool. Generated through the
+		/// `dynrc!` macro
+		pub struct $rc<'p, 'a, const SLOT_SIZE: usize, const N: usize> {
+			pool: &'p $pool<'a, SLOT_SIZE, N>,
+			slot: usize,
+		}
+
+		// The vtables are obtained by transmuting `*const dyn $trait` to two words.
+		const _: () = assert!(
+			core::mem::size_of::<*const dyn $trait>() == 2 * core::mem::size_of::<usize>(),
+			"trait object pointers are not a data pointer and a vtable pointer"
+		);
+
+		impl<const SLOT_SIZE: usize, const N: usize> Drop for $pool<'_, SLOT_SIZE, N> {
+			fn drop(&mut self) {
+				// Only values whose handles were leaked are left.
+				for slot in 0..N {
+					self.remove_slot(slot);
+				}
+			}
+		}
+
+		impl<const SLOT_SIZE: usize, const N: usize> Default for $pool<'_, SLOT_SIZE, N> {
+			fn default() -> Self {
+				Self::new()
+			}
+		}
+
+		impl<'a, const SLOT_SIZE: usize, const N: usize> $pool<'a, SLOT_SIZE, N> {
+			/// Creates a new pool with all slots free.
+			pub const fn new() -> $pool<'a, SLOT_SIZE, N> {
+				$pool {
+					store: [const { core::cell::UnsafeCell::new($crate::Storage::new()) }; N],
+					vtables: [const { core::cell::Cell::new(None) }; N],
+					counts: [const { core::cell::Cell::new(0) }; N],
+					_content: core::marker::PhantomData,
+				}
+			}
+
+			/// Returns the number of values in the pool.
+			pub fn len(&self) -> usize {
+				self.vtables
+					.iter()
+					.filter(|vtable| vtable.get().is_some())
+					.count()
+			}
+
+			/// Returns whether the pool holds no values.
+			pub fn is_empty(&self) -> bool {
+				self.len() == 0
+			}
+
+			/// Stores a value of some generic type which implements $trait in a free
+			/// slot and returns the first handle to it, or gives the value back if
+			/// there is no free slot or it does not fit into one.
+			pub fn insert<T: $trait + 'a>(
+				&self,
+				content: T,
+			) -> Result<$rc<'_, 'a, SLOT_SIZE, N>, T> {
+				let free = self
+					.vtables
+					.iter()
+					.position(|vtable| vtable.get().is_none());
+				let slot = match free {
+					Some(slot)
+						if core::mem::size_of::<T>() <= SLOT_SIZE
+							&& core::mem::align_of::<T>()
+								<= core::mem::align_of::<$crate::Storage<SLOT_SIZE>>() =>
+					{
+						slot
+					}
+					_ => return Err(content),
+				};
+
+				let parts = unsafe {
+					core::mem::transmute::<*const dyn $trait, $crate::DynParts>(
+						&content as *const dyn $trait,
+					)
+				};
+				unsafe { ((*self.store[slot].get()).as_mut_ptr() as *mut T).write(content) }
+				self.vtables[slot].set(Some(parts.vtable));
+				self.counts[slot].set(1);
+				Ok($rc { pool: self, slot })
+			}
+
+			/// Drops the value in `slot`, if any, and frees the slot.
+			fn remove_slot(&self, slot: usize) {
+				if self.vtables[slot].get().is_some() {
+					let content = unsafe { self.get_ptr(slot) } as *mut (dyn $trait + 'a);
+					// Frees the slot only after the value is dropped, so that its destructor
+					// cannot reuse it, but even if that panics, so it is not dropped twice.
+					struct Free<'s>(&'s core::cell::Cell<Option<core::ptr::NonNull<()>>>);
+					impl Drop for Free<'_> {
+						fn drop(&mut self) {
+							self.0.set(None);
+						}
+					}
+					let _free = Free(&self.vtables[slot]);
+					unsafe { core::ptr::drop_in_place(content) }
+				}
+			}
+
+			/// Returns a pointer to the content of `slot`, which is only valid for
+			/// reads unless the last handle is gone.
+			unsafe fn get_ptr(&self, slot: usize) -> *const (dyn $trait + 'a) {
+				let data = (*self.store[slot].get()).as_mut_ptr();
+				core::mem::transmute::<$crate::DynParts, *mut (dyn $trait + 'a)>($crate::DynParts {
+					data,
+					vtable: self.vtables[slot].get().unwrap_unchecked(),
+				})
+			}
+		}
+
+		impl<'p, 'a, const SLOT_SIZE: usize, const N: usize> $rc<'p, 'a, SLOT_SIZE, N> {
+			/// Returns the number of handles to the value of `this`.
+			pub fn strong_count(this: &Self) -> usize {
+				this.pool.counts[this.slot].get()
+			}
+
+			/// Returns whether both handles refer to the same value.
+			pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+				core::ptr::eq(this.pool, other.pool) && this.slot == other.slot
+			}
+		}
+
+		impl<const SLOT_SIZE: usize, const N: usize> Clone for $rc<'_, '_, SLOT_SIZE, N> {
+			fn clone(&self) -> Self {
+				let count = &self.pool.counts[self.slot];
+				count.set(
+					count
+						.get()
+						.checked_add(1)
+						.expect("reference count overflow"),
+				);
+				$rc {
+					pool: self.pool,
+					slot: self.slot,
+				}
+			}
+		}
+
+		impl<'a, const SLOT_SIZE: usize, const N: usize> core::ops::Deref
+			for $rc<'_, 'a, SLOT_SIZE, N>
+		{
+			type Target = dyn $trait + 'a;
+
+			fn deref(&self) -> &Self::Target {
+				unsafe { &*self.pool.get_ptr(self.slot) }
+			}
+		}
+
+		impl<const SLOT_SIZE: usize, const N: usize> Drop for $rc<'_, '_, SLOT_SIZE, N> {
+			fn drop(&mut self) {
+				let count = &self.pool.counts[self.slot];
+				count.set(count.get() - 1);
+				if count.get() == 0 {
+					self.pool.remove_slot(self.slot);
+				}
+			}
+		}
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	use core::cell::Cell;
+
+	pub trait Config {
+		fn rate(&self) -> u32;
+	}
+
+	struct B(u128);
+	struct Droppable<'a>(&'a Cell<bool>);
+
+	impl Config for B {
+		fn rate(&self) -> u32 {
+			self.0 as u32
+		}
+	}
+	impl Config for Droppable<'_> {
+		fn rate(&self) -> u32 {
+			2
+		}
+	}
+	impl Drop for Droppable<'_> {
+		fn drop(&mut self) {
+			self.0.set(true);
+		}
+	}
+
+	dynrc!(ConfigRc, ConfigPool: Config);
+
+	#[test]
+	fn handles_share_value() {
+		let pool = ConfigPool::<16, 2>::default();
+		let first = pool.insert(B(1)).ok().unwrap();
+		let second = pool.insert(B(2)).ok().unwrap();
+		assert!(pool.insert(B(3)).err().unwrap().0 == 3);
+
+		let shared = first.clone();
+		assert!(shared.rate() == 1 && second.rate() == 2);
+		assert!(ConfigRc::strong_count(&first) == 2);
+		assert!(ConfigRc::ptr_eq(&first, &shared) && !ConfigRc::ptr_eq(&first, &second));
+		assert!(pool.len() == 2);
+	}
+
+	#[test]
+	fn last_handle_frees_slot() {
+		let drop_was_called = Cell::new(false);
+		let pool = ConfigPool::<16, 1>::new();
+		let first = pool.insert(Droppable(&drop_was_called)).ok().unwrap();
+		let shared = first.clone();
+		drop(first);
+		assert!(!drop_was_called.get() && shared.rate() == 2);
+
+		drop(shared);
+		assert!(drop_was_called.get() && pool.is_empty());
+		assert!(pool.insert(B(5)).ok().unwrap().rate() == 5);
+	}
+
+	#[test]
+	#[should_panic(expected = "reference count overflow")]
+	fn clone_panics_on_count_overflow() {
+		let pool = ConfigPool::<16, 1>::new();
+		let first = pool.insert(B(1)).ok().unwrap();
+		pool.counts[0].set(usize::MAX);
+		let _ = first.clone();
+	}
+}
@@ -0,0 +1,238 @@
+#[allow(unused_macros)]
+#[macro_export]
+/// Generates a container like [`dynbox!`], which stores its content in a
+/// caller-provided buffer instead of inline, e.g. to place it in a memory region
+/// chosen by the linker. The buffer's size and alignment limit what it can hold.
+/// Example:
+/// ```
+/// use core::mem::MaybeUninit;
+/// use dyn_box::dynbox_ref;
+/// trait MyTrait { fn foo(&self) -> u32; }
+/// impl MyTrait for u32 { fn foo(&self) -> u32 { *self } }
+/// dynbox_ref!(MyDynBoxRef: MyTrait);
+/// #[repr(align(16))]
+/// struct Buffer([MaybeUninit<u8>; 32]);
+/// let mut buffer = Buffer([MaybeUninit::uninit(); 32]);
+/// let mut my_dynbox = MyDynBoxRef::new(&mut buffer.0);
+/// my_dynbox.set(42u32);
+/// assert!(my_dynbox.get().unwrap().foo() == 42);
+/// ```
+macro_rules! dynbox_ref {
+	($name:ident : $trait:path) => {
+		/// Container for a given Trait, storing an implementor of the trait in the
+		/// buffer it was created with, if the value fits into the buffer and its
+		/// alignment does not exceed the buffer's. Generated through the
+		/// `dynbox_ref!` macro
+		pub struct $name<'a> {
+			buffer: &'a mut [core::mem::MaybeUninit<u8>],
+			vtable: Option<core::ptr::NonNull<()>>,
+			// Neither `Send` nor `Sync`, as the content may not be.
+			_content: core::marker::PhantomData<*const ()>,
+		}
+
+		// The vtable is obtained by transmuting `*const dyn $trait` to two words.
+		const _: () = assert!(
+			core::mem::size_of::<*const dyn $trait>() == 2 * core::mem::size_of::<usize>(),
+			"trait object pointers are not a data pointer and a vtable pointer"
+		);
+
+		impl Drop for $name<'_> {
+			fn drop(&mut self) {
+				self.clear();
+			}
+		}
+
+		impl<'a> $name<'a> {
+			/// Creates a new empty container storing its content in `buffer`.
+			pub fn new(buffer: &'a mut [core::mem::MaybeUninit<u8>]) -> $name<'a> {
+				$name {
+					buffer,
+					vtable: None,
+					_content: core::marker::PhantomData,
+				}
+			}
+
+			/// Returns the size of the buffer.
+			pub fn capacity(&self) -> usize {
+				self.buffer.len()
+			}
+
+			/// Returns the largest alignment the buffer provides, capped at 4096.
+			pub fn max_align(&self) -> usize {
+				1 << self.buffer.as_ptr().addr().trailing_zeros().min(12)
+			}
+
+			/// Returns whether a `T` fits into the buffer's size and alignment.
+			pub fn can_hold<T>(&self) -> bool {
+				core::mem::size_of::<T>() <= self.capacity()
+					&& core::mem::align_of::<T>() <= self.max_align()
+			}
+
+			/// Stores a value of some generic type which implements $trait. Panics if
+			/// it does not fit into the buffer. Clears (and drops) the previous value,
+			/// if present.
+			#[track_caller]
+			pub fn set<T: $trait + 'a>(&mut self, content: T) {
+				if let Err(error) = self.try_set(content) {
+					panic!("{}", error);
+				}
+			}
+
+			/// Stores a value like `set()`, but returns an error giving it back instead
+			/// of panicking if it does not fit, leaving the container unchanged.
+			pub fn try_set<T: $trait + 'a>(
+				&mut self,
+				content: T,
+			) -> Result<(), $crate::SetError<T>> {
+				if !self.can_hold::<T>() {
+					return Err($crate::SetError::new(
+						content,
+						self.capacity(),
+						self.max_align(),
+					));
+				}
+
+				self.clear();
+				let parts = unsafe {
+					core::mem::transmute::<*const dyn $trait, $crate::DynParts>(
+						&content as *const dyn $trait,
+					)
+				};
+				unsafe { (self.buffer.as_mut_ptr() as *mut T).write(content) }
+				self.vtable = Some(parts.vtable);
+				Ok(())
+			}
+
+			/// Makes the container empty again by dropping the previous content, if
+			/// any.
+			pub fn clear(&mut self) {
+				if self.vtable.is_some() {
+					let content = unsafe { self.get_ptr_mut() };
+					// Mark as empty first, so a panicking destructor cannot cause a double
+					// drop.
+					self.vtable = None;
+					unsafe { core::ptr::drop_in_place(content) }
+				}
+			}
+
+			/// Returns whether the container currently contains any value.
+			pub fn empty(&self) -> bool {
+				self.vtable.is_none()
+			}
+
+			/// Returns a `&dyn Trait` reference to the content if not empty, or None
+			/// otherwise.
+			pub fn get(&self) -> Option<&dyn $trait> {
+				if self.empty() {
+					None
+				} else {
+					Some(unsafe { &*self.get_ptr() })
+				}
+			}
+
+			/// Returns a `&mut dyn Trait` reference to the content if not empty, or
+			/// None otherwise.
+			pub fn get_mut(&mut self) -> Option<&mut dyn $trait> {
+				if self.empty() {
+					None
+				} else {
+					Some(unsafe { &mut *self.get_ptr_mut() })
+				}
+			}
+
+			/// Returns a pointer to the content, which is only valid for reads.
+			unsafe fn get_ptr(&self) -> *const (dyn $trait + 'a) {
+				let data = self.buffer.as_ptr() as *mut u8;
+				core::mem::transmute::<$crate::DynParts, *mut (dyn $trait + 'a)>($crate::DynParts {
+					data,
+					vtable: self.vtable.unwrap_unchecked(),
+				})
+			}
+
+			/// Returns a pointer to the content, which is also valid for writes.
+			unsafe fn get_ptr_mut(&mut self) -> *mut (dyn $trait + 'a) {
+				let data = self.buffer.as_mut_ptr() as *mut u8;
+				core::mem::transmute::<$crate::DynParts, *mut (dyn $trait + 'a)>($crate::DynParts {
+					data,
+					vtable: self.vtable.unwrap_unchecked(),
+				})
+			}
+		}
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	use core::cell::Cell;
+	use core::mem::MaybeUninit;
+
+	pub trait MyTrait {
+		fn foo(&self) -> u32;
+	}
+
+	struct B(u128);
+	struct Droppable<'a>(&'a Cell<bool>);
+
+	impl MyTrait for B {
+		fn foo(&self) -> u32 {
+			self.0 as u32
+		}
+	}
+	impl MyTrait for Droppable<'_> {
+		fn foo(&self) -> u32 {
+			2
+		}
+	}
+	impl Drop for Droppable<'_> {
+		fn drop(&mut self) {
+			self.0.set(true);
+		}
+	}
+
+	#[repr(align(16))]
+	struct Buffer([MaybeUninit<u8>; 32]);
+
+	dynbox_ref!(DynBoxRef: MyTrait);
+
+	#[test]
+	fn content_is_stored_in_buffer() {
+		let mut buffer = Buffer([MaybeUninit::uninit(); 32]);
+		let start = buffer.0.as_ptr().addr();
+		let mut dynbox = DynBoxRef::new(&mut buffer.0);
+		assert!(dynbox.empty() && dynbox.get().is_none());
+
+		dynbox.set(B(42));
+		let content = dynbox.get_mut().unwrap();
+		assert!(content.foo() == 42);
+		assert!((content as *mut dyn MyTrait as *mut u8).addr() == start);
+	}
+
+	#[test]
+	fn try_set_checks_buffer_size_and_alignment() {
+		let mut buffer = Buffer([MaybeUninit::uninit(); 32]);
+		let mut dynbox = DynBoxRef::new(&mut buffer.0[..8]);
+		let error = dynbox.try_set(B(1)).err().unwrap();
+		assert!(error.size() == 16 && error.capacity() == 8);
+		drop(dynbox);
+
+		let mut dynbox = DynBoxRef::new(&mut buffer.0[1..]);
+		assert!(dynbox.max_align() == 1);
+		assert!(dynbox.try_set(B(1)).err().unwrap().align() == core::mem::align_of::<B>());
+		assert!(dynbox.empty());
+	}
+
+	#[test]
+	fn drop_is_called_on_clear_and_drop() {
+		let drop_was_called = Cell::new(false);
+		let mut buffer = Buffer([MaybeUninit::uninit(); 32]);
+		let mut dynbox = DynBoxRef::new(&mut buffer.0);
+		dynbox.set(Droppable(&drop_was_called));
+		dynbox.clear();
+		assert!(drop_was_called.get());
+
+		drop_was_called.set(false);
+		dynbox.set(Droppable(&drop_was_called));
+		drop(dynbox);
+		assert!(drop_was_called.get());
+	}
+}
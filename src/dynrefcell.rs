@@ -0,0 +1,144 @@
+#[allow(unused_macros)]
+#[macro_export]
+/// Generates a `RefCell`-like cell around a DynBox type generated by
+/// [`dynbox!`], which hands out `Ref`s and `RefMut`s to the content, so that it
+/// can be shared without `unsafe`. Borrows are tracked at runtime; conflicting
+/// ones panic like those of `RefCell`. The trait must be the one of the DynBox.
+/// Example:
+/// ```
+/// use dyn_box::{dynbox, dynbox_refcell};
+/// trait Task { fn poll(&mut self) -> u32; }
+/// impl Task for u32 { fn poll(&mut self) -> u32 { *self += 1; *self } }
+/// dynbox!(TaskBox: Task);
+/// dynbox_refcell!(TaskCell: TaskBox, Task);
+/// let task = TaskCell::<16>::new();
+/// task.borrow_box_mut().set(1u32);
+/// assert!(task.borrow_mut().unwrap().poll() == 2);
+/// ```
+macro_rules! dynbox_refcell {
+	($name:ident : $box:ident, $trait:path) => {
+		/// Cell holding a DynBox, which tracks borrows of it at runtime like a
+		/// `RefCell`. Generated through the `dynbox_refcell!` macro
+		pub struct $name<'a, const SIZE: usize> {
+			dynbox: core::cell::RefCell<$box<'a, SIZE>>,
+		}
+
+		impl<const SIZE: usize> Default for $name<'_, SIZE> {
+			fn default() -> Self {
+				Self::new()
+			}
+		}
+
+		impl<'a, const SIZE: usize> $name<'a, SIZE> {
+			/// Creates a new cell holding an empty DynBox. Can be evaluated at compile
+			/// time.
+			pub const fn new() -> $name<'a, SIZE> {
+				$name {
+					dynbox: core::cell::RefCell::new($box::new()),
+				}
+			}
+
+			/// Returns the DynBox, consuming the cell.
+			pub fn into_inner(self) -> $box<'a, SIZE> {
+				self.dynbox.into_inner()
+			}
+
+			/// Borrows the content immutably if not empty, or returns None otherwise.
+			/// Panics if it is currently borrowed mutably.
+			#[track_caller]
+			pub fn borrow(&self) -> Option<core::cell::Ref<'_, dyn $trait + 'a>> {
+				// Through `DynContainer`, whose accessors keep the content's lifetime.
+				core::cell::Ref::filter_map(self.dynbox.borrow(), $crate::DynContainer::get).ok()
+			}
+
+			/// Borrows the content mutably if not empty, or returns None otherwise.
+			/// Panics if it is currently borrowed.
+			#[track_caller]
+			pub fn borrow_mut(&self) -> Option<core::cell::RefMut<'_, dyn $trait + 'a>> {
+				core::cell::RefMut::filter_map(
+					self.dynbox.borrow_mut(),
+					$crate::DynContainer::get_mut,
+				)
+				.ok()
+			}
+
+			/// Borrows the DynBox itself immutably. Panics if it is currently borrowed
+			/// mutably.
+			#[track_caller]
+			pub fn borrow_box(&self) -> core::cell::Ref<'_, $box<'a, SIZE>> {
+				self.dynbox.borrow()
+			}
+
+			/// Borrows the DynBox itself mutably, e.g. to `set()` new content. Panics
+			/// if it is currently borrowed.
+			#[track_caller]
+			pub fn borrow_box_mut(&self) -> core::cell::RefMut<'_, $box<'a, SIZE>> {
+				self.dynbox.borrow_mut()
+			}
+
+			/// Returns whether the DynBox is empty. Panics if it is currently borrowed
+			/// mutably.
+			#[track_caller]
+			pub fn empty(&self) -> bool {
+				self.dynbox.borrow().empty()
+			}
+
+			/// Drops the content, if any. Panics if it is currently borrowed.
+			#[track_caller]
+			pub fn clear(&self) {
+				self.dynbox.borrow_mut().clear()
+			}
+		}
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::dynbox;
+
+	pub trait Task {
+		fn poll(&mut self) -> u32;
+		fn polls(&self) -> u32;
+	}
+
+	struct Counter(u32);
+
+	impl Task for Counter {
+		fn poll(&mut self) -> u32 {
+			self.0 += 1;
+			self.0
+		}
+		fn polls(&self) -> u32 {
+			self.0
+		}
+	}
+
+	dynbox!(TaskBox: Task);
+	dynbox_refcell!(TaskCell: TaskBox, Task);
+
+	#[test]
+	fn borrows_are_shared_or_exclusive() {
+		let task = TaskCell::<16>::default();
+		assert!(task.borrow().is_none() && task.borrow_mut().is_none());
+
+		task.borrow_box_mut().set(Counter(0));
+		assert!(task.borrow_mut().unwrap().poll() == 1);
+		let first = task.borrow().unwrap();
+		let second = task.borrow().unwrap();
+		assert!(first.polls() == 1 && second.polls() == 1);
+		assert!(!task.empty() && task.borrow_box().get().is_some());
+		drop((first, second));
+
+		task.clear();
+		assert!(task.into_inner().empty());
+	}
+
+	#[test]
+	#[should_panic]
+	fn conflicting_borrow_panics() {
+		let task = TaskCell::<16>::new();
+		task.borrow_box_mut().set(Counter(0));
+		let _shared = task.borrow();
+		task.borrow_mut();
+	}
+}
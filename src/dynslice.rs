@@ -0,0 +1,160 @@
+#[allow(unused_macros)]
+#[macro_export]
+/// Generates a container which stores a slice of `Copy` elements or a string
+/// inline, like [`dynbox!`] does for trait objects. `set_from()` copies the
+/// given slice into the store and records its length, `get()` returns it again.
+/// Example:
+/// ```
+/// use dyn_box::dynslice;
+/// dynslice!(Payload: [u8]);
+/// dynslice!(Label: str);
+/// let mut payload = Payload::<16>::new();
+/// payload.set_from(&[1, 2, 3]);
+/// assert!(payload.get() == Some(&[1, 2, 3][..]));
+/// let mut label = Label::<16>::new();
+/// label.set_from("hello");
+/// assert!(label.get() == Some("hello"));
+/// ```
+macro_rules! dynslice {
+	($name:ident : [$elem:ty]) => {
+		$crate::dynslice!(
+			@define $name, $elem, [$elem], core::convert::identity, core::convert::identity,
+			core::convert::identity
+		);
+	};
+	($name:ident : str) => {
+		$crate::dynslice!(
+			@define $name, u8, str, str::as_bytes, core::str::from_utf8_unchecked,
+			core::str::from_utf8_unchecked_mut
+		);
+	};
+	(@define $name:ident, $elem:ty, $content:ty, $as_elems:path, $from_elems:path, $from_elems_mut:path) => {
+		/// Container for a slice or string, storing a copy of it inline if it does
+		/// not exceed `SIZE` bytes. Generated through the `dynslice!` macro
+		#[derive(Clone, Copy)]
+		pub struct $name<const SIZE: usize> {
+			store: $crate::Storage<SIZE>,
+			len: Option<usize>,
+		}
+
+		impl<const SIZE: usize> Default for $name<SIZE> {
+			fn default() -> Self {
+				Self::new()
+			}
+		}
+
+		// Elements are copied bitwise and never dropped.
+		impl<const SIZE: usize> $name<SIZE>
+		where
+			$elem: Copy,
+		{
+			/// Creates a new empty container.
+			pub const fn new() -> $name<SIZE> {
+				const {
+					assert!(
+						core::mem::align_of::<$elem>() <= core::mem::align_of::<$crate::Storage<SIZE>>(),
+						"element alignment exceeds the store's"
+					)
+				}
+				$name {
+					store: $crate::Storage::new(),
+					len: None,
+				}
+			}
+
+			/// Returns the size of the store in bytes, i.e. `SIZE`.
+			pub const fn capacity() -> usize {
+				SIZE
+			}
+
+			/// Copies `content` into the store, replacing the previous content, if
+			/// any. Panics if it exceeds the capacity.
+			#[track_caller]
+			pub fn set_from(&mut self, content: &$content) {
+				let size = core::mem::size_of_val(content);
+				if self.try_set_from(content).is_err() {
+					panic!("size {} > capacity {}", size, SIZE);
+				}
+			}
+
+			/// Copies `content` like `set_from()`, but gives it back instead of
+			/// panicking if it exceeds the capacity, leaving the container unchanged.
+			pub fn try_set_from<'s>(&mut self, content: &'s $content) -> Result<(), &'s $content> {
+				let elems: &[$elem] = $as_elems(content);
+				if core::mem::size_of_val(elems) > SIZE {
+					return Err(content);
+				}
+
+				let store = self.store.as_mut_ptr() as *mut $elem;
+				unsafe { core::ptr::copy_nonoverlapping(elems.as_ptr(), store, elems.len()) }
+				self.len = Some(elems.len());
+				Ok(())
+			}
+
+			/// Makes the container empty again.
+			pub fn clear(&mut self) {
+				self.len = None;
+			}
+
+			/// Returns whether the container currently contains any content.
+			pub fn empty(&self) -> bool {
+				self.len.is_none()
+			}
+
+			/// Returns a reference to the content if not empty, or None otherwise.
+			pub fn get(&self) -> Option<&$content> {
+				let elems = self.store.as_ptr() as *const $elem;
+				self.len
+					.map(|len| unsafe { $from_elems(core::slice::from_raw_parts(elems, len)) })
+			}
+
+			/// Returns a mutable reference to the content if not empty, or None
+			/// otherwise.
+			pub fn get_mut(&mut self) -> Option<&mut $content> {
+				let elems = self.store.as_mut_ptr() as *mut $elem;
+				self.len
+					.map(|len| unsafe { $from_elems_mut(core::slice::from_raw_parts_mut(elems, len)) })
+			}
+		}
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	dynslice!(Payload: [u16]);
+	dynslice!(Label: str);
+
+	#[test]
+	fn slice_is_copied_into_store() {
+		let mut payload = Payload::<8>::new();
+		assert!(payload.empty() && payload.get().is_none());
+		payload.set_from(&[1, 2, 3]);
+		payload.get_mut().unwrap()[0] = 4;
+		assert!(payload.get() == Some(&[4, 2, 3][..]));
+
+		payload.set_from(&[]);
+		assert!(payload.get() == Some(&[][..]));
+		payload.clear();
+		assert!(payload.empty());
+	}
+
+	#[test]
+	fn try_set_from_gives_back_oversized_content() {
+		let mut payload = Payload::<4>::new();
+		assert!(Payload::<4>::capacity() == 4);
+		payload.set_from(&[1, 2]);
+		assert!(payload.try_set_from(&[1, 2, 3]) == Err(&[1, 2, 3][..]));
+		assert!(payload.get() == Some(&[1, 2][..]));
+	}
+
+	#[test]
+	fn string_is_copied_into_store() {
+		let mut label = Label::<8>::new();
+		label.set_from("hello");
+		label.get_mut().unwrap().make_ascii_uppercase();
+		assert!(label.get() == Some("HELLO"));
+		assert!(label.try_set_from("too long a label").is_err());
+		label.clear();
+		assert!(label.empty() && Label::<8>::capacity() == 8);
+	}
+}
@@ -0,0 +1,227 @@
+#[allow(unused_macros)]
+#[macro_export]
+/// Generates a container with a fixed number of independently-occupiable slots,
+/// each able to hold a certain trait.
+/// Example:
+/// ```
+/// use dyn_box::dynslots;
+/// trait MyTrait { fn foo(&self) -> u32; }
+/// dynslots!(MySlots: MyTrait, 4);
+/// let my_slots = MySlots::<16>::new();
+/// ```
+macro_rules! dynslots {
+	($name:ident : $trait:path, $count:expr) => {
+		/// Fixed number of slots for a given Trait, each with `Option<dyn Trait>`-like
+		/// semantics. Every slot can hold implementors of the trait if they do not
+		/// exceed `SLOT_SIZE`. All slots share one inline arena split into equal
+		/// regions. Generated through the `dynslots!` macro
+		pub struct $name<'a, const SLOT_SIZE: usize> {
+			store: [$crate::Storage<SLOT_SIZE>; $count],
+			vtables: [Option<core::ptr::NonNull<()>>; $count],
+			_content: core::marker::PhantomData<&'a ()>,
+		}
+
+		// The vtables are obtained by transmuting `*const dyn $trait` to two words.
+		const _: () = assert!(
+			core::mem::size_of::<*const dyn $trait>() == 2 * core::mem::size_of::<usize>(),
+			"trait object pointers are not a data pointer and a vtable pointer"
+		);
+
+		impl<const SLOT_SIZE: usize> Drop for $name<'_, SLOT_SIZE> {
+			fn drop(&mut self) {
+				for slot in 0..$count {
+					self.clear(slot);
+				}
+			}
+		}
+
+		impl<'a, const SLOT_SIZE: usize> $name<'a, SLOT_SIZE> {
+			/// Creates a new container with all slots empty.
+			pub fn new() -> $name<'a, SLOT_SIZE> {
+				$name {
+					store: [$crate::Storage::new(); $count],
+					vtables: [None; $count],
+					_content: core::marker::PhantomData,
+				}
+			}
+
+			/// Returns the number of slots.
+			pub const fn slots(&self) -> usize {
+				$count
+			}
+
+			/// Stores a value of some generic type which implements $trait in `slot`.
+			/// Panics if T's size exceeds `SLOT_SIZE`, if its alignment exceeds that of
+			/// the slots (16 bytes), or if `slot` is out of range. Clears (and drops)
+			/// the slot's previous value, if present.
+			#[track_caller]
+			pub fn set<T: $trait + 'a>(&mut self, slot: usize, content: T) {
+				if !self.empty(slot) {
+					self.clear(slot);
+				}
+
+				let size = core::mem::size_of::<T>();
+
+				assert!(size <= SLOT_SIZE);
+				assert!(
+					core::mem::align_of::<T>()
+						<= core::mem::align_of::<$crate::Storage<SLOT_SIZE>>()
+				);
+
+				let parts = unsafe {
+					core::mem::transmute::<*const dyn $trait, $crate::DynParts>(
+						&content as *const dyn $trait,
+					)
+				};
+				self.vtables[slot] = Some(parts.vtable);
+				unsafe { (self.store[slot].as_mut_ptr() as *mut T).write(content) }
+			}
+
+			/// Makes `slot` empty again by dropping its previous content, if any.
+			/// Panics if `slot` is out of range.
+			pub fn clear(&mut self, slot: usize) {
+				if self.vtables[slot].is_some() {
+					let content = unsafe { self.get_ptr_mut(slot) };
+					self.vtables[slot] = None;
+					unsafe { core::ptr::drop_in_place(content) }
+				}
+			}
+
+			/// Returns whether `slot` currently contains any value. Panics if `slot`
+			/// is out of range.
+			pub fn empty(&self, slot: usize) -> bool {
+				self.vtables[slot].is_none()
+			}
+
+			/// Returns a `&dyn Trait` reference to the content of `slot` if not empty,
+			/// or None otherwise. Panics if `slot` is out of range.
+			pub fn get(&self, slot: usize) -> Option<&dyn $trait> {
+				if self.vtables[slot].is_none() {
+					None
+				} else {
+					Some(unsafe { &*self.get_ptr(slot) })
+				}
+			}
+
+			/// Returns a `&mut dyn Trait` reference to the content of `slot` if not
+			/// empty, or None otherwise. Panics if `slot` is out of range.
+			pub fn get_mut(&mut self, slot: usize) -> Option<&mut dyn $trait> {
+				if self.vtables[slot].is_none() {
+					None
+				} else {
+					Some(unsafe { &mut *self.get_ptr_mut(slot) })
+				}
+			}
+
+			/// Returns an iterator over the contents of all occupied slots, in slot
+			/// order.
+			pub fn iter(&self) -> impl Iterator<Item = &dyn $trait> {
+				(0..$count).filter_map(move |slot| self.get(slot))
+			}
+
+			/// Returns a pointer to the content of `slot`, which is only valid for
+			/// reads.
+			unsafe fn get_ptr(&self, slot: usize) -> *const (dyn $trait + 'a) {
+				let data = self.store[slot].as_ptr() as *mut u8;
+				core::mem::transmute::<$crate::DynParts, *mut (dyn $trait + 'a)>($crate::DynParts {
+					data,
+					vtable: self.vtables[slot].unwrap_unchecked(),
+				})
+			}
+
+			/// Returns a pointer to the content of `slot`, which is also valid for
+			/// writes.
+			unsafe fn get_ptr_mut(&mut self, slot: usize) -> *mut (dyn $trait + 'a) {
+				let data = self.store[slot].as_mut_ptr();
+				core::mem::transmute::<$crate::DynParts, *mut (dyn $trait + 'a)>($crate::DynParts {
+					data,
+					vtable: self.vtables[slot].unwrap_unchecked(),
+				})
+			}
+		}
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	use core::cell::Cell;
+
+	pub trait MyTrait {
+		fn foo(&self) -> u32;
+	}
+
+	struct B(u128);
+	struct Droppable<'a>(&'a Cell<bool>);
+
+	impl MyTrait for B {
+		fn foo(&self) -> u32 {
+			self.0 as u32
+		}
+	}
+	impl MyTrait for Droppable<'_> {
+		fn foo(&self) -> u32 {
+			2
+		}
+	}
+	impl Drop for Droppable<'_> {
+		fn drop(&mut self) {
+			self.0.set(true);
+		}
+	}
+
+	dynslots!(Slots: MyTrait, 4);
+
+	#[test]
+	fn new_slots_are_empty() {
+		let slots = Slots::<16>::new();
+		assert!(slots.slots() == 4);
+		for slot in 0..4 {
+			assert!(slots.empty(slot));
+			assert!(slots.get(slot).is_none());
+		}
+	}
+
+	#[test]
+	fn slots_are_independent() {
+		let mut slots = Slots::<16>::new();
+		slots.set(0, B(10));
+		slots.set(2, B(12));
+
+		assert!(slots.get(0).unwrap().foo() == 10);
+		assert!(slots.get(1).is_none());
+		assert!(slots.get_mut(2).unwrap().foo() == 12);
+		assert!(slots.get(3).is_none());
+
+		slots.clear(0);
+		assert!(slots.empty(0));
+		assert!(slots.get(2).unwrap().foo() == 12);
+	}
+
+	#[test]
+	fn iter_visits_occupied_slots() {
+		let mut slots = Slots::<16>::new();
+		slots.set(0, B(1));
+		slots.set(1, B(2));
+		slots.set(3, B(4));
+		assert!(slots.iter().map(|content| content.foo()).sum::<u32>() == 7);
+	}
+
+	#[test]
+	#[should_panic]
+	fn out_of_range_slot_panics() {
+		let mut slots = Slots::<16>::new();
+		slots.set(4, B(1));
+	}
+
+	#[test]
+	fn drop_is_called_on_drop() {
+		let drop_was_called = Cell::new(false);
+		{
+			let mut slots = Slots::<16>::new();
+			slots.set(3, Droppable(&drop_was_called));
+			assert!(!drop_was_called.get());
+		}
+
+		assert!(drop_was_called.get());
+	}
+}
@@ -0,0 +1,265 @@
+#[allow(unused_macros)]
+#[macro_export]
+/// Generates a double buffer holding a value implementing a certain trait,
+/// which one context replaces while another uses it. Reading never waits and
+/// only needs atomic loads and stores, so the publisher may be e.g. a control
+/// thread and the reader a real-time interrupt handler. The trait must be at
+/// least as visible as the double buffer.
+/// Example:
+/// ```
+/// use dyn_box::dynswap;
+/// pub trait Filter { fn apply(&mut self, sample: i32) -> i32; }
+/// impl Filter for i32 { fn apply(&mut self, sample: i32) -> i32 { sample * *self } }
+/// dynswap!(FilterSwap: Filter);
+/// let mut filter = FilterSwap::<16>::new();
+/// let (mut publisher, mut reader) = filter.split();
+/// publisher.publish(2).unwrap();
+/// assert!(reader.borrow_current(|filter| filter.apply(3)) == Some(6));
+/// ```
+/// The double buffer is invariant in the lifetime of its values, so that a
+/// publisher for long-lived values cannot publish ones borrowing shorter-lived
+/// data:
+/// ```compile_fail
+/// use dyn_box::{dynswap, Publisher};
+/// pub trait Filter {}
+/// dynswap!(FilterSwap: Filter);
+/// fn shorten<'p, 's>(
+///     publisher: Publisher<'p, FilterSwap<'static, 16>>,
+/// ) -> Publisher<'p, FilterSwap<'s, 16>> {
+///     publisher
+/// }
+/// ```
+macro_rules! dynswap {
+	($name:ident : $trait:path) => {
+		/// Double buffer for a given Trait, holding a `Send` implementor of the trait
+		/// which does not exceed `SLOT_SIZE` bytes or 16 bytes alignment. Values are
+		/// published and read through the `Publisher` and `Reader` returned by
+		/// `split()`. Generated through the `dynswap!` macro
+		pub struct $name<'a, const SLOT_SIZE: usize> {
+			slots: [core::cell::UnsafeCell<$crate::Storage<SLOT_SIZE>>; 2],
+			vtables: [core::cell::UnsafeCell<Option<core::ptr::NonNull<()>>>; 2],
+			// Index of the slot holding the current value.
+			current: core::sync::atomic::AtomicUsize,
+			// The reader marks itself in `reading[version]` while it holds a value,
+			// so that the publisher can tell when it is done with the previous one.
+			version: core::sync::atomic::AtomicUsize,
+			reading: [core::sync::atomic::AtomicBool; 2],
+			// Invariant, as values are published through a shared reference.
+			_content: core::marker::PhantomData<fn(&'a ()) -> &'a ()>,
+		}
+
+		// The vtables are obtained by transmuting `*const dyn $trait` to two words.
+		const _: () = assert!(
+			core::mem::size_of::<*const dyn $trait>() == 2 * core::mem::size_of::<usize>(),
+			"trait object pointers are not a data pointer and a vtable pointer"
+		);
+
+		// The publisher only writes the slot the reader does not use, and all values
+		// are `Send`.
+		unsafe impl<const SLOT_SIZE: usize> Send for $name<'_, SLOT_SIZE> {}
+		unsafe impl<const SLOT_SIZE: usize> Sync for $name<'_, SLOT_SIZE> {}
+
+		impl<const SLOT_SIZE: usize> Drop for $name<'_, SLOT_SIZE> {
+			fn drop(&mut self) {
+				for slot in 0..2 {
+					unsafe { self.clear_slot(slot) }
+				}
+			}
+		}
+
+		impl<const SLOT_SIZE: usize> Default for $name<'_, SLOT_SIZE> {
+			fn default() -> Self {
+				Self::new()
+			}
+		}
+
+		impl<'a, const SLOT_SIZE: usize> $name<'a, SLOT_SIZE> {
+			/// Creates a new double buffer without a value.
+			pub const fn new() -> $name<'a, SLOT_SIZE> {
+				$name {
+					slots: [const { core::cell::UnsafeCell::new($crate::Storage::new()) }; 2],
+					vtables: [const { core::cell::UnsafeCell::new(None) }; 2],
+					current: core::sync::atomic::AtomicUsize::new(0),
+					version: core::sync::atomic::AtomicUsize::new(0),
+					reading: [const { core::sync::atomic::AtomicBool::new(false) }; 2],
+					_content: core::marker::PhantomData,
+				}
+			}
+
+			/// Returns the publishing and the reading end of the double buffer, which
+			/// may be moved to different contexts.
+			pub fn split(&mut self) -> ($crate::Publisher<'_, Self>, $crate::Reader<'_, Self>) {
+				// The exclusive borrow ensures these are the only ends.
+				unsafe { ($crate::Publisher::new(self), $crate::Reader::new(self)) }
+			}
+
+			/// Drops the value in `slot`, if any.
+			///
+			/// # Safety
+			/// The reader must not use `slot`.
+			unsafe fn clear_slot(&self, slot: usize) {
+				if let Some(vtable) = (*self.vtables[slot].get()).take() {
+					let content = core::mem::transmute::<$crate::DynParts, *mut (dyn $trait + 'a)>(
+						$crate::DynParts {
+							data: (*self.slots[slot].get()).as_mut_ptr(),
+							vtable,
+						},
+					);
+					core::ptr::drop_in_place(content);
+				}
+			}
+		}
+
+		impl<'a, T: $trait + Send + 'a, const SLOT_SIZE: usize> $crate::SwapPublish<T>
+			for $name<'a, SLOT_SIZE>
+		{
+			unsafe fn publish(&self, value: T) -> Result<(), T> {
+				use core::sync::atomic::Ordering::SeqCst;
+
+				if core::mem::size_of::<T>() > SLOT_SIZE
+					|| core::mem::align_of::<T>()
+						> core::mem::align_of::<$crate::Storage<SLOT_SIZE>>()
+				{
+					return Err(value);
+				}
+
+				// The other slot is empty, and the reader does not use it.
+				let previous = self.current.load(SeqCst);
+				let slot = 1 - previous;
+				let parts = core::mem::transmute::<*const dyn $trait, $crate::DynParts>(
+					&value as *const dyn $trait,
+				);
+				((*self.slots[slot].get()).as_mut_ptr() as *mut T).write(value);
+				*self.vtables[slot].get() = Some(parts.vtable);
+				self.current.store(slot, SeqCst);
+
+				// Wait for the reader to leave the previous slot: once it is done under
+				// both versions, it came back after the switch and reads the new slot.
+				let version = self.version.load(SeqCst);
+				while self.reading[1 - version].load(SeqCst) {
+					core::hint::spin_loop();
+				}
+				self.version.store(1 - version, SeqCst);
+				while self.reading[version].load(SeqCst) {
+					core::hint::spin_loop();
+				}
+				self.clear_slot(previous);
+				Ok(())
+			}
+		}
+
+		impl<'a, const SLOT_SIZE: usize> $crate::SwapRead for $name<'a, SLOT_SIZE> {
+			type Content = dyn $trait + 'a;
+
+			unsafe fn read_with<R>(&self, f: impl FnOnce(&mut Self::Content) -> R) -> Option<R> {
+				use core::sync::atomic::Ordering::SeqCst;
+
+				let version = self.version.load(SeqCst);
+				self.reading[version].store(true, SeqCst);
+				// Leaves the slot even if `f` panics.
+				struct Leave<'r>(&'r core::sync::atomic::AtomicBool);
+				impl Drop for Leave<'_> {
+					fn drop(&mut self) {
+						self.0.store(false, SeqCst);
+					}
+				}
+				let _leave = Leave(&self.reading[version]);
+
+				let slot = self.current.load(SeqCst);
+				let vtable = (*self.vtables[slot].get())?;
+				let content = core::mem::transmute::<$crate::DynParts, *mut (dyn $trait + 'a)>(
+					$crate::DynParts {
+						data: (*self.slots[slot].get()).as_mut_ptr(),
+						vtable,
+					},
+				);
+				Some(f(&mut *content))
+			}
+		}
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	extern crate std;
+
+	use core::sync::atomic::{AtomicU32, Ordering};
+
+	pub trait Filter {
+		fn gain(&mut self) -> u32;
+	}
+
+	struct B(u128);
+	struct Counted<'a>(u32, &'a AtomicU32);
+
+	impl Filter for B {
+		fn gain(&mut self) -> u32 {
+			self.0 as u32
+		}
+	}
+	impl Filter for u8 {
+		fn gain(&mut self) -> u32 {
+			*self as u32
+		}
+	}
+	impl Filter for Counted<'_> {
+		fn gain(&mut self) -> u32 {
+			self.0
+		}
+	}
+	impl Drop for Counted<'_> {
+		fn drop(&mut self) {
+			self.1.fetch_add(1, Ordering::Relaxed);
+		}
+	}
+
+	dynswap!(FilterSwap: Filter);
+
+	#[test]
+	fn reader_sees_latest_published_value() {
+		let mut swap = FilterSwap::<16>::new();
+		let (mut publisher, mut reader) = swap.split();
+		assert!(reader.borrow_current(|filter| filter.gain()).is_none());
+		publisher.publish(1u8).unwrap();
+		assert!(reader.borrow_current(|filter| filter.gain()) == Some(1));
+		publisher.publish(B(2)).ok().unwrap();
+		publisher.publish(3u8).unwrap();
+		assert!(reader.borrow_current(|filter| filter.gain()) == Some(3));
+
+		let mut small = FilterSwap::<8>::new();
+		assert!(small.split().0.publish(B(4)).err().unwrap().0 == 4);
+	}
+
+	#[test]
+	fn previous_values_are_dropped() {
+		let counter = AtomicU32::new(0);
+		{
+			let mut swap = FilterSwap::<32>::new();
+			let (mut publisher, _) = swap.split();
+			publisher.publish(Counted(1, &counter)).ok().unwrap();
+			publisher.publish(Counted(2, &counter)).ok().unwrap();
+			assert!(counter.load(Ordering::Relaxed) == 1);
+		}
+		assert!(counter.load(Ordering::Relaxed) == 2);
+	}
+
+	#[test]
+	fn ends_work_from_different_threads() {
+		let mut swap = FilterSwap::<16>::new();
+		let (mut publisher, mut reader) = swap.split();
+		std::thread::scope(|scope| {
+			scope.spawn(move || {
+				for gain in 1..=1000 {
+					publisher.publish(B(gain)).ok().unwrap();
+				}
+			});
+			let mut last = 0;
+			while last < 1000 {
+				if let Some(gain) = reader.borrow_current(|filter| filter.gain()) {
+					assert!(gain >= last);
+					last = gain;
+				}
+			}
+		});
+	}
+}
@@ -0,0 +1,227 @@
+#[allow(unused_macros)]
+#[macro_export]
+/// Generates a slab of `N` inline slots for `Future<Output = ()>` tasks, on top
+/// of which a small executor can be written without an allocator. As the tasks
+/// are polled in place, the slab is used pinned. Each task gets a waker which
+/// marks it in the [`ReadyFlags`](crate::ReadyFlags) the slab was created
+/// with, and `poll_ready_tasks()` polls the marked ones.
+/// Example:
+/// ```
+/// use core::pin::pin;
+/// use dyn_box::{dyntasks, ReadyFlags};
+/// dyntasks!(Tasks);
+/// static READY: ReadyFlags<4> = ReadyFlags::new();
+/// let mut tasks = pin!(Tasks::<64, 4>::new(&READY));
+/// tasks.as_mut().spawn(async {}).ok().unwrap();
+/// while !tasks.is_empty() {
+///     tasks.as_mut().poll_ready_tasks();
+/// }
+/// ```
+macro_rules! dyntasks {
+	($name:ident) => {
+		/// Slab of `N` tasks, each of which must not exceed `SLOT_SIZE` bytes or 16
+		/// bytes alignment. Generated through the `dyntasks!` macro
+		pub struct $name<'a, const SLOT_SIZE: usize, const N: usize> {
+			store: [$crate::Storage<SLOT_SIZE>; N],
+			vtables: [Option<core::ptr::NonNull<()>>; N],
+			ready: &'static $crate::ReadyFlags<N>,
+			// Tasks are polled in place, so the slab must not move once pinned.
+			_pinned: core::marker::PhantomPinned,
+			// Neither `Send` nor `Sync`, as the tasks may not be.
+			_content: core::marker::PhantomData<(&'a (), *const ())>,
+		}
+
+		// The vtables are obtained by transmuting `*const dyn Future` to two words.
+		const _: () = assert!(
+			core::mem::size_of::<*const dyn core::future::Future<Output = ()>>()
+				== 2 * core::mem::size_of::<usize>(),
+			"trait object pointers are not a data pointer and a vtable pointer"
+		);
+
+		impl<const SLOT_SIZE: usize, const N: usize> Drop for $name<'_, SLOT_SIZE, N> {
+			fn drop(&mut self) {
+				for slot in 0..N {
+					self.remove_slot(slot);
+				}
+			}
+		}
+
+		impl<'a, const SLOT_SIZE: usize, const N: usize> $name<'a, SLOT_SIZE, N> {
+			/// Creates a new slab without tasks, whose wakers mark `ready`.
+			pub const fn new(ready: &'static $crate::ReadyFlags<N>) -> $name<'a, SLOT_SIZE, N> {
+				$name {
+					store: [$crate::Storage::new(); N],
+					vtables: [None; N],
+					ready,
+					_pinned: core::marker::PhantomPinned,
+					_content: core::marker::PhantomData,
+				}
+			}
+
+			/// Returns the number of tasks which did not complete yet.
+			pub fn len(&self) -> usize {
+				self.vtables
+					.iter()
+					.filter(|vtable| vtable.is_some())
+					.count()
+			}
+
+			/// Returns whether all tasks completed.
+			pub fn is_empty(&self) -> bool {
+				self.len() == 0
+			}
+
+			/// Stores `task` in a free slot and marks it as ready, returning the slot,
+			/// or gives the task back if there is no free slot or it does not fit into
+			/// one.
+			pub fn spawn<F: core::future::Future<Output = ()> + 'a>(
+				self: core::pin::Pin<&mut Self>,
+				task: F,
+			) -> Result<usize, F> {
+				// SAFETY: Only a free slot is written, no task is moved.
+				let this = unsafe { self.get_unchecked_mut() };
+				let free = this.vtables.iter().position(|vtable| vtable.is_none());
+				let slot = match free {
+					Some(slot)
+						if core::mem::size_of::<F>() <= SLOT_SIZE
+							&& core::mem::align_of::<F>()
+								<= core::mem::align_of::<$crate::Storage<SLOT_SIZE>>() =>
+					{
+						slot
+					}
+					_ => return Err(task),
+				};
+
+				let parts = unsafe {
+					core::mem::transmute::<
+						*const dyn core::future::Future<Output = ()>,
+						$crate::DynParts,
+					>(&task as *const dyn core::future::Future<Output = ()>)
+				};
+				unsafe { (this.store[slot].as_mut_ptr() as *mut F).write(task) }
+				this.vtables[slot] = Some(parts.vtable);
+				this.ready.set(slot);
+				Ok(slot)
+			}
+
+			/// Polls each task which was woken since it was last polled, in slot
+			/// order, and drops the completed ones. Returns the number of polled tasks.
+			pub fn poll_ready_tasks(self: core::pin::Pin<&mut Self>) -> usize {
+				// SAFETY: Tasks are polled in place and dropped by `remove_slot()`.
+				let this = unsafe { self.get_unchecked_mut() };
+				let mut polled = 0;
+				for slot in 0..N {
+					if this.vtables[slot].is_none() || !this.ready.take(slot) {
+						continue;
+					}
+
+					let waker = this.ready.waker(slot);
+					let mut context = core::task::Context::from_waker(&waker);
+					let task =
+						unsafe { core::pin::Pin::new_unchecked(&mut *this.get_ptr_mut(slot)) };
+					if task.poll(&mut context).is_ready() {
+						this.remove_slot(slot);
+					}
+					polled += 1;
+				}
+				polled
+			}
+
+			/// Drops the task in `slot`, if any.
+			fn remove_slot(&mut self, slot: usize) {
+				if self.vtables[slot].is_some() {
+					let task = unsafe { self.get_ptr_mut(slot) };
+					// Free the slot first, so a panicking destructor cannot cause a double
+					// drop.
+					self.vtables[slot] = None;
+					unsafe { core::ptr::drop_in_place(task) }
+				}
+			}
+
+			/// Returns a pointer to the task in `slot`.
+			unsafe fn get_ptr_mut(
+				&mut self,
+				slot: usize,
+			) -> *mut (dyn core::future::Future<Output = ()> + 'a) {
+				let data = self.store[slot].as_mut_ptr();
+				core::mem::transmute::<
+					$crate::DynParts,
+					*mut (dyn core::future::Future<Output = ()> + 'a),
+				>($crate::DynParts {
+					data,
+					vtable: self.vtables[slot].unwrap_unchecked(),
+				})
+			}
+		}
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::ReadyFlags;
+	use core::cell::{Cell, RefCell};
+	use core::future::{poll_fn, Future};
+	use core::pin::pin;
+	use core::task::{Poll, Waker};
+
+	dyntasks!(Tasks);
+
+	/// Completes after being polled `polls` times, waking itself in between.
+	fn count_down<'c>(polls: u32, log: &'c Cell<u32>) -> impl Future<Output = ()> + 'c {
+		let mut left = polls;
+		poll_fn(move |context| {
+			log.set(log.get() + 1);
+			left -= 1;
+			if left == 0 {
+				Poll::Ready(())
+			} else {
+				context.waker().wake_by_ref();
+				Poll::Pending
+			}
+		})
+	}
+
+	#[test]
+	fn tasks_run_until_completion() {
+		static READY: ReadyFlags<2> = ReadyFlags::new();
+		let log = Cell::new(0);
+		let mut tasks = pin!(Tasks::<64, 2>::new(&READY));
+		tasks.as_mut().spawn(count_down(1, &log)).ok().unwrap();
+		tasks.as_mut().spawn(count_down(3, &log)).ok().unwrap();
+		assert!(tasks.as_mut().spawn(count_down(1, &log)).is_err());
+
+		assert!(tasks.as_mut().poll_ready_tasks() == 2);
+		assert!(tasks.len() == 1);
+		while !tasks.is_empty() {
+			tasks.as_mut().poll_ready_tasks();
+		}
+		assert!(log.get() == 4 && !READY.any());
+	}
+
+	#[test]
+	fn only_woken_tasks_are_polled() {
+		static READY: ReadyFlags<4> = ReadyFlags::new();
+		let waker = RefCell::new(None::<Waker>);
+		let done = Cell::new(false);
+		let mut tasks = pin!(Tasks::<64, 4>::new(&READY));
+		tasks
+			.as_mut()
+			.spawn(poll_fn(|context| {
+				if done.get() {
+					return Poll::Ready(());
+				}
+				*waker.borrow_mut() = Some(context.waker().clone());
+				Poll::Pending
+			}))
+			.ok()
+			.unwrap();
+
+		assert!(tasks.as_mut().poll_ready_tasks() == 1);
+		assert!(tasks.as_mut().poll_ready_tasks() == 0);
+		done.set(true);
+		waker.take().unwrap().wake();
+		assert!(READY.any());
+		assert!(tasks.as_mut().poll_ready_tasks() == 1);
+		assert!(tasks.is_empty());
+	}
+}
@@ -0,0 +1,355 @@
+#[allow(unused_macros)]
+#[macro_export]
+/// Generates a fixed-capacity vector of values implementing a certain trait,
+/// which may be of different types. All values are packed into one inline
+/// buffer, so the vector never allocates.
+/// Example:
+/// ```
+/// use dyn_box::dynvec;
+/// trait MyTrait { fn foo(&self) -> u32; }
+/// impl MyTrait for u8 { fn foo(&self) -> u32 { *self as u32 } }
+/// impl MyTrait for u64 { fn foo(&self) -> u32 { *self as u32 } }
+/// dynvec!(MyVec: MyTrait);
+/// let mut my_vec = MyVec::<64, 4>::new();
+/// my_vec.push(1u8);
+/// my_vec.push(2u64);
+/// assert!(my_vec.iter().map(|value| value.foo()).sum::<u32>() == 3);
+/// ```
+macro_rules! dynvec {
+	($name:ident : $trait:path) => {
+		/// Vector for a given Trait, holding up to `N` implementors of the trait,
+		/// which may be of different types. Their contents are packed into one inline
+		/// buffer of `BYTES` bytes, aligned to 16 bytes, which also limits the
+		/// alignment of the values. Generated through the `dynvec!` macro
+		pub struct $name<'a, const BYTES: usize, const N: usize> {
+			store: $crate::Storage<BYTES>,
+			offsets: [usize; N],
+			vtables: [core::ptr::NonNull<()>; N],
+			len: usize,
+			used: usize,
+			// Neither `Send` nor `Sync`, as the content may not be.
+			_content: core::marker::PhantomData<(&'a (), *const ())>,
+		}
+
+		// The vtables are obtained by transmuting `*const dyn $trait` to two words.
+		const _: () = assert!(
+			core::mem::size_of::<*const dyn $trait>() == 2 * core::mem::size_of::<usize>(),
+			"trait object pointers are not a data pointer and a vtable pointer"
+		);
+
+		impl<const BYTES: usize, const N: usize> Drop for $name<'_, BYTES, N> {
+			fn drop(&mut self) {
+				self.clear();
+			}
+		}
+
+		impl<const BYTES: usize, const N: usize> Default for $name<'_, BYTES, N> {
+			fn default() -> Self {
+				Self::new()
+			}
+		}
+
+		impl<'a, const BYTES: usize, const N: usize> $name<'a, BYTES, N> {
+			/// Creates a new empty vector.
+			pub const fn new() -> $name<'a, BYTES, N> {
+				$name {
+					store: $crate::Storage::new(),
+					offsets: [0; N],
+					vtables: [core::ptr::NonNull::dangling(); N],
+					len: 0,
+					used: 0,
+					_content: core::marker::PhantomData,
+				}
+			}
+
+			/// Returns the number of values.
+			pub const fn len(&self) -> usize {
+				self.len
+			}
+
+			/// Returns whether the vector holds no values.
+			pub const fn is_empty(&self) -> bool {
+				self.len == 0
+			}
+
+			/// Appends a value of some generic type which implements $trait. Panics if
+			/// there is no room for it, i.e. if the vector holds `N` values already, if
+			/// the value does not fit into the rest of the buffer, or if its alignment
+			/// exceeds 16 bytes.
+			#[track_caller]
+			pub fn push<T: $trait + 'a>(&mut self, content: T) {
+				assert!(self.try_push(content).is_ok(), "no room in the DynVec");
+			}
+
+			/// Appends a value like `push()`, but gives it back instead of panicking if
+			/// there is no room for it.
+			pub fn try_push<T: $trait + 'a>(&mut self, content: T) -> Result<(), T> {
+				let size = core::mem::size_of::<T>();
+				let align = core::mem::align_of::<T>();
+				let offset = self.used.next_multiple_of(align);
+				if self.len == N
+					|| align > core::mem::align_of::<$crate::Storage<BYTES>>()
+					|| offset + size > BYTES
+				{
+					return Err(content);
+				}
+
+				let parts = unsafe {
+					core::mem::transmute::<*const dyn $trait, $crate::DynParts>(
+						&content as *const dyn $trait,
+					)
+				};
+				unsafe { (self.store.as_mut_ptr().add(offset) as *mut T).write(content) }
+				self.offsets[self.len] = offset;
+				self.vtables[self.len] = parts.vtable;
+				self.len += 1;
+				self.used = offset + size;
+				Ok(())
+			}
+
+			/// Drops the last value, if any. Returns whether there was one.
+			pub fn pop(&mut self) -> bool {
+				if self.len == 0 {
+					return false;
+				}
+
+				// Remove the value first, so a panicking destructor cannot cause a
+				// double drop.
+				self.len -= 1;
+				self.used = self.offsets[self.len];
+				unsafe { core::ptr::drop_in_place(self.get_ptr_mut(self.len)) }
+				true
+			}
+
+			/// Drops all values, last to first.
+			pub fn clear(&mut self) {
+				while self.pop() {}
+			}
+
+			/// Returns a `&dyn Trait` reference to the value at `index`, or None if
+			/// `index` is out of range.
+			pub fn get(&self, index: usize) -> Option<&dyn $trait> {
+				if index < self.len {
+					Some(unsafe { &*self.get_ptr(index) })
+				} else {
+					None
+				}
+			}
+
+			/// Returns a `&mut dyn Trait` reference to the value at `index`, or None if
+			/// `index` is out of range.
+			pub fn get_mut(&mut self, index: usize) -> Option<&mut dyn $trait> {
+				if index < self.len {
+					Some(unsafe { &mut *self.get_ptr_mut(index) })
+				} else {
+					None
+				}
+			}
+
+			/// Returns an iterator over the values, in order.
+			pub fn iter(&self) -> impl Iterator<Item = &dyn $trait> {
+				(0..self.len).map(move |index| unsafe { &*self.get_ptr(index) })
+			}
+
+			/// Returns an iterator over mutable references to the values, in order.
+			pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut dyn $trait> {
+				let data = self.store.as_mut_ptr();
+				let vtables = &self.vtables[..self.len];
+				self.offsets[..self.len]
+					.iter()
+					.zip(vtables)
+					.map(move |(&offset, &vtable)| unsafe {
+						&mut *Self::content_ptr(data.add(offset), vtable)
+					})
+			}
+
+			/// Keeps only the values for which `f` returns true, dropping the others.
+			/// The remaining values are moved together, keeping their order.
+			pub fn retain(&mut self, mut f: impl FnMut(&dyn $trait) -> bool) {
+				let len = self.len;
+				// Values are moved out of the vector while retaining them, so if `f` or a
+				// destructor panics, the unvisited ones are leaked instead of dropped
+				// twice.
+				self.len = 0;
+				self.used = 0;
+				// Both the values and their new places are derived from one pointer, as
+				// taking a new one would invalidate the others.
+				let data = self.store.as_mut_ptr();
+				for index in 0..len {
+					let content = unsafe {
+						Self::content_ptr(data.add(self.offsets[index]), self.vtables[index])
+					};
+					if f(unsafe { &*content }) {
+						let (size, align) = unsafe {
+							(
+								core::mem::size_of_val(&*content),
+								core::mem::align_of_val(&*content),
+							)
+						};
+						// Never past the value's previous offset, as that is aligned too.
+						let offset = self.used.next_multiple_of(align);
+						unsafe { data.add(offset).copy_from(content as *mut u8, size) }
+						self.offsets[self.len] = offset;
+						self.vtables[self.len] = self.vtables[index];
+						self.len += 1;
+						self.used = offset + size;
+					} else {
+						unsafe { core::ptr::drop_in_place(content) }
+					}
+				}
+			}
+
+			/// Returns a pointer to the value at `index`, which is only valid for
+			/// reads.
+			unsafe fn get_ptr(&self, index: usize) -> *const (dyn $trait + 'a) {
+				let data = self.store.as_ptr() as *mut u8;
+				Self::content_ptr(data.add(self.offsets[index]), self.vtables[index])
+			}
+
+			/// Returns a pointer to the value at `index`, which is also valid for
+			/// writes.
+			unsafe fn get_ptr_mut(&mut self, index: usize) -> *mut (dyn $trait + 'a) {
+				let data = self.store.as_mut_ptr();
+				Self::content_ptr(data.add(self.offsets[index]), self.vtables[index])
+			}
+
+			/// Builds a pointer to the value at `data` with the given vtable.
+			unsafe fn content_ptr<'b>(
+				data: *mut u8,
+				vtable: core::ptr::NonNull<()>,
+			) -> *mut (dyn $trait + 'b) {
+				core::mem::transmute::<$crate::DynParts, *mut (dyn $trait + 'b)>($crate::DynParts {
+					data,
+					vtable,
+				})
+			}
+		}
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	use core::cell::Cell;
+
+	pub trait MyTrait {
+		fn foo(&self) -> u32;
+		fn set_foo(&mut self, foo: u32);
+	}
+
+	struct Small(u8);
+	struct B(u128);
+	struct Counted<'a>(u32, &'a Cell<u32>);
+
+	impl MyTrait for Small {
+		fn foo(&self) -> u32 {
+			self.0 as u32
+		}
+		fn set_foo(&mut self, foo: u32) {
+			self.0 = foo as u8;
+		}
+	}
+	impl MyTrait for B {
+		fn foo(&self) -> u32 {
+			self.0 as u32
+		}
+		fn set_foo(&mut self, foo: u32) {
+			self.0 = foo as u128;
+		}
+	}
+	impl MyTrait for Counted<'_> {
+		fn foo(&self) -> u32 {
+			self.0
+		}
+		fn set_foo(&mut self, foo: u32) {
+			self.0 = foo;
+		}
+	}
+	impl Drop for Counted<'_> {
+		fn drop(&mut self) {
+			self.1.set(self.1.get() + 1);
+		}
+	}
+
+	dynvec!(DynVec: MyTrait);
+
+	#[test]
+	fn push_packs_values_of_different_types() {
+		let mut vec = DynVec::<48, 4>::new();
+		assert!(vec.is_empty());
+		vec.push(Small(1));
+		vec.push(B(2));
+		vec.push(Small(3));
+
+		assert!(vec.len() == 3);
+		assert!(vec.get(0).unwrap().foo() == 1);
+		assert!(vec.get(1).unwrap().foo() == 2);
+		assert!(vec.get_mut(2).unwrap().foo() == 3);
+		assert!(vec.get(3).is_none());
+	}
+
+	#[test]
+	fn try_push_gives_back_value_without_room() {
+		let mut vec = DynVec::<32, 2>::new();
+		vec.push(Small(1));
+		assert!(vec.try_push(B(2)).is_ok());
+		assert!(vec.try_push(Small(3)).err().unwrap().0 == 3);
+
+		let mut vec = DynVec::<24, 4>::new();
+		vec.push(Small(1));
+		assert!(vec.try_push(B(2)).err().unwrap().0 == 2);
+		assert!(vec.len() == 1);
+	}
+
+	#[test]
+	fn pop_drops_last_value() {
+		let drops = Cell::new(0);
+		let mut vec = DynVec::<32, 4>::new();
+		vec.push(Counted(1, &drops));
+		vec.push(Counted(2, &drops));
+		assert!(vec.pop());
+		assert!(drops.get() == 1);
+		assert!(vec.len() == 1 && vec.get(0).unwrap().foo() == 1);
+		assert!(vec.pop());
+		assert!(!vec.pop());
+		assert!(drops.get() == 2);
+	}
+
+	#[test]
+	fn iter_walks_values_at_their_offsets() {
+		let mut vec = DynVec::<48, 4>::new();
+		vec.push(Small(1));
+		vec.push(B(2));
+		vec.push(Small(3));
+		assert!(vec.iter().map(|value| value.foo()).sum::<u32>() == 6);
+	}
+
+	#[test]
+	fn iter_mut_modifies_values_in_order() {
+		let mut vec = DynVec::<48, 4>::new();
+		vec.push(Small(1));
+		vec.push(B(2));
+		for (index, value) in vec.iter_mut().enumerate() {
+			value.set_foo(value.foo() + index as u32 * 10);
+		}
+		assert!(vec.iter().map(|value| value.foo()).eq([1, 12]));
+	}
+
+	#[test]
+	fn retain_drops_and_compacts() {
+		let drops = Cell::new(0);
+		let mut vec = DynVec::<64, 4>::new();
+		vec.push(Counted(1, &drops));
+		vec.push(Small(2));
+		vec.push(B(3));
+		vec.push(Counted(4, &drops));
+
+		vec.retain(|value| value.foo() != 1 && value.foo() != 3);
+		assert!(drops.get() == 1);
+		assert!(vec.iter().map(|value| value.foo()).eq([2, 4]));
+
+		vec.push(B(5));
+		assert!(vec.get(2).unwrap().foo() == 5);
+		drop(vec);
+		assert!(drops.get() == 2);
+	}
+}
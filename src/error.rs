@@ -0,0 +1,110 @@
+/// Error returned when accessing the content of an empty container.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EmptyError;
+
+impl core::fmt::Display for EmptyError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		f.write_str("container is empty")
+	}
+}
+
+impl core::error::Error for EmptyError {}
+
+/// Error returned when moving content into a container too small to hold it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TooBig {
+	size: usize,
+	capacity: usize,
+}
+
+impl TooBig {
+	#[doc(hidden)]
+	pub fn new(size: usize, capacity: usize) -> TooBig {
+		TooBig { size, capacity }
+	}
+
+	/// Returns the size of the content.
+	pub fn size(&self) -> usize {
+		self.size
+	}
+
+	/// Returns the size the destination can hold.
+	pub fn capacity(&self) -> usize {
+		self.capacity
+	}
+}
+
+impl core::fmt::Display for TooBig {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		write!(f, "size {} > capacity {}", self.size, self.capacity)
+	}
+}
+
+impl core::error::Error for TooBig {}
+
+/// Error returned when a value does not fit into a container, because it
+/// exceeds the container's size or alignment. Gives the value back.
+pub struct SetError<T> {
+	value: T,
+	capacity: usize,
+	max_align: usize,
+}
+
+impl<T> SetError<T> {
+	#[doc(hidden)]
+	pub fn new(value: T, capacity: usize, max_align: usize) -> SetError<T> {
+		SetError {
+			value,
+			capacity,
+			max_align,
+		}
+	}
+
+	/// Returns the value which did not fit.
+	pub fn into_inner(self) -> T {
+		self.value
+	}
+
+	/// Returns the size of the value.
+	pub fn size(&self) -> usize {
+		core::mem::size_of::<T>()
+	}
+
+	/// Returns the size the container can hold.
+	pub fn capacity(&self) -> usize {
+		self.capacity
+	}
+
+	/// Returns the alignment the value requires.
+	pub fn align(&self) -> usize {
+		core::mem::align_of::<T>()
+	}
+
+	/// Returns the alignment the container provides.
+	pub fn max_align(&self) -> usize {
+		self.max_align
+	}
+}
+
+impl<T> core::fmt::Debug for SetError<T> {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		f.debug_struct("SetError")
+			.field("size", &self.size())
+			.field("capacity", &self.capacity)
+			.field("align", &self.align())
+			.field("max_align", &self.max_align)
+			.finish_non_exhaustive()
+	}
+}
+
+impl<T> core::fmt::Display for SetError<T> {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		if self.size() > self.capacity {
+			write!(f, "size {} > capacity {}", self.size(), self.capacity)
+		} else {
+			write!(f, "alignment {} > {}", self.align(), self.max_align)
+		}
+	}
+}
+
+impl<T> core::error::Error for SetError<T> {}
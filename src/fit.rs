@@ -0,0 +1,70 @@
+/// Explanation of why a type does not fit into a container. Formatted at compile
+/// time, so that it can be handed out as `&'static str`.
+pub struct FitMessage {
+	bytes: [u8; 64],
+	len: usize,
+}
+
+impl FitMessage {
+	/// Describes the first violated limit, or nothing if the type fits.
+	pub const fn new(size: usize, capacity: usize, align: usize, max_align: usize) -> FitMessage {
+		let message = FitMessage {
+			bytes: [0; 64],
+			len: 0,
+		};
+		if size > capacity {
+			message
+				.push_str("size ")
+				.push_number(size)
+				.push_str(" > capacity ")
+				.push_number(capacity)
+		} else if align > max_align {
+			message
+				.push_str("alignment ")
+				.push_number(align)
+				.push_str(" > ")
+				.push_number(max_align)
+		} else {
+			message
+		}
+	}
+
+	/// Returns the message, or None if the type fits.
+	pub fn as_str(&'static self) -> Option<&'static str> {
+		if self.len == 0 {
+			None
+		} else {
+			core::str::from_utf8(&self.bytes[..self.len]).ok()
+		}
+	}
+
+	const fn push_str(mut self, s: &str) -> FitMessage {
+		let s = s.as_bytes();
+		let mut i = 0;
+		while i < s.len() {
+			self.bytes[self.len] = s[i];
+			self.len += 1;
+			i += 1;
+		}
+		self
+	}
+
+	const fn push_number(mut self, mut n: usize) -> FitMessage {
+		let mut digits = [0u8; 20];
+		let mut count = 0;
+		loop {
+			digits[count] = b'0' + (n % 10) as u8;
+			count += 1;
+			n /= 10;
+			if n == 0 {
+				break;
+			}
+		}
+		while count > 0 {
+			count -= 1;
+			self.bytes[self.len] = digits[count];
+			self.len += 1;
+		}
+		self
+	}
+}
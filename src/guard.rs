@@ -0,0 +1,54 @@
+use crate::DynContainer;
+
+/// Mutable handle to a container whose content it dereferences to, clearing
+/// the container when dropped. Obtained through the generated `set_scoped`
+/// method.
+pub struct BoxGuard<'a, B: DynContainer<T> + ?Sized, T: ?Sized> {
+	// A pointer for the same reason as in `OccupiedBox`.
+	inner: *mut B,
+	// Obtained from `inner` once, so that dereferencing cannot fail.
+	content: *mut T,
+	_inner: core::marker::PhantomData<&'a mut B>,
+}
+
+// Like the `&mut B` and `&mut T` it consists of.
+unsafe impl<B: DynContainer<T> + Send + ?Sized, T: Send + ?Sized> Send for BoxGuard<'_, B, T> {}
+unsafe impl<B: DynContainer<T> + Sync + ?Sized, T: Sync + ?Sized> Sync for BoxGuard<'_, B, T> {}
+
+impl<'a, B: DynContainer<T> + ?Sized, T: ?Sized> BoxGuard<'a, B, T> {
+	/// # Safety
+	/// `container.get_mut()` must return the content.
+	#[doc(hidden)]
+	pub unsafe fn new(container: &'a mut B) -> BoxGuard<'a, B, T> {
+		let inner = container as *mut B;
+		let content = (*inner).get_mut().unwrap_unchecked() as *mut T;
+		BoxGuard {
+			inner,
+			content,
+			_inner: core::marker::PhantomData,
+		}
+	}
+}
+
+impl<B: DynContainer<T> + ?Sized, T: ?Sized> core::ops::Deref for BoxGuard<'_, B, T> {
+	type Target = T;
+
+	fn deref(&self) -> &T {
+		// SAFETY: The container stays borrowed until the guard clears it.
+		unsafe { &*self.content }
+	}
+}
+
+impl<B: DynContainer<T> + ?Sized, T: ?Sized> core::ops::DerefMut for BoxGuard<'_, B, T> {
+	fn deref_mut(&mut self) -> &mut T {
+		// SAFETY: As above, and `self` is borrowed mutably.
+		unsafe { &mut *self.content }
+	}
+}
+
+impl<B: DynContainer<T> + ?Sized, T: ?Sized> Drop for BoxGuard<'_, B, T> {
+	fn drop(&mut self) {
+		// SAFETY: The container is borrowed for `'a`.
+		unsafe { (*self.inner).clear() }
+	}
+}
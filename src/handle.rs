@@ -0,0 +1,46 @@
+/// Index of a container in a slice, together with the container's generation
+/// at the time its content was stored. Obtained through the generated `set_at`
+/// method of containers with the `generation` option, or through the `insert`
+/// method of pools generated by [`dynarena!`](crate::dynarena).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Handle {
+	index: usize,
+	generation: usize,
+}
+
+impl Handle {
+	#[doc(hidden)]
+	pub fn new(index: usize, generation: usize) -> Handle {
+		Handle { index, generation }
+	}
+
+	/// Returns the index of the container in its slice.
+	pub fn index(&self) -> usize {
+		self.index
+	}
+
+	/// Returns the generation of the container when the handle was obtained.
+	pub fn generation(&self) -> usize {
+		self.generation
+	}
+}
+
+/// Lets the content of a container be marked as changed in place. Obtained
+/// through the generated `split_mut` method of containers with the `generation`
+/// option.
+pub struct GenerationBump<'g> {
+	generation: &'g mut usize,
+}
+
+impl GenerationBump<'_> {
+	#[doc(hidden)]
+	pub fn new(generation: &mut usize) -> GenerationBump<'_> {
+		GenerationBump { generation }
+	}
+
+	/// Increments the container's generation, so that handles obtained before no
+	/// longer refer to its content.
+	pub fn bump(self) {
+		*self.generation = self.generation.wrapping_add(1);
+	}
+}
@@ -0,0 +1,27 @@
+//! Type-erased functions captured at `set` time by the generated containers.
+
+/// Clones the `T` at `src` into the uninitialized memory at `dst`.
+///
+/// # Safety
+/// `src` must point to a valid `T`, and `dst` must be valid for writing a `T`.
+pub unsafe fn clone_into<T: Clone>(src: *const u8, dst: *mut u8) {
+	dst.cast::<T>().write((*src.cast::<T>()).clone());
+}
+
+/// Moves the closure `T` out of `src`, calls it and writes its result to the
+/// uninitialized memory at `dst`.
+///
+/// # Safety
+/// `src` must point to a valid `T`, which must not be used afterwards, and `dst`
+/// must be valid for writing an `R`.
+pub unsafe fn call_once_into<T: FnOnce() -> R, R>(src: *mut u8, dst: *mut u8) {
+	dst.cast::<R>().write(src.cast::<T>().read()());
+}
+
+/// Reports the `T` at `src` to `visitor`.
+///
+/// # Safety
+/// `src` must point to a valid `T`.
+pub unsafe fn visit_with<T: crate::Visit>(src: *const u8, visitor: &mut dyn crate::Visitor) {
+	(*src.cast::<T>()).visit(visitor);
+}
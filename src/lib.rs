@@ -1,8 +1,298 @@
 #![no_std]
 
+#[cfg(feature = "alloc")]
+#[doc(hidden)]
+pub extern crate alloc as __alloc;
+
+mod cell;
+mod checksum;
+mod dynarena;
+mod dyncell;
+mod dynerror;
+mod dynfn;
+mod dyniter;
+mod dynmap;
+mod dynmulti;
+mod dynqueue;
+mod dynrc;
+mod dynref;
+mod dynrefcell;
+mod dynslice;
+mod dynslots;
+mod dynswap;
+mod dyntasks;
+mod dynvec;
+mod error;
+mod fit;
+mod guard;
+mod handle;
+mod hooks;
+mod occupied;
+mod parts;
+mod proven;
+mod queue;
+mod raw;
+mod storage;
+mod swap;
+mod tasks;
+mod visit;
+
+pub use cell::CriticalSection;
+#[cfg(feature = "critical-section")]
+pub use cell::GlobalCriticalSection;
+#[doc(hidden)]
+pub use checksum::checksum;
+/// Registers an implementor with a `#[dynbox_registry]` DynBox. Requires the
+/// `macros` feature.
+#[cfg(feature = "macros")]
+pub use dyn_box_macros::dynbox_impl;
+/// Sizes a DynBox to the implementors registered with `#[dynbox_impl]`.
+/// Requires the `macros` feature.
+/// Example:
+/// ```
+/// use dyn_box::dynbox_registry;
+/// pub trait Sensor { fn read(&self) -> u32; }
+///
+/// #[dynbox_registry(SensorBox: Sensor)]
+/// mod sensors {
+///     use super::Sensor;
+///
+///     #[dynbox_impl(SensorBox)]
+///     pub struct Fixed(pub u32);
+///     impl Sensor for Fixed { fn read(&self) -> u32 { self.0 } }
+///
+///     #[dynbox_impl(SensorBox)]
+///     #[derive(Clone)]
+///     pub struct Pair(pub u64, pub u64);
+///     impl Sensor for Pair { fn read(&self) -> u32 { (self.0 + self.1) as u32 } }
+/// }
+///
+/// fn main() {
+///     let mut sensor = sensors::SensorBox::new_default_size();
+///     sensor.set(sensors::Pair(1, 2));
+///     assert!(sensor.get().unwrap().read() == 3);
+/// }
+/// ```
+/// A registered type which exceeds `max_size` fails to compile, with an error
+/// naming it:
+/// ```compile_fail
+/// use dyn_box::dynbox_registry;
+/// pub trait Sensor {}
+///
+/// #[dynbox_registry(SensorBox: Sensor, max_size = 8)]
+/// mod sensors {
+///     use super::Sensor;
+///
+///     #[dynbox_impl(SensorBox)]
+///     pub struct Pair(pub u64, pub u64);
+///     impl Sensor for Pair {}
+/// }
+/// # fn main() {}
+/// ```
+#[cfg(feature = "macros")]
+pub use dyn_box_macros::dynbox_registry;
+pub use dynerror::DynError;
+pub use error::{EmptyError, SetError, TooBig};
+#[doc(hidden)]
+pub use fit::FitMessage;
+pub use guard::BoxGuard;
+pub use handle::{GenerationBump, Handle};
+#[doc(hidden)]
+pub use hooks::{call_once_into, clone_into, visit_with};
+pub use occupied::OccupiedBox;
+#[doc(hidden)]
+pub use parts::{DynParts, Vtable};
+pub use proven::ProvenSetter;
+#[doc(hidden)]
+pub use proven::SetProven;
+pub use queue::{Consumer, Popped, Producer};
+#[doc(hidden)]
+pub use queue::{QueuePop, QueuePush};
+pub use raw::RawHandle;
+#[doc(hidden)]
+pub use storage::{max, Align, Alignment, Storage};
+pub use swap::{Publisher, Reader};
+#[doc(hidden)]
+pub use swap::{SwapPublish, SwapRead};
+pub use tasks::ReadyFlags;
+pub use visit::{Value, Visit, Visitor};
+
+/// Common interface of the containers generated by [`dynbox!`], used by generic
+/// wrappers such as [`OccupiedBox`]. `T` is the trait object type held by the
+/// container, e.g. `dyn MyTrait`.
+pub trait DynContainer<T: ?Sized> {
+	/// Returns a reference to the content if not empty, or None otherwise.
+	fn get(&self) -> Option<&T>;
+
+	/// Returns a mutable reference to the content if not empty, or None otherwise.
+	fn get_mut(&mut self) -> Option<&mut T>;
+
+	/// Makes the container empty again by dropping the previous content, if any.
+	fn clear(&mut self);
+
+	/// Returns whether the container currently contains any value.
+	fn empty(&self) -> bool;
+}
+
+/// Capacity requirement of at least `N` bytes, implemented by the containers
+/// generated by [`dynbox!`] for every `N`. Generic code accesses the container
+/// through `at_least()`, which fails to compile if the capacity is too small:
+/// ```compile_fail
+/// use dyn_box::{dynbox, AtLeast};
+/// trait MyTrait {}
+/// dynbox!(MyDynBox: MyTrait);
+/// fn store_widget(dynbox: &mut impl AtLeast<64>) {
+///     dynbox.at_least();
+/// }
+/// store_widget(&mut MyDynBox::<32>::new());
+/// ```
+///
+/// Stable Rust cannot bound the impl on the capacity, so the check is only
+/// evaluated after monomorphisation: `impl AtLeast<64>` is accepted for any
+/// container, and the error appears where `at_least()` is instantiated for a
+/// too small one, possibly deep inside generic code, and not under `cargo check`.
+pub trait AtLeast<const N: usize> {
+	/// Evaluates to `()` if the capacity is at least `N` bytes and fails to
+	/// compile otherwise.
+	#[doc(hidden)]
+	const FITS: ();
+
+	/// Returns the container itself, after checking its capacity at compile time.
+	#[inline(always)]
+	fn at_least(&mut self) -> &mut Self {
+		#[allow(clippy::let_unit_value)]
+		let () = Self::FITS;
+		self
+	}
+}
+
+#[cfg(feature = "alloc")]
+#[doc(hidden)]
+#[macro_export]
+/// Emits the given items only if the `alloc` feature is enabled.
+macro_rules! __if_alloc {
+	($($item:tt)*) => { $($item)* };
+}
+
+#[cfg(not(feature = "alloc"))]
+#[doc(hidden)]
+#[macro_export]
+/// Emits the given items only if the `alloc` feature is enabled.
+macro_rules! __if_alloc {
+	($($item:tt)*) => {};
+}
+
+#[doc(hidden)]
+#[macro_export]
+/// Emits the first block if the flag is contained in the list, or the optional
+/// `else` block otherwise.
+macro_rules! __dynbox_if {
+	(clone in [clone $($rest:ident)*] { $($then:tt)* } $(else { $($else:tt)* })?) => {
+		$($then)*
+	};
+	(visit in [visit $($rest:ident)*] { $($then:tt)* } $(else { $($else:tt)* })?) => {
+		$($then)*
+	};
+	(downcast in [downcast $($rest:ident)*] { $($then:tt)* } $(else { $($else:tt)* })?) => {
+		$($then)*
+	};
+	(pinned in [pinned $($rest:ident)*] { $($then:tt)* } $(else { $($else:tt)* })?) => {
+		$($then)*
+	};
+	(future in [future $($rest:ident)*] { $($then:tt)* } $(else { $($else:tt)* })?) => {
+		$($then)*
+	};
+	(copy in [copy $($rest:ident)*] { $($then:tt)* } $(else { $($else:tt)* })?) => {
+		$($then)*
+	};
+	(display in [display $($rest:ident)*] { $($then:tt)* } $(else { $($else:tt)* })?) => {
+		$($then)*
+	};
+	(defmt in [defmt $($rest:ident)*] { $($then:tt)* } $(else { $($else:tt)* })?) => {
+		$($then)*
+	};
+	(defmt_type_name in [defmt_type_name $($rest:ident)*] { $($then:tt)* } $(else { $($else:tt)* })?) => {
+		$($then)*
+	};
+	(debug in [debug $($rest:ident)*] { $($then:tt)* } $(else { $($else:tt)* })?) => {
+		$($then)*
+	};
+	(min_useful in [min_useful $($rest:ident)*] { $($then:tt)* } $(else { $($else:tt)* })?) => {
+		$($then)*
+	};
+	(stable_addr in [stable_addr $($rest:ident)*] { $($then:tt)* } $(else { $($else:tt)* })?) => {
+		$($then)*
+	};
+	(spill in [spill $($rest:ident)*] { $($then:tt)* } $(else { $($else:tt)* })?) => {
+		$($then)*
+	};
+	(generation in [generation $($rest:ident)*] { $($then:tt)* } $(else { $($else:tt)* })?) => {
+		$($then)*
+	};
+	(sort_key in [sort_key $($rest:ident)*] { $($then:tt)* } $(else { $($else:tt)* })?) => {
+		$($then)*
+	};
+	(eq_hash in [eq_hash $($rest:ident)*] { $($then:tt)* } $(else { $($else:tt)* })?) => {
+		$($then)*
+	};
+	(type_name in [type_name $($rest:ident)*] { $($then:tt)* } $(else { $($else:tt)* })?) => {
+		$($then)*
+	};
+	(checksum in [checksum $($rest:ident)*] { $($then:tt)* } $(else { $($else:tt)* })?) => {
+		$($then)*
+	};
+	(zeroize in [zeroize $($rest:ident)*] { $($then:tt)* } $(else { $($else:tt)* })?) => {
+		$($then)*
+	};
+	(no_panic in [no_panic $($rest:ident)*] { $($then:tt)* } $(else { $($else:tt)* })?) => {
+		$($then)*
+	};
+	(repr_c in [repr_c $($rest:ident)*] { $($then:tt)* } $(else { $($else:tt)* })?) => {
+		$($then)*
+	};
+	(on_move in [on_move $($rest:ident)*] { $($then:tt)* } $(else { $($else:tt)* })?) => {
+		$($then)*
+	};
+	(call_once in [call_once $($rest:ident)*] { $($then:tt)* } $(else { $($else:tt)* })?) => {
+		$($then)*
+	};
+	(send in [send $($rest:ident)*] { $($then:tt)* } $(else { $($else:tt)* })?) => {
+		$($then)*
+	};
+	(sync in [sync $($rest:ident)*] { $($then:tt)* } $(else { $($else:tt)* })?) => {
+		$($then)*
+	};
+	(typed in [typed $($rest:ident)*] { $($then:tt)* } $(else { $($else:tt)* })?) => {
+		$($then)*
+	};
+	($flag:ident in [$other:ident $($rest:ident)*] $then:tt $(else $else:tt)?) => {
+		$crate::__dynbox_if! { $flag in [$($rest)*] $then $(else $else)? }
+	};
+	($flag:ident in [] $then:tt $(else { $($else:tt)* })?) => {
+		$($($else)*)?
+	};
+}
+
+#[doc(hidden)]
+#[macro_export]
+/// Emits the item with `#[repr(C)]` if the `repr_c` flag is contained in the
+/// list, or unchanged otherwise.
+macro_rules! __dynbox_repr {
+	([$($flags:ident)*] $($item:tt)*) => {
+		$crate::__dynbox_if! { repr_c in [$($flags)*] {
+			#[repr(C)]
+			$($item)*
+		} else {
+			$($item)*
+		}}
+	};
+}
+
 #[allow(unused_macros)]
 #[macro_export]
-/// Generates a DynBox-like data structure that can hold a certain trait.
+/// Generates a DynBox-like data structure that can hold a certain trait, which
+/// may also be given by path and with generic arguments, like
+/// `other_crate::Trait` or `Iterator<Item = u32>`.
 /// Example:
 /// ```
 /// use dyn_box::dynbox;
@@ -10,202 +300,4630 @@
 /// dynbox!(MyDynBox: MyTrait);
 /// let my_dynbox = MyDynBox::<16>::new();
 /// ```
+///
+/// Unless `stable_addr` is used, `new()` is a `const fn` and `EMPTY` an empty
+/// DynBox, so that DynBoxes can be placed in statics:
+/// ```
+/// use dyn_box::dynbox;
+/// trait MyTrait {}
+/// dynbox!(MyDynBox: MyTrait, sync);
+/// static MY_DYNBOX: MyDynBox<16> = MyDynBox::EMPTY;
+/// ```
+///
+/// If the size is the same throughout a project, `sizes` names the box of that
+/// size, so that `SIZE` does not need to be spelled out everywhere:
+/// ```
+/// use dyn_box::dynbox;
+/// trait MyTrait { fn foo(&self) -> u32; }
+/// impl MyTrait for u32 { fn foo(&self) -> u32 { *self } }
+/// dynbox!(MyGenericDynBox: MyTrait, sizes = [MyDynBox = 64]);
+/// fn make(value: u32) -> MyDynBox<'static> {
+///     let mut my_dynbox = MyDynBox::new();
+///     my_dynbox.set(value);
+///     my_dynbox
+/// }
+/// assert!(make(42).get().unwrap().foo() == 42);
+/// ```
+///
+/// Non-`'static` values, like references, can be stored as well. The lifetime
+/// parameter of the generated box ensures it does not outlive them:
+/// ```compile_fail
+/// use dyn_box::dynbox;
+/// trait MyTrait { fn foo(&self) -> u32; }
+/// impl MyTrait for &u32 { fn foo(&self) -> u32 { **self } }
+/// dynbox!(MyDynBox: MyTrait);
+/// let mut my_dynbox = MyDynBox::<16>::new();
+/// {
+///     let value = 42;
+///     my_dynbox.set(&value);
+/// }
+/// my_dynbox.get();
+/// ```
+///
+/// Generic code can require a minimum capacity through `assert_capacity()`,
+/// which rejects too small boxes at compile time:
+/// ```compile_fail
+/// use dyn_box::dynbox;
+/// trait MyTrait {}
+/// dynbox!(MyDynBox: MyTrait);
+/// fn process<const N: usize>(dynbox: &mut MyDynBox<N>) {
+///     MyDynBox::<N>::assert_capacity::<32>();
+/// }
+/// process(&mut MyDynBox::<16>::new());
+/// ```
+///
+/// `set_checked()` rejects values exceeding the capacity or alignment at compile
+/// time:
+/// ```compile_fail
+/// use dyn_box::dynbox;
+/// trait MyTrait {}
+/// impl MyTrait for u64 {}
+/// dynbox!(MyDynBox: MyTrait);
+/// MyDynBox::<4>::new().set_checked(0u64);
+/// ```
+///
+/// If the trait has a method taking only `&self`, naming it after the trait
+/// generates `produce()`, which calls it on the content:
+/// ```
+/// use dyn_box::dynbox;
+/// trait Factory { fn make(&self) -> u32; }
+/// dynbox!(FactoryBox: Factory { fn make(&self) -> u32 });
+/// assert!(FactoryBox::<16>::new().produce().is_none());
+/// ```
+///
+/// A method taking `&mut self` instead generates a method of the same name,
+/// which must not clash with the generated ones, e.g. for driving futures:
+/// ```
+/// use core::task::Poll;
+/// use dyn_box::dynbox;
+/// trait MyFuture { fn poll(&mut self) -> Poll<u32>; }
+/// dynbox!(FutBox: MyFuture { fn poll(&mut self) -> Poll<u32> });
+/// assert!(FutBox::<16>::new().poll().is_none());
+/// ```
+///
+/// For `FnOnce() -> R`, `call_once()` moves the closure out and calls it,
+/// leaving the DynBox empty:
+/// ```
+/// use dyn_box::dynbox;
+/// dynbox!(OnceBox: FnOnce() -> u32);
+/// let mut once_box = OnceBox::<16>::new();
+/// once_box.set(|| 42);
+/// assert!(once_box.call_once() == Some(42));
+/// assert!(once_box.call_once().is_none());
+/// ```
+///
+/// Listing the types to be stored after `for` makes their largest size and
+/// alignment the defaults, like [`dynbox_for!`], so that plain `MyDynBox` can
+/// hold each of them and follows their sizes as they change. The trait has to
+/// be a single identifier here:
+/// ```
+/// use dyn_box::dynbox;
+/// trait Driver {}
+/// struct Uart([u8; 12]);
+/// struct Spi(u64);
+/// impl Driver for Uart {}
+/// impl Driver for Spi {}
+/// dynbox!(DriverBox: Driver for [Uart, Spi]);
+/// let mut driver = DriverBox::new_default_size();
+/// driver.set_checked(Uart([0; 12]));
+/// driver.set_checked(Spi(0));
+/// ```
+///
+/// Options may follow the trait, separated by commas:
+/// - `default_size = N`: Makes `N` the default for `SIZE`, so that plain
+///   `MyDynBox` names a box of that size, and adds `new_default_size()`.
+///   (The trait itself cannot carry the size as an associated constant, since
+///   that would make it dyn-incompatible.)
+/// - `align = N`: Makes `N` the default for `ALIGN`, instead of 16 bytes.
+/// - `align_for = [A, B, ...]`: Makes the largest alignment of the listed types
+///   the default for `ALIGN`, instead of 16 bytes.
+/// - `clone`: Requires stored values to implement `Clone` and captures their
+///   clone function, adding `clone_to()` and implementing `Clone`.
+/// - `visit`: Requires stored values to implement [`Visit`] and captures their
+///   `visit()` function, adding `visit()`, which reports the content's state to
+///   a [`Visitor`].
+/// - `send`, `sync`: Require stored values to be `Send` or `Sync`, respectively,
+///   and make the DynBox `Send` or `Sync` in turn. Without them, it is neither:
+///   ```compile_fail
+///   use dyn_box::dynbox;
+///   trait MyTrait {}
+///   dynbox!(MyDynBox: MyTrait);
+///   fn assert_send<T: Send>(_: T) {}
+///   assert_send(MyDynBox::<16>::new());
+///   ```
+/// - `downcast`: Requires stored values to be `'static` and records their
+///   `TypeId`, adding `is()`, `downcast_ref()`, `downcast_mut()`, `map()`,
+///   `take()`, `take_and_replace_with()`, `try_into_inner()`, `update()`,
+///   `clear_of_type()` and `PartialEq<T>` against values of a concrete type.
+///   There is no getter returning a `Cow<T>`, as a stored `T` can always be
+///   borrowed: `downcast_ref()` wrapped in `Cow::Borrowed` serves.
+/// - `copy`: Requires stored values to implement `Copy`, and makes the DynBox
+///   itself `Copy` (and `Clone`) in turn. Content whose type is not known, as
+///   taken by `try_from_box()` or `set_from_dyn()`, cannot be checked for that,
+///   so these are not available:
+///   ```compile_fail
+///   extern crate alloc;
+///   use dyn_box::dynbox;
+///   trait MyTrait {}
+///   impl MyTrait for u32 {}
+///   dynbox!(MyDynBox: MyTrait, copy);
+///   let boxed: alloc::boxed::Box<dyn MyTrait> = alloc::boxed::Box::new(1u32);
+///   let my_dynbox = MyDynBox::<16>::try_from_box(boxed);
+///   ```
+/// - `display`: Implements `Display` by forwarding to the content, or printing
+///   `<empty>` if there is none. Requires `Display` to be a supertrait of the
+///   trait.
+/// - `debug`: Implements `Debug` by forwarding to the content, or printing
+///   `<empty>` if there is none. Requires `Debug` to be a supertrait of the
+///   trait.
+/// - `defmt`: Implements `defmt::Format` by forwarding to the content, or
+///   printing `<empty>` if there is none, for logging with `defmt`. Requires
+///   `defmt::Format` to be a supertrait of the trait, and the crate invoking
+///   the macro to depend on `defmt`.
+/// - `defmt_type_name`: Implements `defmt::Format` for traits which do not
+///   extend it, printing the name of the stored type, `<occupied>` if it is
+///   unknown, or `<empty>`. Implies `type_name`, and cannot be combined with
+///   `defmt`.
+/// - `min_useful`: Rejects boxes smaller than a `usize` at compile time, as they
+///   can hold zero-sized types only:
+///   ```compile_fail
+///   use dyn_box::dynbox;
+///   trait MyTrait {}
+///   dynbox!(MyDynBox: MyTrait, min_useful);
+///   let my_dynbox = MyDynBox::<4>::new();
+///   ```
+/// - `stable_addr`: Allocates the store on the heap once, so that the content
+///   keeps its address when the DynBox is moved. Requires the `alloc` feature.
+/// - `spill`: Moves content which does not fit into `SIZE` or `ALIGN` to a heap
+///   allocation instead of panicking, and keeps a pointer to it in the store,
+///   which therefore needs to hold at least a `usize`. `get()` and the like
+///   work the same for both. Cannot be combined with `copy`. Requires the
+///   `alloc` feature.
+/// - `generation`: Counts changes of the content, and adds `raw_handle()` and
+///   `resolve_raw()` for handles which detect stale uses in debug builds, as
+///   well as `set_at()` and `get_by_handle()` for checked handles into slices
+///   of DynBoxes, and `split_mut()` for bumping the generation by hand.
+/// - `pinned`: Makes the DynBox `!Unpin`, so that self-referential content can
+///   be stored. Once pinned, the content is only accessible mutably through
+///   `get_pin_mut()`, and can be replaced through `set_pinned()`. Pinning is
+///   structural: a pinned DynBox never moves its content, which stays in place
+///   until it is dropped by `set_pinned()` or when the DynBox is dropped. The
+///   DynBox itself must be pinned for this, as an unpinned one moves its content
+///   along with it.
+/// - `future`: Implements `Future` by polling the content through
+///   `get_pin_mut()`, for traits which are or extend `Future`. Implies `pinned`.
+///   Polling an empty DynBox panics, unless `no_panic` is used.
+/// - `checksum`: Adds `seal()` and `verify()`, which record and check a
+///   checksum over the content and its vtable to detect memory corruption.
+/// - `zeroize`: Overwrites the content's bytes with zeros after dropping it in
+///   `clear()`, and thus in `set()` and on drop, e.g. for key material. The
+///   writes are volatile, so they are not optimized away. Content moved out,
+///   e.g. by `take()` or `move_into()`, leaves its bytes behind unwiped.
+/// - `type_name`: Records the name of the stored type, adding `type_name()` for
+///   logging.
+/// - `sort_key`: Adds `sort_key()`, forwarding to a `fn dyn_sort_key(&self) ->
+///   u64` method of the trait, for use with `sort_by_key()`. Empty DynBoxes
+///   return `u64::MAX` and thus sort last.
+/// - `eq_hash`: Implements `PartialEq` and `Hash` by forwarding to `fn
+///   dyn_eq(&self, other: &dyn Trait) -> bool` and `fn dyn_hash(&self, state:
+///   &mut dyn Hasher)` methods of the trait. Empty DynBoxes are only equal to
+///   empty ones.
+/// - `on_move`: Calls a `fn on_move(&mut self)` method of the trait whenever the
+///   content was moved, so that self-referential content can fix up its
+///   pointers. This happens when storing the content, and in `get_mut()` if the
+///   DynBox was moved since; in debug builds, `get()` panics in that case.
+/// - `repr_c`: Lays the DynBox out like a `#[repr(C)]` struct, with the store
+///   first and the vtable word last, so that two builds of the same firmware,
+///   e.g. on two cores, agree on it in shared memory. The fields of other
+///   options and, in debug builds, a vtable check word lie in between. `SIZE`
+///   must be a multiple of the size of a `usize`, so that no padding follows the
+///   store. Cannot be combined with `stable_addr` or `spill`.
+/// - `no_panic`: Replaces the panics of the generated methods on misuse by
+///   compile-time checks or other ways of reporting errors: storing a value
+///   which does not fit fails to compile, while `try_set()` still returns it as
+///   an error, and methods which can only report errors by panicking, like
+///   `expect()`, `emplace_with()` or the raw parts conversions, are not
+///   generated. `set_at()` gives the value back if the index is out of range,
+///   `delegate` methods need a default, and an empty future box stays pending
+///   when polled. Debug assertions and panics in the content's own methods
+///   remain. CI links such boxes into a `panic = "abort"` firmware image for
+///   thumbv6m whose panic handler is undefined, see `ci/no-panic`.
+///   ```compile_fail
+///   use dyn_box::dynbox;
+///   trait MyTrait {}
+///   impl MyTrait for u64 {}
+///   dynbox!(MyDynBox: MyTrait, no_panic);
+///   MyDynBox::<4>::new().set(1u64);
+///   ```
+/// - `sizes = [Alias = N, ...]`: Generates type aliases for boxes of the given
+///   sizes, e.g. `pub type Alias = MyDynBox<N>;`.
+/// - `upcast = [get_as_super: SuperTrait, ...]`: Generates accessors returning
+///   the content as `&dyn SuperTrait` for supertraits of the trait, so that boxes
+///   of different traits can be viewed through a common supertrait.
+/// - `delegate { fn method(&self, arg: Type) -> Ret = default; ... }`: Implements
+///   the trait on the box itself by forwarding the listed methods, which take
+///   `&self` or `&mut self`, to the content, so that the box can be passed where a
+///   `&dyn Trait` is expected. If empty, a method returns its `= default`, or
+///   panics if none is given, which fails to compile with `no_panic`.
+/// - `ops = MyDynBoxOps`: Generates a trait of that name, which extends
+///   [`DynContainer`] by `set()`, so that code can be written against it instead
+///   of a concrete box.
 macro_rules! dynbox {
-	($name:ident : $trait:ident) => {
-		#[repr(align(16))]
-		/// DynBox for a given Trait with `Option<dyn Trait>`-like semantics.
-		/// Can hold implementors of the trait if they do not exceed `SIZE`, and
-		/// allows to retrieve `&dyn Trait` and `&mut dyn Trait` references.
-		/// Generated through the `dynbox!` macro
-		pub struct $name<const SIZE: usize> {
-			store: [u8; SIZE],
-			vtable: usize,
-		}
-
-		impl<const SIZE: usize> Drop for $name<SIZE> {
-			fn drop(&mut self) {
-				self.clear();
+	($name:ident : $trait:ident for [$($type:ty),+ $(,)?] $(, $($options:tt)*)?) => {
+		$crate::dynbox_for!($name: $trait; $($type),+ $(; $($options)*)?);
+	};
+	($name:ident : $trait:path { fn $method:ident(&self) -> $ret:ty $(;)? } $(, $($options:tt)*)?) => {
+		$crate::dynbox!($name: $trait $(, $($options)*)?);
+
+		impl<'a, const SIZE: usize, const ALIGN: usize> $name<'a, SIZE, ALIGN>
+		where
+			$crate::Align<ALIGN>: $crate::Alignment,
+		{
+			#[doc = concat!("Calls `", stringify!($method), "()` on the content if not empty.")]
+			pub fn produce(&self) -> Option<$ret> {
+				self.get().map(|content| content.$method())
+			}
+		}
+	};
+	($name:ident : $trait:path { fn $method:ident(&mut self) -> $ret:ty $(;)? } $(, $($options:tt)*)?) => {
+		$crate::dynbox!($name: $trait $(, $($options)*)?);
+
+		impl<'a, const SIZE: usize, const ALIGN: usize> $name<'a, SIZE, ALIGN>
+		where
+			$crate::Align<ALIGN>: $crate::Alignment,
+		{
+			#[doc = concat!("Calls `", stringify!($method), "()` on the content if not empty.")]
+			pub fn $method(&mut self) -> Option<$ret> {
+				self.get_mut().map(|content| content.$method())
+			}
+		}
+	};
+	($name:ident : FnOnce() -> $ret:ty $(, $($options:tt)*)?) => {
+		$crate::dynbox!(@options $name, FnOnce() -> $ret, [] [16] [call_once typed] [] [], $($($options)*)?);
+
+		impl<'a, const SIZE: usize, const ALIGN: usize> $name<'a, SIZE, ALIGN>
+		where
+			$crate::Align<ALIGN>: $crate::Alignment,
+		{
+			/// Moves the closure out and calls it, leaving the DynBox empty. Returns
+			/// None if it was empty already.
+			pub fn call_once(&mut self) -> Option<$ret> {
+				if self.empty() {
+					return None;
+				}
+
+				self.check_vtable();
+				// SAFETY: `call_once` implies `typed`, so all content records the hook.
+				let call_fn = unsafe { self.call_fn.unwrap_unchecked() };
+				let layout = core::alloc::Layout::for_value(unsafe { &*self.get_ptr() });
+				let mut result = core::mem::MaybeUninit::<$ret>::uninit();
+				// Mark as empty first, as the closure is consumed even if it panics.
+				self.vtable = $crate::Vtable::EMPTY;
+				self.next_generation();
+				unsafe {
+					call_fn(self.data_ptr_mut(), result.as_mut_ptr() as *mut u8);
+					self.release(layout);
+					Some(result.assume_init())
+				}
+			}
+		}
+	};
+	($name:ident : $trait:path $(, $($options:tt)*)?) => {
+		$crate::dynbox!(@options $name, $trait, [] [16] [] [] [], $($($options)*)?);
+	};
+	(
+		@options $name:ident, $trait:path, $default_size:tt $align:tt $flags:tt $bounds:tt $ops:tt,
+		default_size = $size:expr $(, $($rest:tt)*)?
+	) => {
+		$crate::dynbox!(@options $name, $trait, [$size] $align $flags $bounds $ops, $($($rest)*)?);
+	};
+	(
+		@options $name:ident, $trait:path, $default_size:tt $align:tt $flags:tt $bounds:tt $ops:tt,
+		align = $new_align:expr $(, $($rest:tt)*)?
+	) => {
+		$crate::dynbox!(@options $name, $trait, $default_size [$new_align] $flags $bounds $ops, $($($rest)*)?);
+	};
+	(
+		@options $name:ident, $trait:path, $default_size:tt $align:tt $flags:tt $bounds:tt $ops:tt,
+		align_for = [$($type:ty),* $(,)?] $(, $($rest:tt)*)?
+	) => {
+		$crate::dynbox!(
+			@options $name, $trait,
+			$default_size [$crate::max(&[$(core::mem::align_of::<$type>()),*])] $flags $bounds $ops,
+			$($($rest)*)?
+		);
+	};
+	(
+		@options $name:ident, $trait:path, $default_size:tt $align:tt [$($flags:ident)*] [$($bounds:tt)*] $ops:tt,
+		clone $(, $($rest:tt)*)?
+	) => {
+		$crate::dynbox!(
+			@options $name, $trait, $default_size $align [$($flags)* clone typed] [$($bounds)* + Clone] $ops,
+			$($($rest)*)?
+		);
+	};
+	(
+		@options $name:ident, $trait:path, $default_size:tt $align:tt [$($flags:ident)*] [$($bounds:tt)*] $ops:tt,
+		visit $(, $($rest:tt)*)?
+	) => {
+		$crate::dynbox!(
+			@options $name, $trait, $default_size $align [$($flags)* visit typed] [$($bounds)* + $crate::Visit] $ops,
+			$($($rest)*)?
+		);
+	};
+	(
+		@options $name:ident, $trait:path, $default_size:tt $align:tt [$($flags:ident)*] [$($bounds:tt)*] $ops:tt,
+		send $(, $($rest:tt)*)?
+	) => {
+		$crate::dynbox!(
+			@options $name, $trait, $default_size $align [$($flags)* send typed] [$($bounds)* + Send] $ops,
+			$($($rest)*)?
+		);
+	};
+	(
+		@options $name:ident, $trait:path, $default_size:tt $align:tt [$($flags:ident)*] [$($bounds:tt)*] $ops:tt,
+		sync $(, $($rest:tt)*)?
+	) => {
+		$crate::dynbox!(
+			@options $name, $trait, $default_size $align [$($flags)* sync typed] [$($bounds)* + Sync] $ops,
+			$($($rest)*)?
+		);
+	};
+	(
+		@options $name:ident, $trait:path, $default_size:tt $align:tt [$($flags:ident)*] [$($bounds:tt)*] $ops:tt,
+		downcast $(, $($rest:tt)*)?
+	) => {
+		$crate::dynbox!(
+			@options $name, $trait, $default_size $align [$($flags)* downcast] [$($bounds)* + 'static] $ops,
+			$($($rest)*)?
+		);
+	};
+	(
+		@options $name:ident, $trait:path, $default_size:tt $align:tt $flags:tt $bounds:tt $ops:tt,
+		sizes = [$($alias:ident = $size:expr),* $(,)?] $(, $($rest:tt)*)?
+	) => {
+		$(
+			#[doc = concat!("`", stringify!($name), "` with a capacity of ", stringify!($size), " bytes.")]
+			pub type $alias<'a> = $name<'a, { $size }>;
+		)*
+		$crate::dynbox!(@options $name, $trait, $default_size $align $flags $bounds $ops, $($($rest)*)?);
+	};
+	(
+		@options $name:ident, $trait:path, $default_size:tt $align:tt $flags:tt $bounds:tt $ops:tt,
+		upcast = [$($accessor:ident : $super:path),* $(,)?] $(, $($rest:tt)*)?
+	) => {
+		impl<'a, const SIZE: usize, const ALIGN: usize> $name<'a, SIZE, ALIGN>
+		where
+			$crate::Align<ALIGN>: $crate::Alignment,
+		{
+			$(
+				#[doc = concat!("Returns a `&dyn ", stringify!($super), "` reference if not empty, or None otherwise.")]
+				pub fn $accessor(&self) -> Option<&dyn $super> {
+					self.get().map(|content| content as &dyn $super)
+				}
+			)*
+		}
+		$crate::dynbox!(@options $name, $trait, $default_size $align $flags $bounds $ops, $($($rest)*)?);
+	};
+	(
+		@options $name:ident, $trait:path, $default_size:tt $align:tt $flags:tt $bounds:tt $ops:tt,
+		delegate { $($methods:tt)* } $(, $($rest:tt)*)?
+	) => {
+		$crate::dynbox!(@delegate $name, $trait, [] $($methods)*);
+		$crate::dynbox!(@options $name, $trait, $default_size $align $flags $bounds $ops, $($($rest)*)?);
+	};
+	// Collects the forwarding methods of `delegate { ... }`.
+	(
+		@delegate $name:ident, $trait:path, [$($done:tt)*]
+		fn $method:ident(&self $(, $arg:ident : $type:ty)* $(,)?) $(-> $ret:ty)? $(= $default:expr)?;
+		$($rest:tt)*
+	) => {
+		$crate::dynbox!(@delegate $name, $trait, [$($done)*
+			fn $method(&self $(, $arg: $type)*) $(-> $ret)? {
+				match self.get() {
+					Some(content) => content.$method($($arg),*),
+					None => $crate::dynbox!(@delegate_empty $method $(, $default)?),
+				}
+			}
+		] $($rest)*);
+	};
+	(
+		@delegate $name:ident, $trait:path, [$($done:tt)*]
+		fn $method:ident(&mut self $(, $arg:ident : $type:ty)* $(,)?) $(-> $ret:ty)? $(= $default:expr)?;
+		$($rest:tt)*
+	) => {
+		$crate::dynbox!(@delegate $name, $trait, [$($done)*
+			fn $method(&mut self $(, $arg: $type)*) $(-> $ret)? {
+				match self.get_mut() {
+					Some(content) => content.$method($($arg),*),
+					None => $crate::dynbox!(@delegate_empty $method $(, $default)?),
+				}
+			}
+		] $($rest)*);
+	};
+	(@delegate $name:ident, $trait:path, [$($done:tt)*]) => {
+		impl<'a, const SIZE: usize, const ALIGN: usize> $trait for $name<'a, SIZE, ALIGN>
+		where
+			$crate::Align<ALIGN>: $crate::Alignment,
+		{
+			$($done)*
+		}
+	};
+	(@delegate_empty $method:ident) => {
+		Self::delegate_without_default(stringify!($method))
+	};
+	(@delegate_empty $method:ident, $default:expr) => {
+		$default
+	};
+	(
+		@options $name:ident, $trait:path, $default_size:tt $align:tt [$($flags:ident)*] [$($bounds:tt)*] $ops:tt,
+		copy $(, $($rest:tt)*)?
+	) => {
+		$crate::dynbox!(
+			@options $name, $trait, $default_size $align [$($flags)* copy typed] [$($bounds)* + Copy] $ops,
+			$($($rest)*)?
+		);
+	};
+	(
+		@options $name:ident, $trait:path, $default_size:tt $align:tt [$($flags:ident)*] $bounds:tt $ops:tt,
+		on_move $(, $($rest:tt)*)?
+	) => {
+		$crate::dynbox!(
+			@options $name, $trait, $default_size $align [$($flags)* on_move] $bounds $ops,
+			$($($rest)*)?
+		);
+	};
+	(
+		@options $name:ident, $trait:path, $default_size:tt $align:tt [$($flags:ident)*] $bounds:tt $ops:tt,
+		repr_c $(, $($rest:tt)*)?
+	) => {
+		$crate::dynbox!(
+			@options $name, $trait, $default_size $align [$($flags)* repr_c] $bounds $ops,
+			$($($rest)*)?
+		);
+	};
+	(
+		@options $name:ident, $trait:path, $default_size:tt $align:tt [$($flags:ident)*] $bounds:tt $ops:tt,
+		checksum $(, $($rest:tt)*)?
+	) => {
+		$crate::dynbox!(
+			@options $name, $trait, $default_size $align [$($flags)* checksum] $bounds $ops,
+			$($($rest)*)?
+		);
+	};
+	(
+		@options $name:ident, $trait:path, $default_size:tt $align:tt [$($flags:ident)*] $bounds:tt $ops:tt,
+		zeroize $(, $($rest:tt)*)?
+	) => {
+		$crate::dynbox!(
+			@options $name, $trait, $default_size $align [$($flags)* zeroize] $bounds $ops,
+			$($($rest)*)?
+		);
+	};
+	(
+		@options $name:ident, $trait:path, $default_size:tt $align:tt [$($flags:ident)*] $bounds:tt $ops:tt,
+		no_panic $(, $($rest:tt)*)?
+	) => {
+		$crate::dynbox!(
+			@options $name, $trait, $default_size $align [$($flags)* no_panic] $bounds $ops,
+			$($($rest)*)?
+		);
+	};
+	(
+		@options $name:ident, $trait:path, $default_size:tt $align:tt [$($flags:ident)*] $bounds:tt $ops:tt,
+		type_name $(, $($rest:tt)*)?
+	) => {
+		$crate::dynbox!(
+			@options $name, $trait, $default_size $align [$($flags)* type_name] $bounds $ops,
+			$($($rest)*)?
+		);
+	};
+	(
+		@options $name:ident, $trait:path, $default_size:tt $align:tt [$($flags:ident)*] $bounds:tt $ops:tt,
+		sort_key $(, $($rest:tt)*)?
+	) => {
+		$crate::dynbox!(
+			@options $name, $trait, $default_size $align [$($flags)* sort_key] $bounds $ops,
+			$($($rest)*)?
+		);
+	};
+	(
+		@options $name:ident, $trait:path, $default_size:tt $align:tt [$($flags:ident)*] $bounds:tt $ops:tt,
+		eq_hash $(, $($rest:tt)*)?
+	) => {
+		$crate::dynbox!(
+			@options $name, $trait, $default_size $align [$($flags)* eq_hash] $bounds $ops,
+			$($($rest)*)?
+		);
+	};
+	(
+		@options $name:ident, $trait:path, $default_size:tt $align:tt [$($flags:ident)*] $bounds:tt $ops:tt,
+		display $(, $($rest:tt)*)?
+	) => {
+		$crate::dynbox!(
+			@options $name, $trait, $default_size $align [$($flags)* display] $bounds $ops,
+			$($($rest)*)?
+		);
+	};
+	(
+		@options $name:ident, $trait:path, $default_size:tt $align:tt [$($flags:ident)*] $bounds:tt $ops:tt,
+		defmt $(, $($rest:tt)*)?
+	) => {
+		$crate::dynbox!(
+			@options $name, $trait, $default_size $align [$($flags)* defmt] $bounds $ops,
+			$($($rest)*)?
+		);
+	};
+	(
+		@options $name:ident, $trait:path, $default_size:tt $align:tt [$($flags:ident)*] $bounds:tt $ops:tt,
+		defmt_type_name $(, $($rest:tt)*)?
+	) => {
+		$crate::dynbox!(
+			@options $name, $trait, $default_size $align [$($flags)* defmt_type_name type_name] $bounds $ops,
+			$($($rest)*)?
+		);
+	};
+	(
+		@options $name:ident, $trait:path, $default_size:tt $align:tt [$($flags:ident)*] $bounds:tt $ops:tt,
+		debug $(, $($rest:tt)*)?
+	) => {
+		$crate::dynbox!(
+			@options $name, $trait, $default_size $align [$($flags)* debug] $bounds $ops,
+			$($($rest)*)?
+		);
+	};
+	(
+		@options $name:ident, $trait:path, $default_size:tt $align:tt [$($flags:ident)*] $bounds:tt $ops:tt,
+		min_useful $(, $($rest:tt)*)?
+	) => {
+		$crate::dynbox!(
+			@options $name, $trait, $default_size $align [$($flags)* min_useful] $bounds $ops,
+			$($($rest)*)?
+		);
+	};
+	(
+		@options $name:ident, $trait:path, $default_size:tt $align:tt [$($flags:ident)*] $bounds:tt $ops:tt,
+		stable_addr $(, $($rest:tt)*)?
+	) => {
+		$crate::dynbox!(
+			@options $name, $trait, $default_size $align [$($flags)* stable_addr] $bounds $ops,
+			$($($rest)*)?
+		);
+	};
+	(
+		@options $name:ident, $trait:path, $default_size:tt $align:tt [$($flags:ident)*] $bounds:tt $ops:tt,
+		spill $(, $($rest:tt)*)?
+	) => {
+		$crate::dynbox!(
+			@options $name, $trait, $default_size $align [$($flags)* spill] $bounds $ops,
+			$($($rest)*)?
+		);
+	};
+	(
+		@options $name:ident, $trait:path, $default_size:tt $align:tt [$($flags:ident)*] $bounds:tt $ops:tt,
+		generation $(, $($rest:tt)*)?
+	) => {
+		$crate::dynbox!(
+			@options $name, $trait, $default_size $align [$($flags)* generation] $bounds $ops,
+			$($($rest)*)?
+		);
+	};
+	(
+		@options $name:ident, $trait:path, $default_size:tt $align:tt [$($flags:ident)*] $bounds:tt $ops:tt,
+		pinned $(, $($rest:tt)*)?
+	) => {
+		$crate::dynbox!(
+			@options $name, $trait, $default_size $align [$($flags)* pinned] $bounds $ops,
+			$($($rest)*)?
+		);
+	};
+	(
+		@options $name:ident, $trait:path, $default_size:tt $align:tt [$($flags:ident)*] $bounds:tt $ops:tt,
+		future $(, $($rest:tt)*)?
+	) => {
+		$crate::dynbox!(
+			@options $name, $trait, $default_size $align [$($flags)* future pinned] $bounds $ops,
+			$($($rest)*)?
+		);
+	};
+	(
+		@options $name:ident, $trait:path, $default_size:tt $align:tt $flags:tt $bounds:tt $ops:tt,
+		ops = $ops_name:ident $(, $($rest:tt)*)?
+	) => {
+		$crate::dynbox!(@options $name, $trait, $default_size $align $flags $bounds [$ops_name], $($($rest)*)?);
+	};
+	(@options $name:ident, $trait:path, $default_size:tt $align:tt $flags:tt $bounds:tt $ops:tt,) => {
+		$crate::dynbox!(@box $name, $trait, $default_size $align $flags $bounds $ops);
+	};
+	(
+		@box $name:ident, $trait:path,
+		[$($default_size:expr)?] [$align:expr] [$($flags:ident)*] [$($bounds:tt)*] [$($ops:ident)?]
+	) => {
+		$crate::__dynbox_repr! { [$($flags)*]
+			/// DynBox for a given Trait with `Option<dyn Trait>`-like semantics.
+			/// Can hold implementors of the trait if they do not exceed `SIZE` bytes
+			/// and `ALIGN` alignment, and allows to retrieve `&dyn Trait` and
+			/// `&mut dyn Trait` references. `ALIGN` must be a power of two of at most
+			/// 4096. The content is stored inline, so moving the DynBox moves it as
+			/// well, invalidating raw pointers to it (unless `stable_addr` is used).
+			/// Generated through the `dynbox!` macro
+			pub struct $name<'a, const SIZE: usize $(= { $default_size })?, const ALIGN: usize = { $align }>
+			where
+				$crate::Align<ALIGN>: $crate::Alignment,
+			{
+				store: $crate::__dynbox_if!(stable_addr in [$($flags)*] {
+					$crate::__alloc::boxed::Box<$crate::Storage<SIZE, ALIGN>>
+				} else {
+					$crate::Storage<SIZE, ALIGN>
+				}),
+				#[cfg(debug_assertions)]
+				vtable_check: usize,
+				#[allow(dead_code)]
+				clone_fn: $crate::__dynbox_if!(clone in [$($flags)*] {
+					Option<unsafe fn(*const u8, *mut u8)>
+				} else {
+					()
+				}),
+				#[allow(dead_code)]
+				type_id: $crate::__dynbox_if!(downcast in [$($flags)*] {
+					Option<fn() -> core::any::TypeId>
+				} else {
+					()
+				}),
+				#[allow(dead_code)]
+				call_fn: $crate::__dynbox_if!(call_once in [$($flags)*] {
+					Option<unsafe fn(*mut u8, *mut u8)>
+				} else {
+					()
+				}),
+				#[allow(dead_code)]
+				type_name: $crate::__dynbox_if!(type_name in [$($flags)*] {
+					Option<fn() -> &'static str>
+				} else {
+					()
+				}),
+				#[allow(dead_code)]
+				visit_fn: $crate::__dynbox_if!(visit in [$($flags)*] {
+					Option<unsafe fn(*const u8, &mut dyn $crate::Visitor)>
+				} else {
+					()
+				}),
+				#[allow(dead_code)]
+				checksum: $crate::__dynbox_if!(checksum in [$($flags)*] { u32 } else { () }),
+				#[allow(dead_code)]
+				generation: $crate::__dynbox_if!(generation in [$($flags)*] { usize } else { () }),
+				#[allow(dead_code)]
+				address: $crate::__dynbox_if!(on_move in [$($flags)*] { usize } else { () }),
+				#[allow(dead_code)]
+				spilled: $crate::__dynbox_if!(spill in [$($flags)*] { bool } else { () }),
+				#[allow(dead_code)]
+				pinned: $crate::__dynbox_if!(pinned in [$($flags)*] {
+					core::marker::PhantomPinned
+				} else {
+					()
+				}),
+				// Neither `Send` nor `Sync` unless the options require the content to be.
+				_content: core::marker::PhantomData<(&'a (), *const ())>,
+				// Last, for the layout promised by `repr_c`.
+				vtable: $crate::Vtable,
+			}
+		}
+
+		$crate::__dynbox_if! { repr_c in [$($flags)*] {
+			$crate::__dynbox_if! { stable_addr in [$($flags)*] {
+				compile_error!("`repr_c` cannot be combined with `stable_addr`");
+			}}
+			$crate::__dynbox_if! { spill in [$($flags)*] {
+				compile_error!("`repr_c` cannot be combined with `spill`");
+			}}
+		}}
+
+		$crate::__dynbox_if! { send in [$($flags)*] {
+			unsafe impl<const SIZE: usize, const ALIGN: usize> Send for $name<'_, SIZE, ALIGN> where
+				$crate::Align<ALIGN>: $crate::Alignment
+			{
+			}
+		}}
+
+		$crate::__dynbox_if! { sync in [$($flags)*] {
+			unsafe impl<const SIZE: usize, const ALIGN: usize> Sync for $name<'_, SIZE, ALIGN> where
+				$crate::Align<ALIGN>: $crate::Alignment
+			{
+			}
+		}}
+
+		impl<const SIZE: usize, const ALIGN: usize> Default for $name<'_, SIZE, ALIGN>
+		where
+			$crate::Align<ALIGN>: $crate::Alignment,
+		{
+			fn default() -> Self {
+				Self::new()
 			}
 		}
 
-		impl<const SIZE: usize> $name<SIZE> {
-			/// Creates a new empty DynBox.
-			pub fn new() -> $name<SIZE> {
-				$name {
-					store: [0; SIZE],
-					vtable: 0,
-				}
-			}
+		// The vtable is obtained by transmuting `*const dyn $trait` to two words.
+		const _: () = assert!(
+			core::mem::size_of::<*const dyn $trait>() == 2 * core::mem::size_of::<usize>(),
+			"trait object pointers are not a data pointer and a vtable pointer"
+		);
+
+		$crate::__dynbox_if! { copy in [$($flags)*] {
+			$crate::__dynbox_if! { spill in [$($flags)*] {
+				compile_error!("`spill` cannot be combined with `copy`");
+			}}
+
+			impl<const SIZE: usize, const ALIGN: usize> Clone for $name<'_, SIZE, ALIGN>
+			where
+				$crate::Align<ALIGN>: $crate::Alignment,
+			{
+				fn clone(&self) -> Self {
+					*self
+				}
+			}
+
+			impl<const SIZE: usize, const ALIGN: usize> Copy for $name<'_, SIZE, ALIGN> where
+				$crate::Align<ALIGN>: $crate::Alignment
+			{
+			}
+		} else {
+			impl<const SIZE: usize, const ALIGN: usize> Drop for $name<'_, SIZE, ALIGN>
+			where
+				$crate::Align<ALIGN>: $crate::Alignment,
+			{
+				fn drop(&mut self) {
+					self.clear();
+				}
+			}
+
+			$crate::__dynbox_if! { clone in [$($flags)*] {
+				impl<const SIZE: usize, const ALIGN: usize> Clone for $name<'_, SIZE, ALIGN>
+				where
+					$crate::Align<ALIGN>: $crate::Alignment,
+				{
+					fn clone(&self) -> Self {
+						let mut clone = Self::new();
+						clone.clone_from(self);
+						clone
+					}
+
+					fn clone_from(&mut self, source: &Self) {
+						// Clear first, so that a panicking clone leaves the box empty.
+						self.clear();
+						if !source.empty() {
+							// SAFETY: `clone` implies `typed`, so all content records the hook.
+							let clone_fn = unsafe { source.clone_fn.unwrap_unchecked() };
+							let layout = core::alloc::Layout::for_value(unsafe { &*source.get_ptr() });
+							unsafe {
+								let ptr = self.place(layout);
+								clone_fn(source.data_ptr(), ptr);
+							}
+							self.set_vtable(source.vtable);
+							self.copy_hooks(source);
+							self.placed();
+						}
+					}
+				}
+			}}
+		}}
+
+		#[allow(dead_code)]
+		impl<'a, const SIZE: usize, const ALIGN: usize> $name<'a, SIZE, ALIGN>
+		where
+			$crate::Align<ALIGN>: $crate::Alignment,
+		{
+			$crate::__dynbox_if! { stable_addr in [$($flags)*] {
+				/// Creates a new empty DynBox.
+				pub fn new() -> $name<'a, SIZE, ALIGN> {
+					Self::from_store($crate::__alloc::boxed::Box::default())
+				}
+			} else {
+				/// An empty DynBox, for use in statics and other const contexts.
+				pub const EMPTY: Self = Self::new();
+
+				/// Creates a new empty DynBox. Can be evaluated at compile time.
+				pub const fn new() -> $name<'a, SIZE, ALIGN> {
+					Self::from_store($crate::Storage::new())
+				}
+			}}
+
+			/// Creates a new empty DynBox around `store`.
+			const fn from_store(
+				store: $crate::__dynbox_if!(stable_addr in [$($flags)*] {
+					$crate::__alloc::boxed::Box<$crate::Storage<SIZE, ALIGN>>
+				} else {
+					$crate::Storage<SIZE, ALIGN>
+				}),
+			) -> $name<'a, SIZE, ALIGN> {
+				$crate::__dynbox_if! { min_useful in [$($flags)*] {
+					const {
+						assert!(
+							SIZE >= core::mem::size_of::<usize>(),
+							"DynBox is too small to hold anything but zero-sized types"
+						)
+					}
+				}}
+				$crate::__dynbox_if! { spill in [$($flags)*] {
+					const {
+						assert!(
+							SIZE >= core::mem::size_of::<usize>(),
+							"DynBox is too small to hold a pointer to spilled content"
+						)
+					}
+				}}
+				$crate::__dynbox_if! { repr_c in [$($flags)*] {
+					const {
+						assert!(
+							SIZE.is_multiple_of(core::mem::size_of::<usize>()),
+							"SIZE of a repr_c DynBox is not a multiple of the size of a usize"
+						)
+					}
+				}}
+				$name {
+					store,
+					vtable: $crate::Vtable::EMPTY,
+					#[cfg(debug_assertions)]
+					vtable_check: !$crate::Vtable::EMPTY_BITS,
+					clone_fn: $crate::__dynbox_if!(clone in [$($flags)*] { None } else { () }),
+					type_id: $crate::__dynbox_if!(downcast in [$($flags)*] { None } else { () }),
+					call_fn: $crate::__dynbox_if!(call_once in [$($flags)*] { None } else { () }),
+					type_name: $crate::__dynbox_if!(type_name in [$($flags)*] { None } else { () }),
+					visit_fn: $crate::__dynbox_if!(visit in [$($flags)*] { None } else { () }),
+					checksum: $crate::__dynbox_if!(checksum in [$($flags)*] { 0 } else { () }),
+					generation: $crate::__dynbox_if!(generation in [$($flags)*] { 0 } else { () }),
+					address: $crate::__dynbox_if!(on_move in [$($flags)*] { 0 } else { () }),
+					spilled: $crate::__dynbox_if!(spill in [$($flags)*] { false } else { () }),
+					pinned: $crate::__dynbox_if!(pinned in [$($flags)*] {
+						core::marker::PhantomPinned
+					} else {
+						()
+					}),
+					_content: core::marker::PhantomData,
+				}
+			}
+
+			/// Creates a new DynBox holding `content`. Panics like `set()` if T does
+			/// not fit.
+			#[track_caller]
+			pub fn new_with<T: $trait + 'a $($bounds)*>(content: T) -> $name<'a, SIZE, ALIGN> {
+				let mut dynbox = Self::new();
+				unsafe { dynbox.overwrite_unchecked(content) };
+				dynbox
+			}
+
+			/// Converts `content` into a DynBox holding it, like `From<T>` would, and
+			/// fails to compile if it does not fit. A blanket `From<T>` impl is not
+			/// possible, as it would overlap with `From<Self>` if the DynBox itself
+			/// implements $trait.
+			pub fn from_value<T: $trait + 'a $($bounds)*>(content: T) -> $name<'a, SIZE, ALIGN> {
+				const { assert!(Self::can_hold::<T>(), "T does not fit into the DynBox") }
+				Self::new_with(content)
+			}
+
+			/// Returns whether a `T` fits into this DynBox's size and alignment. Can be
+			/// evaluated at compile time.
+			pub const fn can_hold<T>() -> bool {
+				core::mem::size_of::<T>() <= SIZE && core::mem::align_of::<T>() <= ALIGN
+			}
+
+			/// Fails to compile if `SIZE` is less than `MIN`. Allows generic code
+			/// to state its capacity requirements.
+			pub const fn assert_capacity<const MIN: usize>() {
+				const { assert!(SIZE >= MIN, "DynBox capacity is too small") }
+			}
+
+			/// Returns None if a `T` fits into this DynBox, or a message explaining why
+			/// it does not, like `"size 96 > capacity 64"` or `"alignment 32 > 16"`.
+			pub fn why_not_fit<T: $trait>() -> Option<&'static str> {
+				let message: &'static $crate::FitMessage = const {
+					&$crate::FitMessage::new(
+						core::mem::size_of::<T>(),
+						SIZE,
+						core::mem::align_of::<T>(),
+						ALIGN,
+					)
+				};
+				message.as_str()
+			}
+
+			/// Stores a value of some generic type which implements $trait. Panics if
+			/// T's size exceeds `SIZE` or its alignment exceeds `ALIGN`, or fails to
+			/// compile with `no_panic`. Clears (and drops) the previous value, if
+			/// present. Always inlined, so that the value can be moved straight into
+			/// the store.
+			#[inline(always)]
+			#[track_caller]
+			pub fn set<T: $trait + 'a $($bounds)*>(&mut self, content: T) {
+				if !self.empty() {
+					self.clear();
+				}
+
+				unsafe { self.overwrite_unchecked(content) }
+			}
+
+			/// Stores the value returned by `f`, which is given the previous content. The
+			/// previous content is only dropped once `f` has returned, so if `f` panics,
+			/// the DynBox is left untouched.
+			#[track_caller]
+			pub fn replace_with<T, F>(&mut self, f: F)
+			where
+				T: $trait + 'a $($bounds)*,
+				F: FnOnce(Option<&mut dyn $trait>) -> T,
+			{
+				let content = f(self.get_mut());
+				self.set(content);
+			}
+
+			/// Lets the content, e.g. the state of a state machine, produce its
+			/// successor: calls `f` with it, and if `f` returns `Some`, drops the
+			/// content and stores the returned value in its place. Returns whether
+			/// that happened; an empty DynBox stays empty without calling `f`. If `f`
+			/// panics, the DynBox is left untouched.
+			#[track_caller]
+			pub fn transition<T: $trait + 'a $($bounds)*>(
+				&mut self,
+				f: impl FnOnce(&mut dyn $trait) -> Option<T>,
+			) -> bool {
+				match self.get_mut().and_then(f) {
+					Some(next) => {
+						self.set(next);
+						true
+					}
+					None => false,
+				}
+			}
+
+			/// Stores a value like `set()`, and returns a reference to it, like
+			/// `Option::insert()`.
+			#[track_caller]
+			pub fn insert<T: $trait + 'a $($bounds)*>(&mut self, content: T) -> &mut (dyn $trait + 'a) {
+				self.set(content);
+				unsafe { &mut *self.get_ptr_mut() }
+			}
+
+			/// Returns a reference to the content, storing the value returned by `init`
+			/// first if the DynBox is empty.
+			#[track_caller]
+			pub fn get_or_insert_with<T: $trait + 'a $($bounds)*>(&mut self, init: impl FnOnce() -> T) -> &mut (dyn $trait + 'a) {
+				if self.empty() {
+					self.set(init());
+				}
+				unsafe { &mut *self.get_ptr_mut() }
+			}
+
+			/// Stores a value like `set()`, but returns an error giving it back instead
+			/// of panicking if it exceeds `SIZE` or `ALIGN` (and `spill` is not used),
+			/// leaving the DynBox unchanged.
+			pub fn try_set<T: $trait + 'a $($bounds)*>(&mut self, content: T) -> Result<(), $crate::SetError<T>> {
+				if !Self::accepts(core::alloc::Layout::new::<T>()) {
+					return Err($crate::SetError::new(content, SIZE, ALIGN));
+				}
+
+				if !self.empty() {
+					self.clear();
+				}
+				unsafe { self.write(content) };
+				Ok(())
+			}
+
+			/// Stores a value like `set()`, but fails to compile instead of panicking if
+			/// it exceeds `SIZE` or `ALIGN`.
+			pub fn set_checked<T: $trait + 'a $($bounds)*>(&mut self, content: T) {
+				const { assert!(Self::can_hold::<T>(), "T does not fit into the DynBox") }
+				self.set(content);
+			}
+
+			/// Stores `content` like `set()`, and returns a guard dereferencing to it,
+			/// which clears the DynBox when dropped.
+			#[track_caller]
+			pub fn set_scoped<T: $trait + 'a $($bounds)*>(&mut self, content: T) -> $crate::BoxGuard<'_, Self, dyn $trait + 'a> {
+				self.set(content);
+				// SAFETY: The DynBox was just filled.
+				unsafe { $crate::BoxGuard::new(self) }
+			}
+
+			/// Returns a setter for values of type `T`, which cannot panic because it
+			/// fails to compile if `T` does not fit.
+			pub fn prove<T: $trait + 'a $($bounds)*>(&mut self) -> $crate::ProvenSetter<'_, Self, T> {
+				const { assert!(Self::can_hold::<T>(), "T does not fit into the DynBox") }
+				// SAFETY: The assertion above ensures that a `T` fits.
+				unsafe { $crate::ProvenSetter::new(self) }
+			}
+
+			/// Stores a value like `set()`, but without checking for and dropping a
+			/// previous value. Panics if T's size exceeds `SIZE` or its alignment
+			/// exceeds `ALIGN`.
+			///
+			/// # Safety
+			/// The DynBox must be empty; otherwise, its previous value is leaked.
+			#[inline(always)]
+			#[track_caller]
+			pub unsafe fn overwrite_unchecked<T: $trait + 'a $($bounds)*>(&mut self, content: T) {
+				Self::check_fit_of::<T>();
+				self.write(content);
+			}
+
+			/// Stores a value which `accepts()` its layout into the empty DynBox.
+			#[inline(always)]
+			unsafe fn write<T: $trait + 'a $($bounds)*>(&mut self, content: T) {
+				let layout = core::alloc::Layout::new::<T>();
+				let parts = core::mem::transmute::<*const dyn $trait, $crate::DynParts>(&content as *const dyn $trait);
+				let ptr = self.place(layout) as *mut T;
+				// Written before touching `self` again, which would invalidate `ptr`.
+				ptr.write(content);
+				self.set_vtable($crate::Vtable::occupied(parts.vtable));
+				self.record_hooks::<T>();
+				self.placed();
+			}
+
+			/// Stores a value of some generic type which implements $trait by letting
+			/// `init` initialize it in place, without copying it through the stack.
+			/// Panics if T's size exceeds `SIZE` or its alignment exceeds `ALIGN`.
+			/// Clears (and drops) the previous value, if present.
+			///
+			/// # Safety
+			/// `init` must fully initialize the `T` behind the pointer it is given.
+			#[inline(always)]
+			#[track_caller]
+			pub unsafe fn set_fn<T: $trait + 'a $($bounds)*>(&mut self, init: impl FnOnce(*mut T)) {
+				if !self.empty() {
+					self.clear();
+				}
+
+				Self::check_fit_of::<T>();
+				let layout = core::alloc::Layout::new::<T>();
+				let ptr = self.place(layout) as *mut T;
+				init(ptr);
+				let parts = core::mem::transmute::<*const dyn $trait, $crate::DynParts>(ptr as *const dyn $trait);
+				self.set_vtable($crate::Vtable::occupied(parts.vtable));
+				self.record_hooks::<T>();
+				self.placed();
+			}
+
+			/// Stores the value returned by `init`, which is only called once the
+			/// previous value was dropped and the slot is known, so that the compiler
+			/// can construct large values directly in the store instead of on the
+			/// stack. Panics like `set()` if T does not fit.
+			#[inline(always)]
+			#[track_caller]
+			pub fn set_with<T: $trait + 'a $($bounds)*>(&mut self, init: impl FnOnce() -> T) {
+				unsafe { self.set_fn(|ptr: *mut T| ptr.write(init())) }
+			}
+
+			$crate::__dynbox_if! { no_panic in [$($flags)*] {} else {
+				/// Stores a value initialized in place like `set_fn()`, but safely: `init`
+				/// has to return the reference obtained from `MaybeUninit::write()` on the
+				/// slot it is given. Panics if it returns any other reference, and like
+				/// `set()` if T does not fit.
+				#[track_caller]
+				pub fn emplace_with<T: $trait + 'a $($bounds)*>(
+					&mut self,
+					init: impl FnOnce(&mut core::mem::MaybeUninit<T>) -> &mut T,
+				) {
+					unsafe {
+						self.set_fn(|ptr: *mut T| {
+							let initialized = init(&mut *(ptr as *mut core::mem::MaybeUninit<T>)) as *mut T;
+							assert!(initialized == ptr, "emplace_with() must initialize the given slot");
+						})
+					}
+				}
+			}}
+
+			$crate::__dynbox_if! { typed in [$($flags)*] {} else {
+				$crate::__dynbox_if! { no_panic in [$($flags)*] {} else {
+					/// Stores the value behind `src` by copying its bytes and recording its
+					/// vtable, taking over ownership. This is the low-level ingestion path for
+					/// values whose concrete type cannot be named. Panics if the value exceeds
+					/// `SIZE` or `ALIGN`. Clears (and drops) the previous value, if present.
+					///
+					/// # Safety
+					/// The DynBox takes over ownership of the value, so the caller must neither
+					/// use nor drop it afterwards (e.g. by `forget`ting its owner). `src` must
+					/// not point into this DynBox.
+					#[track_caller]
+					pub unsafe fn set_from_dyn(&mut self, src: &mut (dyn $trait + 'a)) {
+						Self::check_fit(core::alloc::Layout::for_value(src));
+						self.write_from_dyn(src);
+					}
+				}}
+
+				/// Stores the value behind `src` like `set_from_dyn()`, whose layout
+				/// `accepts()` must have accepted.
+				unsafe fn write_from_dyn(&mut self, src: &mut (dyn $trait + 'a)) {
+					let layout = core::alloc::Layout::for_value(src);
+					let size = layout.size();
+					if !self.empty() {
+						self.clear();
+					}
+
+					let parts =
+						core::mem::transmute::<*mut (dyn $trait + 'a), $crate::DynParts>(src as *mut (dyn $trait + 'a));
+					self.place(layout).copy_from(parts.data, size);
+					self.set_vtable($crate::Vtable::occupied(parts.vtable));
+					self.forget_hooks();
+					self.placed();
+				}
+
+				$crate::__if_alloc! {
+					/// Moves the content of `boxed` into a new DynBox, or gives it back if it
+					/// exceeds `SIZE` or `ALIGN` and `spill` is not used. Requires the `alloc`
+					/// feature.
+					pub fn try_from_box(
+						boxed: $crate::__alloc::boxed::Box<dyn $trait + 'a>,
+					) -> Result<Self, $crate::__alloc::boxed::Box<dyn $trait + 'a>> {
+						let layout = core::alloc::Layout::for_value(&*boxed);
+						if !Self::accepts(layout) {
+							return Err(boxed);
+						}
+
+						let mut dynbox = Self::new();
+						let raw = $crate::__alloc::boxed::Box::into_raw(boxed);
+						unsafe {
+							dynbox.write_from_dyn(&mut *raw);
+							if layout.size() != 0 {
+								$crate::__alloc::alloc::dealloc(raw as *mut u8, layout);
+							}
+						}
+						Ok(dynbox)
+					}
+				}
+			}}
+
+			$crate::__dynbox_if! { no_panic in [$($flags)*] {} else {
+				/// Reconstitutes a `T` from its raw `bytes`, e.g. after deserialization,
+				/// and stores it. `sample` is only used to obtain `T`'s vtable; any
+				/// instance of `T` will do. Panics if `bytes` is not exactly as long as a
+				/// `T`, or if T's size exceeds `SIZE` or its alignment exceeds `ALIGN`.
+				/// Clears (and drops) the previous value, if present.
+				///
+				/// # Safety
+				/// `bytes` must be a valid bit pattern for `T`, as if obtained from a live
+				/// `T` whose ownership is transferred to the DynBox. For types owning
+				/// resources (heap memory, handles, ...) or containing references, this is
+				/// almost never the case across process boundaries: as with
+				/// `core::mem::transmute`, only plain-old-data types are safe to round-trip.
+				/// The bytes must not point into this DynBox.
+				#[track_caller]
+				pub unsafe fn set_from_bytes_and_sample<T: $trait + 'a $($bounds)*>(
+					&mut self,
+					bytes: &[u8],
+					sample: &T,
+				) {
+					assert!(bytes.len() == core::mem::size_of::<T>());
+					let layout = core::alloc::Layout::new::<T>();
+					Self::check_fit(layout);
+
+					if !self.empty() {
+						self.clear();
+					}
+
+					let parts = core::mem::transmute::<*const dyn $trait, $crate::DynParts>(sample as *const dyn $trait);
+					self.place(layout).copy_from_nonoverlapping(bytes.as_ptr(), bytes.len());
+					self.set_vtable($crate::Vtable::occupied(parts.vtable));
+					self.record_hooks::<T>();
+					self.placed();
+				}
+			}}
+
+			/// Replaces the current value, if any, with `T::default()`.
+			#[track_caller]
+			pub fn reset_to_default<T: $trait + 'a $($bounds)* + Default>(&mut self) {
+				self.set(T::default());
+			}
+
+			/// Makes the DynBox empty again by dropping the previous content, if any.
+			/// In debug builds, the store is overwritten with a poison pattern
+			/// afterwards, so stale references read obviously-wrong data, unless
+			/// `zeroize` is used, which overwrites it with zeros in all builds. The
+			/// same goes for the store left behind by `take()` and `map()`.
+			pub fn clear(&mut self) {
+				if !self.empty() {
+					self.check_vtable();
+					// Before taking `content`, as reborrowing `self` would invalidate it.
+					self.next_generation();
+					let content = unsafe { self.get_ptr_mut() };
+					let layout = core::alloc::Layout::for_value(unsafe { &*content });
+					// Mark as empty first, so a panicking destructor cannot cause a
+					// double drop.
+					self.vtable = $crate::Vtable::EMPTY;
+					unsafe {
+						core::ptr::drop_in_place(content);
+						$crate::__dynbox_if! { zeroize in [$($flags)*] {
+							for offset in 0..layout.size() {
+								(content as *mut u8).add(offset).write_volatile(0);
+							}
+							core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::SeqCst);
+						}}
+						self.release(layout);
+					}
+					self.poison();
+				}
+			}
+
+			/// Calls `f` with the content and drops it afterwards, leaving the DynBox
+			/// empty. Returns false without calling `f` if the DynBox was empty.
+			pub fn consume_with<F: FnOnce(&mut dyn $trait)>(&mut self, f: F) -> bool {
+				match self.get_mut() {
+					Some(content) => f(content),
+					None => return false,
+				}
+				self.clear();
+				true
+			}
+
+			/// Clears all DynBoxes in `boxes`. If a destructor panics, the remaining
+			/// DynBoxes are still cleared before the panic propagates.
+			pub fn clear_all(boxes: &mut [Self]) {
+				struct ClearRest<'a, 'b, const SIZE: usize, const ALIGN: usize>(
+					core::slice::IterMut<'a, $name<'b, SIZE, ALIGN>>,
+				)
+				where
+					$crate::Align<ALIGN>: $crate::Alignment;
+				impl<const SIZE: usize, const ALIGN: usize> Drop for ClearRest<'_, '_, SIZE, ALIGN>
+				where
+					$crate::Align<ALIGN>: $crate::Alignment,
+				{
+					fn drop(&mut self) {
+						self.0.by_ref().for_each($name::clear);
+					}
+				}
+
+				let mut rest = ClearRest(boxes.iter_mut());
+				while let Some(dynbox) = rest.0.next() {
+					dynbox.clear();
+				}
+			}
+
+			/// Stores successive items of `iter` into successive empty DynBoxes of
+			/// `boxes`, until either runs out. Returns how many items were stored;
+			/// the remaining items are left in the iterator.
+			#[track_caller]
+			pub fn fill_from<T: $trait + 'a $($bounds)*, I: Iterator<Item = T>>(
+				boxes: &mut [Self],
+				mut iter: I,
+			) -> usize {
+				let mut stored = 0;
+				for dynbox in boxes.iter_mut().filter(|dynbox| dynbox.empty()) {
+					match iter.next() {
+						Some(content) => dynbox.set(content),
+						None => break,
+					}
+					stored += 1;
+				}
+				stored
+			}
+
+			/// Returns an array of `N` empty DynBoxes.
+			pub fn empty_array<const N: usize>() -> [Self; N] {
+				core::array::from_fn(|_| Self::new())
+			}
+
+			/// Returns an array of DynBoxes holding the respective `values`. Panics
+			/// if T's size exceeds `SIZE` or its alignment exceeds `ALIGN`, before
+			/// storing any of them.
+			#[track_caller]
+			pub fn array_from<T: $trait + 'a $($bounds)*, const N: usize>(values: [T; N]) -> [Self; N] {
+				Self::check_fit_of::<T>();
+				values.map(Self::new_with)
+			}
+
+			/// Returns whether the DynBox currently contains any value.
+			#[inline]
+			pub fn empty(&self) -> bool {
+				self.vtable.is_empty()
+			}
+
+			/// Lets the optimizer assume that the DynBox is occupied, so that following
+			/// checks like in `get()` can be elided. In debug builds, panics if it is
+			/// empty.
+			///
+			/// # Safety
+			/// The DynBox must be occupied. Otherwise, the behaviour is undefined in
+			/// release builds.
+			#[inline]
+			#[track_caller]
+			pub unsafe fn assume_occupied(&self) {
+				debug_assert!(!self.empty(), "DynBox assumed to be occupied is empty");
+				core::hint::assert_unchecked(!self.empty());
+			}
+
+			/// Leaves the DynBox empty and returns its previous state as a new DynBox.
+			/// Unless `stable_addr` is used, this moves the content, so it does not
+			/// keep its address.
+			pub fn take_box(&mut self) -> Self {
+				core::mem::take(self)
+			}
+
+			/// Replaces the DynBox with `new` and returns its previous state. Unless
+			/// `stable_addr` is used, this moves the content of both DynBoxes, so
+			/// neither keeps its address.
+			pub fn replace_box(&mut self, new: Self) -> Self {
+				core::mem::replace(self, new)
+			}
+
+			/// Returns whether the content of `other` would fit into this DynBox,
+			/// regardless of whether this one is occupied. Returns true if `other` is
+			/// empty.
+			pub fn could_accept<const OTHER: usize, const OTHER_ALIGN: usize>(
+				&self,
+				other: &$name<'_, OTHER, OTHER_ALIGN>,
+			) -> bool
+			where
+				$crate::Align<OTHER_ALIGN>: $crate::Alignment,
+			{
+				match other.get() {
+					Some(content) => {
+						core::mem::size_of_val(content) <= SIZE && core::mem::align_of_val(content) <= ALIGN
+					}
+					None => true,
+				}
+			}
+
+			/// Swaps the contents of both DynBoxes, if each fits into the other.
+			/// Returns false and leaves both unchanged otherwise. Spilled content stays
+			/// on the heap, so it fits into any DynBox.
+			pub fn swap_with<const OTHER: usize>(&mut self, other: &mut $name<'a, OTHER, ALIGN>) -> bool {
+				let size = self.stored_size();
+				let other_size = other.stored_size();
+				if size > OTHER || other_size > SIZE {
+					return false;
+				}
+
+				unsafe {
+					core::ptr::swap_nonoverlapping(
+						self.store.as_mut_ptr(),
+						other.store.as_mut_ptr(),
+						size.max(other_size),
+					);
+				}
+				let vtable = self.vtable;
+				self.set_vtable(other.vtable);
+				other.set_vtable(vtable);
+				core::mem::swap(&mut self.clone_fn, &mut other.clone_fn);
+				core::mem::swap(&mut self.type_id, &mut other.type_id);
+				core::mem::swap(&mut self.call_fn, &mut other.call_fn);
+				core::mem::swap(&mut self.type_name, &mut other.type_name);
+				core::mem::swap(&mut self.visit_fn, &mut other.visit_fn);
+				core::mem::swap(&mut self.spilled, &mut other.spilled);
+				self.placed();
+				other.placed();
+				true
+			}
+
+			/// Moves the content into `dst`, dropping the previous content of `dst` and
+			/// leaving this DynBox empty. If this DynBox is empty, `dst` is cleared.
+			/// Returns a `TooBig` error and leaves both unchanged if the content does not
+			/// fit into `dst`. Spilled content stays on the heap, so it fits into any
+			/// DynBox.
+			pub fn move_into<const OTHER: usize>(&mut self, dst: &mut $name<'a, OTHER, ALIGN>) -> Result<(), $crate::TooBig> {
+				let size = self.stored_size();
+				if size > OTHER {
+					return Err($crate::TooBig::new(size, OTHER));
+				}
+
+				dst.clear();
+				if !self.empty() {
+					unsafe { dst.store.as_mut_ptr().copy_from_nonoverlapping(self.store.as_ptr(), size) };
+					dst.set_vtable(self.vtable);
+					dst.copy_hooks(self);
+					core::mem::swap(&mut self.spilled, &mut dst.spilled);
+					self.vtable = $crate::Vtable::EMPTY;
+					self.next_generation();
+					dst.placed();
+				}
+				Ok(())
+			}
+
+			/// Swaps the contents of both DynBoxes, either of which may be empty. Unlike
+			/// `swap_with()`, this cannot fail, as both have the same size.
+			pub fn swap(&mut self, other: &mut Self) {
+				let swapped = self.swap_with(other);
+				debug_assert!(swapped);
+			}
+
+			/// Returns whether both DynBoxes are occupied by the same implementor of
+			/// $trait, judged by comparing their vtable pointers. Works for non-`'static`
+			/// types, where `TypeId` is unavailable.
+			///
+			/// This is advisory only: the compiler may emit duplicate vtables for the
+			/// same type (e.g. in different codegen units), so false negatives are
+			/// possible, and it may merge identical vtables of distinct types. Never rely
+			/// on this for soundness. Returns false if either DynBox is empty.
+			pub fn same_impl_as(&self, other: &Self) -> bool {
+				!self.empty() && self.vtable == other.vtable
+			}
+
+			/// Returns the size of the content, i.e. the smallest `SIZE` that could
+			/// hold it, or 0 if empty. Like `payload_size()`, but for sizing stores.
+			pub fn min_size(&self) -> usize {
+				self.payload_size().unwrap_or(0)
+			}
+
+			/// Returns the size of the store, i.e. `SIZE`. Can be evaluated at compile
+			/// time.
+			pub const fn capacity() -> usize {
+				SIZE
+			}
+
+			/// Returns the size of the content, or None if empty. Together with
+			/// `capacity()`, this tells how much of the store is in use.
+			pub fn payload_size(&self) -> Option<usize> {
+				self.get().map(core::mem::size_of_val)
+			}
+
+			/// Returns the alignment of the content, or None if empty.
+			pub fn payload_align(&self) -> Option<usize> {
+				self.get().map(core::mem::align_of_val)
+			}
+
+			/// Returns the address of the content's vtable if not empty, or None
+			/// otherwise. The vtable layout is unspecified, so the address is only
+			/// meaningful for identity comparisons, with the same caveats as for
+			/// `same_impl_as()`.
+			pub fn vtable_addr(&self) -> Option<usize> {
+				self.vtable.get().map(|vtable| vtable.as_ptr().addr())
+			}
+
+			/// Returns whether the stores of both DynBoxes overlap in memory, which
+			/// should only happen if they are the same DynBox. Meant as a sanity check
+			/// for debugging.
+			pub fn aliases<const OTHER: usize, const OTHER_ALIGN: usize>(
+				&self,
+				other: &$name<'_, OTHER, OTHER_ALIGN>,
+			) -> bool
+			where
+				$crate::Align<OTHER_ALIGN>: $crate::Alignment,
+			{
+				let start = self.store.as_ptr() as usize;
+				let other_start = other.store.as_ptr() as usize;
+				start < other_start + OTHER && other_start < start + SIZE
+			}
+
+			/// Returns a `&dyn Trait` reference if not empty, or None otherwise.
+			/// In debug builds, this panics if the recorded vtable was corrupted
+			/// since storing the content; release builds skip this check.
+			#[inline]
+			pub fn get(&self) -> Option<&dyn $trait> {
+				self.content()
+			}
+
+			/// Returns a `&mut dyn Trait` reference if not empty, or None otherwise.
+			#[inline]
+			pub fn get_mut(&mut self) -> Option<&mut dyn $trait> {
+				self.content_mut().map(|content| content as &mut dyn $trait)
+			}
+
+			/// Implements `get()`, keeping the content's lifetime.
+			#[inline]
+			fn content(&self) -> Option<&(dyn $trait + 'a)> {
+				$crate::__dynbox_if! { on_move in [$($flags)*] {
+					debug_assert!(
+						self.empty() || self.address == self.store.as_ptr() as usize,
+						"DynBox was moved; call get_mut() to run on_move() first"
+					);
+				}}
+				if self.empty() {
+					None
+				} else {
+					Some(unsafe { &*self.get_ptr() })
+				}
+			}
+
+			/// Implements `get_mut()`, keeping the content's lifetime.
+			#[inline]
+			fn content_mut(&mut self) -> Option<&mut (dyn $trait + 'a)> {
+				self.fix_moved();
+				if self.empty() {
+					None
+				} else {
+					Some(unsafe { &mut *self.get_ptr_mut() })
+				}
+			}
+
+			/// Calls `f` with a `&dyn Trait` reference to the content and returns its
+			/// result, or returns None if empty. Unlike with `get()`, the reference
+			/// cannot be held on to past the call.
+			pub fn with<R>(&self, f: impl FnOnce(&dyn $trait) -> R) -> Option<R> {
+				self.get().map(f)
+			}
+
+			/// Calls `f` with a `&mut dyn Trait` reference to the content and returns
+			/// its result, or returns None if empty.
+			pub fn with_mut<R>(&mut self, f: impl FnOnce(&mut dyn $trait) -> R) -> Option<R> {
+				self.get_mut().map(f)
+			}
+
+			/// Returns a raw `*const dyn Trait` pointer to the content if not empty,
+			/// or None otherwise. The pointer is valid until the DynBox is cleared,
+			/// overwritten or moved.
+			pub fn as_dyn_ptr(&self) -> Option<*const (dyn $trait + 'a)> {
+				if self.empty() {
+					None
+				} else {
+					Some(unsafe { self.get_ptr() })
+				}
+			}
+
+			/// Returns a raw `*mut dyn Trait` pointer to the content if not empty, or
+			/// None otherwise. The pointer is valid until the DynBox is cleared,
+			/// overwritten or moved.
+			pub fn as_dyn_ptr_mut(&mut self) -> Option<*mut (dyn $trait + 'a)> {
+				self.fix_moved();
+				if self.empty() {
+					None
+				} else {
+					Some(unsafe { self.get_ptr_mut() })
+				}
+			}
+
+			/// Returns a `&dyn Trait` reference if not empty, or an `EmptyError`
+			/// otherwise.
+			pub fn try_get(&self) -> Result<&dyn $trait, $crate::EmptyError> {
+				self.get().ok_or($crate::EmptyError)
+			}
+
+			/// Returns a `&mut dyn Trait` reference if not empty, or an `EmptyError`
+			/// otherwise.
+			pub fn try_get_mut(&mut self) -> Result<&mut dyn $trait, $crate::EmptyError> {
+				self.get_mut().ok_or($crate::EmptyError)
+			}
+
+			$crate::__dynbox_if! { no_panic in [$($flags)*] {} else {
+				/// Returns a `&dyn Trait` reference, or panics with `msg` if empty.
+				#[track_caller]
+				pub fn expect(&self, msg: &str) -> &dyn $trait {
+					match self.get() {
+						Some(content) => content,
+						None => panic!("{}", msg),
+					}
+				}
+
+				/// Returns a `&mut dyn Trait` reference, or panics with `msg` if empty.
+				#[track_caller]
+				pub fn expect_mut(&mut self, msg: &str) -> &mut dyn $trait {
+					match self.get_mut() {
+						Some(content) => content,
+						None => panic!("{}", msg),
+					}
+				}
+			}}
+
+			/// Returns a `&dyn Trait` reference without checking whether the DynBox is
+			/// empty, e.g. in hot loops. In debug builds, panics if it is.
+			///
+			/// # Safety
+			/// The DynBox must be occupied.
+			#[inline]
+			#[track_caller]
+			pub unsafe fn get_unchecked(&self) -> &dyn $trait {
+				debug_assert!(!self.empty(), "DynBox assumed to be occupied is empty");
+				&*self.get_ptr()
+			}
+
+			/// Returns a `&mut dyn Trait` reference without checking whether the DynBox
+			/// is empty. In debug builds, panics if it is.
+			///
+			/// # Safety
+			/// The DynBox must be occupied.
+			#[inline]
+			#[track_caller]
+			pub unsafe fn get_unchecked_mut(&mut self) -> &mut dyn $trait {
+				debug_assert!(!self.empty(), "DynBox assumed to be occupied is empty");
+				$crate::__dynbox_if! { on_move in [$($flags)*] {
+					if self.address != self.store.as_ptr() as usize {
+						self.placed();
+					}
+				}}
+				&mut *self.get_ptr_mut()
+			}
+
+			/// Reinterprets the store as a `&T`, without any checks.
+			///
+			/// # Safety
+			/// The DynBox must be occupied by a value of exactly type `T`.
+			pub unsafe fn get_as_unchecked<T: $trait>(&self) -> &T {
+				&*(self.data_ptr() as *const T)
+			}
+
+			/// Reinterprets the start of the content as a `&U`, without any type check.
+			/// Returns None if empty, or if a `U` is larger than the content or the
+			/// content is not aligned for it, which may happen when `ALIGN` is lowered
+			/// or the content was spilled to the heap.
+			///
+			/// # Safety
+			/// The first `size_of::<U>()` bytes of the content must be a valid `U`, as
+			/// with `core::mem::transmute`. This holds e.g. for the first field of a
+			/// `#[repr(C)]` struct, but not in general, as Rust gives no guarantees on
+			/// the layout of other types. The content must not be mutated through
+			/// interior mutability while the reference exists.
+			pub unsafe fn view_as<U>(&self) -> Option<&U> {
+				let size = core::mem::size_of_val(self.get()?);
+				let data = self.data_ptr() as *const U;
+				if core::mem::size_of::<U>() <= size && data.is_aligned() {
+					Some(&*data)
+				} else {
+					None
+				}
+			}
+
+			/// Views the content as a different trait object `U` if not empty, or
+			/// returns None otherwise. As the vtables differ, `cast` has to perform
+			/// the conversion, e.g. by upcasting to a supertrait, or by a conversion
+			/// method provided by the trait.
+			pub fn cast_via<'b, U: ?Sized + 'b, F: FnOnce(&'b dyn $trait) -> &'b U>(
+				&'b self,
+				cast: F,
+			) -> Option<&'b U> {
+				self.get().map(cast)
+			}
+
+			/// Returns the bytes occupied by the stored value if not empty, or None
+			/// otherwise.
+			///
+			/// # Safety
+			/// The stored type must not contain padding or other uninitialized bytes.
+			pub unsafe fn as_bytes(&self) -> Option<&[u8]> {
+				let size = core::mem::size_of_val(self.get()?);
+				Some(core::slice::from_raw_parts(self.data_ptr(), size))
+			}
+
+			/// Returns the bytes occupied by the stored value if not empty, or None
+			/// otherwise. Allows patching the stored value's representation in place.
+			///
+			/// # Safety
+			/// The stored type must not contain padding or other uninitialized bytes.
+			/// Writing a bit pattern that is invalid for the stored type is undefined
+			/// behaviour once the value is accessed (or dropped) again.
+			pub unsafe fn as_bytes_mut(&mut self) -> Option<&mut [u8]> {
+				let size = core::mem::size_of_val(self.get()?);
+				Some(core::slice::from_raw_parts_mut(self.data_ptr_mut(), size))
+			}
+
+			/// Returns an [`OccupiedBox`](crate::OccupiedBox) handle if not empty, or
+			/// None otherwise.
+			pub fn occupied(&mut self) -> Option<$crate::OccupiedBox<'_, Self, dyn $trait + 'a>> {
+				$crate::OccupiedBox::new(self)
+			}
+
+			$crate::__dynbox_if! { no_panic in [$($flags)*] {} else {
+				/// Moves the content out as raw bytes, together with its vtable (null if
+				/// empty) and size, without dropping it. The bytes are `MaybeUninit`, as
+				/// they may contain padding; like `ManuallyDrop`, this never drops the
+				/// content. Unless the parts are passed to `from_raw_parts()`, the
+				/// content is leaked and its destructor never runs. Panics if the content
+				/// was spilled to the heap.
+				#[track_caller]
+				pub fn into_raw_parts(mut self) -> (core::mem::MaybeUninit<[u8; SIZE]>, *const (), usize) {
+					assert!(!self.spilled(), "into_raw_parts() of spilled content");
+					let size = self.get().map_or(0, core::mem::size_of_val);
+					let mut bytes = core::mem::MaybeUninit::<[u8; SIZE]>::uninit();
+					unsafe { (bytes.as_mut_ptr() as *mut u8).copy_from_nonoverlapping(self.store.as_ptr(), size) };
+					let vtable = self.vtable.get().map_or(core::ptr::null(), |vtable| vtable.as_ptr() as *const ());
+					self.vtable = $crate::Vtable::EMPTY;
+					(bytes, vtable, size)
+				}
+
+				/// Moves the content out like `into_raw_parts()`, with the bytes wrapped in
+				/// `ManuallyDrop` to make explicit that no destructor is scheduled. Unless
+				/// the parts are passed to `from_manually_drop()`, the content is leaked.
+				/// Panics if the content was spilled to the heap.
+				#[track_caller]
+				pub fn into_manually_drop(self) -> (core::mem::ManuallyDrop<core::mem::MaybeUninit<[u8; SIZE]>>, *const (), usize) {
+					let (bytes, vtable, size) = self.into_raw_parts();
+					(core::mem::ManuallyDrop::new(bytes), vtable, size)
+				}
+
+				/// Returns a pointer to the content, together with its vtable (null if
+				/// empty) and size, e.g. to hand the store to a foreign message queue which
+				/// copies the bytes. The receiving side can rebuild the DynBox with
+				/// `read_raw_parts()`, after which this DynBox must be forgotten instead of
+				/// dropped, as both would own the content. Panics if the content was
+				/// spilled to the heap.
+				#[track_caller]
+				pub fn as_raw_parts(&self) -> (*const u8, *const (), usize) {
+					assert!(!self.spilled(), "as_raw_parts() of spilled content");
+					let size = self.get().map_or(0, core::mem::size_of_val);
+					let vtable = self.vtable.get().map_or(core::ptr::null(), |vtable| vtable.as_ptr() as *const ());
+					(self.store.as_ptr(), vtable, size)
+				}
+
+				$crate::__dynbox_if! { typed in [$($flags)*] {} else {
+					/// Reconstructs a DynBox from the parts returned by `into_raw_parts()`,
+					/// taking over ownership of the content again. Panics if `size` exceeds
+					/// `SIZE`.
+					///
+					/// # Safety
+					/// The parts must have been returned by `into_raw_parts()` of a DynBox
+					/// of this type, and must not have been used to reconstruct a DynBox
+					/// before.
+					#[track_caller]
+					pub unsafe fn from_raw_parts(bytes: core::mem::MaybeUninit<[u8; SIZE]>, vtable: *const (), size: usize) -> Self {
+						let mut dynbox = Self::new();
+						if let Some(vtable) = core::ptr::NonNull::new(vtable as *mut ()) {
+							assert!(size <= SIZE);
+							dynbox.store.as_mut_ptr().copy_from_nonoverlapping(bytes.as_ptr() as *const u8, size);
+							dynbox.set_vtable($crate::Vtable::occupied(vtable));
+							dynbox.forget_hooks();
+							dynbox.placed();
+						}
+						dynbox
+					}
+
+					/// Reconstructs a DynBox by copying `size` bytes of content from `data`,
+					/// which need not be aligned, like `from_raw_parts()`. Panics if `size`
+					/// exceeds `SIZE`.
+					///
+					/// # Safety
+					/// The parts must have been returned by `as_raw_parts()` of a DynBox of
+					/// this type, which is forgotten and was not used to reconstruct a DynBox
+					/// before, and `data` must still hold its content.
+					#[track_caller]
+					pub unsafe fn read_raw_parts(data: *const u8, vtable: *const (), size: usize) -> Self {
+						assert!(size <= SIZE);
+						let mut bytes = core::mem::MaybeUninit::<[u8; SIZE]>::uninit();
+						(bytes.as_mut_ptr() as *mut u8).copy_from_nonoverlapping(data, size);
+						Self::from_raw_parts(bytes, vtable, size)
+					}
+
+					/// Reconstructs a DynBox from the parts returned by
+					/// `into_manually_drop()`, like `from_raw_parts()`. Panics if `size`
+					/// exceeds `SIZE`.
+					///
+					/// # Safety
+					/// The parts must have been returned by `into_manually_drop()` of a
+					/// DynBox of this type, and must not have been used to reconstruct a
+					/// DynBox before.
+					#[track_caller]
+					pub unsafe fn from_manually_drop(bytes: core::mem::ManuallyDrop<core::mem::MaybeUninit<[u8; SIZE]>>, vtable: *const (), size: usize) -> Self {
+						Self::from_raw_parts(core::mem::ManuallyDrop::into_inner(bytes), vtable, size)
+					}
+				}}
+			}}
+
+			$crate::__if_alloc! {
+				/// Moves the content to the heap, returning it as `Some(Box<dyn Trait>)`,
+				/// or None if empty. Requires the `alloc` feature.
+				pub fn into_option_box(mut self) -> Option<$crate::__alloc::boxed::Box<dyn $trait + 'a>> {
+					self.move_to_heap()
+				}
+
+				/// Stores `content` and returns the previous value as `Some(Box<dyn Trait>)`,
+				/// or None if empty. Unlike `set()`, the previous value is moved to the heap
+				/// instead of being dropped. Requires the `alloc` feature.
+				pub fn exchange<T: $trait + 'a $($bounds)*>(
+					&mut self,
+					content: T,
+				) -> Option<$crate::__alloc::boxed::Box<dyn $trait + 'a>> {
+					let previous = self.move_to_heap();
+					self.set(content);
+					previous
+				}
+
+				/// Moves the content to the heap, leaving the DynBox empty.
+				fn move_to_heap(&mut self) -> Option<$crate::__alloc::boxed::Box<dyn $trait + 'a>> {
+					if self.empty() {
+						return None;
+					}
+
+					let layout = core::alloc::Layout::for_value(unsafe { &*self.get_ptr() });
+					if self.spilled() {
+						// Hand over the allocation, which has the layout a `Box` expects.
+						let content = unsafe { self.get_ptr_mut() };
+						self.vtable = $crate::Vtable::EMPTY;
+						$crate::__dynbox_if! { spill in [$($flags)*] {
+							self.spilled = false;
+						}}
+						return Some(unsafe { $crate::__alloc::boxed::Box::from_raw(content) });
+					}
+					unsafe {
+						let ptr = if layout.size() == 0 {
+							core::ptr::without_provenance_mut::<u8>(layout.align())
+						} else {
+							let ptr = $crate::__alloc::alloc::alloc(layout);
+							if ptr.is_null() {
+								$crate::__alloc::alloc::handle_alloc_error(layout);
+							}
+							ptr
+						};
+						ptr.copy_from_nonoverlapping(self.store.as_ptr(), layout.size());
+						let content = Self::content_ptr(ptr, self.vtable);
+						self.vtable = $crate::Vtable::EMPTY;
+						Some($crate::__alloc::boxed::Box::from_raw(content))
+					}
+				}
+			}
+
+			$crate::__dynbox_if! { clone in [$($flags)*] {
+				/// Clones the content into a new DynBox of a possibly different size.
+				/// Returns None if the content does not fit into `NEW` and `spill` is not
+				/// used; an empty DynBox is cloned into an empty one.
+				pub fn clone_to<const NEW: usize>(&self) -> Option<$name<'a, NEW, ALIGN>> {
+					let mut clone = $name::<NEW, ALIGN>::new();
+					if let Some(value) = self.get() {
+						let layout = core::alloc::Layout::for_value(value);
+						if !$name::<NEW, ALIGN>::accepts(layout) {
+							return None;
+						}
+
+						// SAFETY: As in `clone_from()`.
+						let clone_fn = unsafe { self.clone_fn.unwrap_unchecked() };
+						unsafe {
+							let ptr = clone.place(layout);
+							clone_fn(self.data_ptr(), ptr);
+						}
+						clone.set_vtable(self.vtable);
+						clone.copy_hooks(self);
+						clone.placed();
+					}
+					Some(clone)
+				}
+			}}
+
+			$crate::__dynbox_if! { downcast in [$($flags)*] {
+				/// Returns whether the DynBox currently contains a `T`.
+				pub fn is<T: $trait + 'static>(&self) -> bool {
+					!self.empty() && self.type_id.map(|type_id| type_id()) == Some(core::any::TypeId::of::<T>())
+				}
+
+				/// Returns a `&T` reference if the DynBox contains a `T`, or None otherwise.
+				pub fn downcast_ref<T: $trait + 'static>(&self) -> Option<&T> {
+					if self.is::<T>() {
+						Some(unsafe { self.get_as_unchecked::<T>() })
+					} else {
+						None
+					}
+				}
+
+				/// Returns a `&mut T` reference if the DynBox contains a `T`, or None
+				/// otherwise.
+				pub fn downcast_mut<T: $trait + 'static>(&mut self) -> Option<&mut T> {
+					if self.is::<T>() {
+						self.get_mut().map(|content| unsafe { &mut *(content as *mut dyn $trait as *mut T) })
+					} else {
+						None
+					}
+				}
+
+				/// Replaces the content with `content` if it is a `T`, reusing the recorded
+				/// vtable and hooks. Returns false and drops `content` if the DynBox does
+				/// not contain a `T`.
+				pub fn update<T: $trait + 'static>(&mut self, content: T) -> bool {
+					if !self.is::<T>() {
+						return false;
+					}
+
+					let vtable = self.vtable;
+					// Mark as empty while dropping, so a panicking destructor cannot cause
+					// a double drop.
+					self.vtable = $crate::Vtable::EMPTY;
+					unsafe {
+						let ptr = self.data_ptr_mut() as *mut T;
+						ptr.drop_in_place();
+						ptr.write(content);
+					}
+					self.set_vtable(vtable);
+					self.placed();
+					true
+				}
+
+				/// Clears every DynBox in `boxes` that contains a `T`, leaving the others
+				/// untouched. Returns the number of DynBoxes cleared.
+				pub fn clear_of_type<T: $trait + 'static>(boxes: &mut [Self]) -> usize {
+					let mut cleared = 0;
+					for dynbox in boxes.iter_mut() {
+						if dynbox.is::<T>() {
+							dynbox.clear();
+							cleared += 1;
+						}
+					}
+					cleared
+				}
+
+				/// Moves the content out if it is a `T`, and returns a new DynBox holding
+				/// `f` applied to it. Returns an empty DynBox if the content is not a `T`,
+				/// dropping it. Panics if the resulting `U` does not fit into `NEW`.
+				#[track_caller]
+				pub fn map<T, U, const NEW: usize, F>(mut self, f: F) -> $name<'a, NEW, ALIGN>
+				where
+					T: $trait + 'static,
+					U: $trait + 'a $($bounds)*,
+					F: FnOnce(T) -> U,
+				{
+					let mut result = $name::<NEW, ALIGN>::new();
+					if self.is::<T>() {
+						let value = unsafe { core::ptr::read(self.data_ptr() as *const T) };
+						self.vtable = $crate::Vtable::EMPTY;
+						unsafe { self.release(core::alloc::Layout::new::<T>()) };
+						self.poison();
+						result.set(f(value));
+					}
+					result
+				}
+
+				/// Moves the content out if it is a `T`, leaving the DynBox empty. Returns
+				/// None and leaves the DynBox untouched otherwise.
+				pub fn take<T: $trait + 'static>(&mut self) -> Option<T> {
+					if !self.is::<T>() {
+						return None;
+					}
+
+					let value = unsafe { core::ptr::read(self.data_ptr() as *const T) };
+					self.vtable = $crate::Vtable::EMPTY;
+					self.next_generation();
+					unsafe { self.release(core::alloc::Layout::new::<T>()) };
+					self.poison();
+					Some(value)
+				}
+
+				/// Moves the content out if it is a `T`, or gives the DynBox back
+				/// otherwise.
+				pub fn try_into_inner<T: $trait + 'static>(mut self) -> Result<T, Self> {
+					match self.take::<T>() {
+						Some(value) => Ok(value),
+						None => Err(self),
+					}
+				}
+
+				/// Moves the content out if it is a `T`, and stores the result of `f` in
+				/// its place, so that the DynBox is never observed empty. Returns None
+				/// and leaves the DynBox untouched if the content is not a `T`.
+				#[track_caller]
+				pub fn take_and_replace_with<T, U, F>(&mut self, f: F) -> Option<T>
+				where
+					T: $trait + 'static,
+					U: $trait + 'a $($bounds)*,
+					F: FnOnce() -> U,
+				{
+					if !self.is::<T>() {
+						return None;
+					}
+
+					let replacement = f();
+					let value = unsafe { core::ptr::read(self.data_ptr() as *const T) };
+					self.vtable = $crate::Vtable::EMPTY;
+					unsafe { self.release(core::alloc::Layout::new::<T>()) };
+					self.poison();
+					self.set(replacement);
+					Some(value)
+				}
+			}}
+
+			$crate::__dynbox_if! { checksum in [$($flags)*] {
+				/// Records a checksum over the content and its vtable, which `verify()`
+				/// compares against. Needs to be called again after modifying the
+				/// content.
+				///
+				/// # Safety
+				/// The stored type must not contain padding or other uninitialized bytes.
+				pub unsafe fn seal(&mut self) {
+					self.checksum = self.compute_checksum();
+				}
+
+				/// Returns whether the content and its vtable still match the checksum
+				/// recorded by the last `seal()`.
+				///
+				/// # Safety
+				/// The stored type must not contain padding or other uninitialized bytes.
+				pub unsafe fn verify(&self) -> bool {
+					self.checksum == self.compute_checksum()
+				}
+
+				unsafe fn compute_checksum(&self) -> u32 {
+					$crate::checksum(self.vtable.bits(), self.as_bytes().unwrap_or(&[]))
+				}
+			}}
+
+			$crate::__dynbox_if! { type_name in [$($flags)*] {
+				/// Returns the name of the stored type, as given by
+				/// `core::any::type_name()`, or None if empty or the type is unknown
+				/// because the content was stored through `set_from_dyn()`.
+				pub fn type_name(&self) -> Option<&'static str> {
+					if self.empty() {
+						None
+					} else {
+						self.type_name.map(|type_name| type_name())
+					}
+				}
+			}}
+
+			$crate::__dynbox_if! { visit in [$($flags)*] {
+				/// Reports the state of the content to `visitor`. Returns false without
+				/// calling it if empty.
+				pub fn visit(&self, visitor: &mut dyn $crate::Visitor) -> bool {
+					match self.visit_fn {
+						Some(visit_fn) if !self.empty() => {
+							unsafe { visit_fn(self.data_ptr(), visitor) };
+							true
+						}
+						_ => false,
+					}
+				}
+			}}
+
+			$crate::__dynbox_if! { sort_key in [$($flags)*] {
+				/// Returns the content's `dyn_sort_key()`, or `u64::MAX` if empty.
+				pub fn sort_key(&self) -> u64 {
+					match self.get() {
+						Some(content) => content.dyn_sort_key(),
+						None => u64::MAX,
+					}
+				}
+			}}
+
+			$crate::__dynbox_if! { generation in [$($flags)*] {
+				/// Returns the generation, which is incremented whenever the content is
+				/// set or cleared.
+				pub fn generation(&self) -> usize {
+					self.generation
+				}
+
+				/// Returns a mutable reference to the content together with a
+				/// `GenerationBump`, or None if empty. Allows marking a change of the
+				/// content made in place.
+				pub fn split_mut(&mut self) -> Option<(&mut dyn $trait, $crate::GenerationBump<'_>)> {
+					if self.empty() {
+						return None;
+					}
+
+					// Derive the content pointer from the store alone, so that it does not
+					// alias the generation.
+					self.fix_moved();
+					self.check_vtable();
+					let content = unsafe { &mut *Self::content_ptr(self.data_ptr_mut(), self.vtable) };
+					Some((content, $crate::GenerationBump::new(&mut self.generation)))
+				}
+
+				/// Returns a raw handle to the content, remembering the current
+				/// generation, or None if empty.
+				pub fn raw_handle(&mut self) -> Option<$crate::RawHandle<dyn $trait + 'a>> {
+					let generation = self.generation;
+					self.as_dyn_ptr_mut().map(|ptr| $crate::RawHandle::new(ptr, generation))
+				}
+
+				/// Turns a handle obtained through `raw_handle()` back into a reference. In
+				/// debug builds, panics if the content was set or cleared since.
+				///
+				/// # Safety
+				/// `handle` must have been obtained from this DynBox, which must neither
+				/// have been moved nor had its content set or cleared since.
+				pub unsafe fn resolve_raw(&mut self, handle: $crate::RawHandle<dyn $trait + 'a>) -> &mut (dyn $trait + 'a) {
+					debug_assert!(handle.generation() == self.generation, "stale RawHandle");
+					// Both point to the same content, but only a pointer taken through the
+					// reborrowed `self` is still valid to dereference.
+					&mut *self.get_ptr_mut()
+				}
+
+				$crate::__dynbox_if! { no_panic in [$($flags)*] {
+					/// Stores `content` in the DynBox at `index` of `boxes`, and returns a
+					/// handle to it, or gives `content` back if `index` is out of range.
+					pub fn set_at<T: $trait + 'a $($bounds)*>(
+						boxes: &mut [Self],
+						index: usize,
+						content: T,
+					) -> Result<$crate::Handle, T> {
+						match boxes.get_mut(index) {
+							Some(dynbox) => {
+								dynbox.set(content);
+								Ok($crate::Handle::new(index, dynbox.generation))
+							}
+							None => Err(content),
+						}
+					}
+				} else {
+					/// Stores `content` in the DynBox at `index` of `boxes`, and returns a
+					/// handle to it. Panics if `index` is out of range or `content` does not
+					/// fit.
+					#[track_caller]
+					pub fn set_at<T: $trait + 'a $($bounds)*>(boxes: &mut [Self], index: usize, content: T) -> $crate::Handle {
+						boxes[index].set(content);
+						$crate::Handle::new(index, boxes[index].generation)
+					}
+				}}
+
+				/// Returns a reference to the content `handle` was obtained for, or None
+				/// if that DynBox was set or cleared since, or `index` is out of range.
+				pub fn get_by_handle(boxes: &[Self], handle: $crate::Handle) -> Option<&dyn $trait> {
+					boxes
+						.get(handle.index())
+						.filter(|dynbox| dynbox.generation == handle.generation())
+						.and_then(|dynbox| dynbox.get())
+				}
+			}}
+
+			$crate::__dynbox_if! { pinned in [$($flags)*] {
+				/// Returns a pinned mutable reference to the content of the pinned
+				/// DynBox, or None if it is empty.
+				pub fn get_pin_mut(self: core::pin::Pin<&mut Self>) -> Option<core::pin::Pin<&mut dyn $trait>> {
+					// SAFETY: The DynBox is `!Unpin`, so its store does not move while
+					// pinned, and the content is dropped in place by `clear()`.
+					unsafe { self.get_unchecked_mut() }
+						.get_mut()
+						.map(|content| unsafe { core::pin::Pin::new_unchecked(content) })
+				}
+
+				/// Drops the content of the pinned DynBox in place and stores `content`,
+				/// which is pinned from then on.
+				#[track_caller]
+				pub fn set_pinned<T: $trait + 'a $($bounds)*>(self: core::pin::Pin<&mut Self>, content: T) {
+					// SAFETY: `set()` drops the previous content in place before reusing
+					// its storage, and does not move the DynBox.
+					unsafe { self.get_unchecked_mut() }.set(content);
+				}
+			}}
+
+			/// Records the hooks required by the enabled options for a freshly
+			/// stored `T`.
+			fn record_hooks<T: $trait + 'a $($bounds)*>(&mut self) {
+				$crate::__dynbox_if! { clone in [$($flags)*] {
+					self.clone_fn = Some($crate::clone_into::<T>);
+				}}
+				$crate::__dynbox_if! { downcast in [$($flags)*] {
+					self.type_id = Some(core::any::TypeId::of::<T>);
+				}}
+				$crate::__dynbox_if! { call_once in [$($flags)*] {
+					self.call_fn = Some($crate::call_once_into::<T, _>);
+				}}
+				$crate::__dynbox_if! { type_name in [$($flags)*] {
+					self.type_name = Some(core::any::type_name::<T>);
+				}}
+				$crate::__dynbox_if! { visit in [$($flags)*] {
+					self.visit_fn = Some($crate::visit_with::<T>);
+				}}
+			}
+
+			/// Resets the hooks for a value whose type is unknown.
+			fn forget_hooks(&mut self) {
+				$crate::__dynbox_if! { downcast in [$($flags)*] {
+					self.type_id = None;
+				}}
+				$crate::__dynbox_if! { type_name in [$($flags)*] {
+					self.type_name = None;
+				}}
+			}
+
+			/// Copies the hooks of `other`'s content, which was cloned or moved into
+			/// this DynBox.
+			fn copy_hooks<const OTHER: usize>(&mut self, other: &$name<'_, OTHER, ALIGN>) {
+				self.clone_fn = other.clone_fn;
+				self.type_id = other.type_id;
+				self.call_fn = other.call_fn;
+				self.type_name = other.type_name;
+				self.visit_fn = other.visit_fn;
+			}
+
+			/// Overwrites the store of an emptied DynBox with a poison pattern in debug
+			/// builds, unless `zeroize` is used.
+			fn poison(&mut self) {
+				$crate::__dynbox_if! { zeroize in [$($flags)*] {} else {
+					#[cfg(debug_assertions)]
+					unsafe { self.store.as_mut_ptr().write_bytes(0xDE, SIZE) }
+				}}
+			}
+
+			/// Counts a change of the content, if generations are tracked.
+			fn next_generation(&mut self) {
+				$crate::__dynbox_if! { generation in [$($flags)*] {
+					self.generation = self.generation.wrapping_add(1);
+				}}
+			}
+
+			/// Lets the content fix up its pointers if the DynBox was moved since it
+			/// last did, if `on_move` is used.
+			#[inline(always)]
+			fn fix_moved(&mut self) {
+				$crate::__dynbox_if! { on_move in [$($flags)*] {
+					if self.address != self.store.as_ptr() as usize {
+						self.placed();
+					}
+				}}
+			}
+
+			/// Records the current address of freshly stored or moved content, and
+			/// lets it fix up its pointers, if `on_move` is used.
+			fn placed(&mut self) {
+				$crate::__dynbox_if! { on_move in [$($flags)*] {
+					self.address = self.store.as_ptr() as usize;
+					if !self.empty() {
+						unsafe { (*self.get_ptr_mut()).on_move() }
+					}
+				}}
+			}
+
+			/// Records the vtable of freshly stored content.
+			fn set_vtable(&mut self, vtable: $crate::Vtable) {
+				self.vtable = vtable;
+				self.next_generation();
+				#[cfg(debug_assertions)]
+				{
+					self.vtable_check = !vtable.bits();
+				}
+			}
+
+			/// Returns a pointer to the content, which is only valid for reads.
+			#[inline]
+			unsafe fn get_ptr(&self) -> *const (dyn $trait + 'a) {
+				self.check_vtable();
+				Self::content_ptr(self.data_ptr() as *mut u8, self.vtable)
+			}
+
+			/// Returns a pointer to the content, which is also valid for writes.
+			#[inline]
+			unsafe fn get_ptr_mut(&mut self) -> *mut (dyn $trait + 'a) {
+				self.check_vtable();
+				Self::content_ptr(self.data_ptr_mut(), self.vtable)
+			}
+
+			/// Returns whether the content lives in a heap allocation, which is only
+			/// possible if `spill` is used.
+			#[inline]
+			fn spilled(&self) -> bool {
+				$crate::__dynbox_if!(spill in [$($flags)*] { self.spilled } else { false })
+			}
+
+			/// Returns a pointer to the content's bytes, which are in the store unless
+			/// the content was spilled. Only valid for reads.
+			#[inline]
+			fn data_ptr(&self) -> *const u8 {
+				if self.spilled() {
+					unsafe { (self.store.as_ptr() as *const *mut u8).read_unaligned() }
+				} else {
+					self.store.as_ptr()
+				}
+			}
+
+			/// Returns a pointer to the content's bytes like `data_ptr()`, which is
+			/// also valid for writes.
+			#[inline]
+			fn data_ptr_mut(&mut self) -> *mut u8 {
+				if self.spilled() {
+					unsafe { (self.store.as_ptr() as *const *mut u8).read_unaligned() }
+				} else {
+					self.store.as_mut_ptr()
+				}
+			}
+
+			/// Returns how many bytes of the store the content occupies: its size, or
+			/// that of the pointer to it if spilled. Returns 0 if empty.
+			fn stored_size(&self) -> usize {
+				match self.get() {
+					Some(_) if self.spilled() => core::mem::size_of::<*mut u8>(),
+					Some(content) => core::mem::size_of_val(content),
+					None => 0,
+				}
+			}
+
+			/// Returns whether content with `layout` can be stored, either in the store
+			/// or, if `spill` is used, on the heap.
+			fn accepts(layout: core::alloc::Layout) -> bool {
+				let fits = layout.size() <= SIZE && layout.align() <= ALIGN;
+				fits || $crate::__dynbox_if!(spill in [$($flags)*] { true } else { false })
+			}
+
+			$crate::__dynbox_if! { no_panic in [$($flags)*] {} else {
+				/// Panics unless content with `layout` can be stored.
+				#[track_caller]
+				fn check_fit(layout: core::alloc::Layout) {
+					if !Self::accepts(layout) {
+						assert!(layout.size() <= SIZE, "size {} > capacity {}", layout.size(), SIZE);
+						assert!(layout.align() <= ALIGN, "alignment {} > {}", layout.align(), ALIGN);
+					}
+				}
+
+				/// Panics for a `delegate` method without a default on an empty DynBox.
+				/// Not generated with `no_panic`, so that such methods fail to compile.
+				#[allow(dead_code)]
+				#[track_caller]
+				fn delegate_without_default(method: &str) -> ! {
+					panic!("called `{}()` on an empty DynBox", method)
+				}
+			}}
+
+			/// Panics unless a `T` can be stored, or, with `no_panic`, fails to
+			/// compile.
+			#[inline(always)]
+			#[track_caller]
+			fn check_fit_of<T>() {
+				$crate::__dynbox_if! { no_panic in [$($flags)*] {
+					const {
+						assert!(
+							Self::can_hold::<T>() || $crate::__dynbox_if!(spill in [$($flags)*] { true } else { false }),
+							"T does not fit into the DynBox"
+						)
+					}
+				} else {
+					Self::check_fit(core::alloc::Layout::new::<T>());
+				}}
+			}
+
+			/// Returns where to place content with `layout`, which `check_fit()` must
+			/// have accepted: the store if it fits, or a new heap allocation otherwise.
+			/// The DynBox must be empty.
+			unsafe fn place(&mut self, layout: core::alloc::Layout) -> *mut u8 {
+				$crate::__dynbox_if! { spill in [$($flags)*] {
+					self.spilled = layout.size() > SIZE || layout.align() > ALIGN;
+					if self.spilled {
+						let ptr = if layout.size() == 0 {
+							core::ptr::without_provenance_mut::<u8>(layout.align())
+						} else {
+							let ptr = $crate::__alloc::alloc::alloc(layout);
+							if ptr.is_null() {
+								$crate::__alloc::alloc::handle_alloc_error(layout);
+							}
+							ptr
+						};
+						(self.store.as_mut_ptr() as *mut *mut u8).write_unaligned(ptr);
+						return ptr;
+					}
+				}}
+				let _ = layout;
+				self.store.as_mut_ptr()
+			}
+
+			/// Frees the heap allocation of spilled content with `layout`, once the
+			/// content was dropped or moved out. Does nothing if it was not spilled.
+			unsafe fn release(&mut self, layout: core::alloc::Layout) {
+				$crate::__dynbox_if! { spill in [$($flags)*] {
+					if self.spilled {
+						let ptr = self.data_ptr_mut();
+						self.spilled = false;
+						if layout.size() != 0 {
+							$crate::__alloc::alloc::dealloc(ptr, layout);
+						}
+					}
+				}}
+				let _ = layout;
+			}
+
+			/// In debug builds, panics if the vtable was corrupted or is missing.
+			#[inline]
+			fn check_vtable(&self) {
+				#[cfg(debug_assertions)]
+				assert!(self.vtable.bits() == !self.vtable_check, "DynBox vtable was corrupted");
+				debug_assert!(!self.empty(), "content pointer of an empty DynBox");
+			}
+
+			/// Combines a pointer to the content's bytes with its vtable, keeping the
+			/// provenance of `data`. `vtable` must not be empty.
+			#[inline]
+			unsafe fn content_ptr(data: *mut u8, vtable: $crate::Vtable) -> *mut (dyn $trait + 'a) {
+				let vtable = vtable.get().unwrap_unchecked();
+				core::mem::transmute::<$crate::DynParts, *mut (dyn $trait + 'a)>($crate::DynParts { data, vtable })
+			}
+		}
+
+		impl<const SIZE: usize, const ALIGN: usize, const N: usize> $crate::AtLeast<N> for $name<'_, SIZE, ALIGN>
+		where
+			$crate::Align<ALIGN>: $crate::Alignment,
+		{
+			const FITS: () = assert!(SIZE >= N, "DynBox capacity is too small");
+		}
+
+		impl<'a, const SIZE: usize, const ALIGN: usize, T: $trait + 'a $($bounds)*> $crate::SetProven<T>
+			for $name<'a, SIZE, ALIGN>
+		where
+			$crate::Align<ALIGN>: $crate::Alignment,
+		{
+			unsafe fn set_proven(&mut self, content: T) {
+				if !self.empty() {
+					self.clear();
+				}
+				self.write(content);
+			}
+		}
+
+		impl<'a, const SIZE: usize, const ALIGN: usize> $crate::DynContainer<dyn $trait + 'a>
+			for $name<'a, SIZE, ALIGN>
+		where
+			$crate::Align<ALIGN>: $crate::Alignment,
+		{
+			#[inline]
+			fn get(&self) -> Option<&(dyn $trait + 'a)> {
+				self.content()
+			}
+
+			#[inline]
+			fn get_mut(&mut self) -> Option<&mut (dyn $trait + 'a)> {
+				self.content_mut()
+			}
+
+			fn clear(&mut self) {
+				$name::clear(self)
+			}
+
+			#[inline]
+			fn empty(&self) -> bool {
+				$name::empty(self)
+			}
+		}
+
+		$crate::__if_alloc! {
+			$crate::__dynbox_if! { typed in [$($flags)*] {} else {
+				impl<'a, const SIZE: usize, const ALIGN: usize>
+					TryFrom<$crate::__alloc::boxed::Box<dyn $trait + 'a>> for $name<'a, SIZE, ALIGN>
+				where
+					$crate::Align<ALIGN>: $crate::Alignment,
+				{
+					type Error = $crate::__alloc::boxed::Box<dyn $trait + 'a>;
+
+					fn try_from(boxed: Self::Error) -> Result<Self, Self::Error> {
+						$name::try_from_box(boxed)
+					}
+				}
+			}}
+		}
+
+		$crate::__dynbox_if! { display in [$($flags)*] {
+			impl<const SIZE: usize, const ALIGN: usize> core::fmt::Display for $name<'_, SIZE, ALIGN>
+			where
+				$crate::Align<ALIGN>: $crate::Alignment,
+			{
+				fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+					match self.get() {
+						Some(content) => core::fmt::Display::fmt(content, f),
+						None => f.write_str("<empty>"),
+					}
+				}
+			}
+		}}
+
+		$crate::__dynbox_if! { defmt in [$($flags)*] {
+			impl<const SIZE: usize, const ALIGN: usize> defmt::Format for $name<'_, SIZE, ALIGN>
+			where
+				$crate::Align<ALIGN>: $crate::Alignment,
+			{
+				fn format(&self, f: defmt::Formatter<'_>) {
+					match self.get() {
+						Some(content) => defmt::Format::format(content, f),
+						None => defmt::write!(f, "<empty>"),
+					}
+				}
+			}
+		}}
+
+		$crate::__dynbox_if! { defmt_type_name in [$($flags)*] {
+			impl<const SIZE: usize, const ALIGN: usize> defmt::Format for $name<'_, SIZE, ALIGN>
+			where
+				$crate::Align<ALIGN>: $crate::Alignment,
+			{
+				fn format(&self, f: defmt::Formatter<'_>) {
+					if self.empty() {
+						defmt::write!(f, "<empty>");
+					} else {
+						match self.type_name() {
+							Some(name) => defmt::write!(f, "{=str}", name),
+							None => defmt::write!(f, "<occupied>"),
+						}
+					}
+				}
+			}
+		}}
+
+		$crate::__dynbox_if! { debug in [$($flags)*] {
+			impl<const SIZE: usize, const ALIGN: usize> core::fmt::Debug for $name<'_, SIZE, ALIGN>
+			where
+				$crate::Align<ALIGN>: $crate::Alignment,
+			{
+				fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+					match self.get() {
+						Some(content) => core::fmt::Debug::fmt(content, f),
+						None => f.write_str("<empty>"),
+					}
+				}
+			}
+		}}
+
+		$crate::__dynbox_if! { future in [$($flags)*] {
+			impl<'a, const SIZE: usize, const ALIGN: usize> core::future::Future for $name<'a, SIZE, ALIGN>
+			where
+				$crate::Align<ALIGN>: $crate::Alignment,
+			{
+				type Output = <dyn $trait + 'a as core::future::Future>::Output;
+
+				#[track_caller]
+				fn poll(
+					self: core::pin::Pin<&mut Self>,
+					cx: &mut core::task::Context<'_>,
+				) -> core::task::Poll<Self::Output> {
+					$crate::__dynbox_if! { no_panic in [$($flags)*] {
+						match self.get_pin_mut() {
+							Some(content) => content.poll(cx),
+							None => core::task::Poll::Pending,
+						}
+					} else {
+						self.get_pin_mut().expect("polled an empty DynBox").poll(cx)
+					}}
+				}
+			}
+		}}
+
+		$crate::__dynbox_if! { eq_hash in [$($flags)*] {
+			impl<const SIZE: usize, const ALIGN: usize> PartialEq for $name<'_, SIZE, ALIGN>
+			where
+				$crate::Align<ALIGN>: $crate::Alignment,
+			{
+				fn eq(&self, other: &Self) -> bool {
+					match (self.get(), other.get()) {
+						(Some(content), Some(other)) => content.dyn_eq(other),
+						(content, other) => content.is_none() && other.is_none(),
+					}
+				}
+			}
+
+			impl<const SIZE: usize, const ALIGN: usize> core::hash::Hash for $name<'_, SIZE, ALIGN>
+			where
+				$crate::Align<ALIGN>: $crate::Alignment,
+			{
+				fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+					// Tell empty boxes apart from content which hashes nothing.
+					match self.get() {
+						Some(content) => {
+							state.write_u8(1);
+							content.dyn_hash(state);
+						}
+						None => state.write_u8(0),
+					}
+				}
+			}
+		}}
+
+		$crate::__dynbox_if! { downcast in [$($flags)*] {
+			impl<T, const SIZE: usize, const ALIGN: usize> PartialEq<T> for $name<'_, SIZE, ALIGN>
+			where
+				T: $trait + PartialEq + 'static,
+				$crate::Align<ALIGN>: $crate::Alignment,
+			{
+				fn eq(&self, other: &T) -> bool {
+					self.downcast_ref::<T>() == Some(other)
+				}
+			}
+		}}
+
+		$(
+			impl<'a> $name<'a, { $default_size }> {
+				/// Creates a new empty DynBox of the default size.
+				pub fn new_default_size() -> $name<'a, { $default_size }> {
+					$name::new()
+				}
+			}
+		)?
+
+		$crate::dynbox!(@ops $name, $trait, [$($ops)?] [$($bounds)*]);
+	};
+	(@ops $name:ident, $trait:path, [] $bounds:tt) => {};
+	(@ops $name:ident, $trait:path, [$ops:ident] [$($bounds:tt)*]) => {
+		#[doc = concat!(
+			"Operations of `", stringify!($name), "`, independent of its size. Allows code ",
+			"to accept any `", stringify!($name), "`, or a test double implementing this trait."
+		)]
+		pub trait $ops<'a>: $crate::DynContainer<dyn $trait + 'a> {
+			/// Stores `content`, clearing (and dropping) the previous value, if present.
+			fn set<T: $trait + 'a $($bounds)*>(&mut self, content: T);
+		}
+
+		impl<'a, const SIZE: usize, const ALIGN: usize> $ops<'a> for $name<'a, SIZE, ALIGN>
+		where
+			$crate::Align<ALIGN>: $crate::Alignment,
+		{
+			#[track_caller]
+			fn set<T: $trait + 'a $($bounds)*>(&mut self, content: T) {
+				$name::set(self, content)
+			}
+		}
+	};
+}
+
+#[allow(unused_macros)]
+#[macro_export]
+/// Generates a DynBox like [`dynbox!`] whose default size and alignment are
+/// the largest of the listed types, so that it can hold each of them. Further
+/// options may follow after another `;`.
+/// Example:
+/// ```
+/// use dyn_box::dynbox_for;
+/// trait MyTrait {}
+/// impl MyTrait for u8 {}
+/// impl MyTrait for [u64; 3] {}
+/// dynbox_for!(MyDynBox: MyTrait; u8, [u64; 3]);
+/// let mut my_dynbox = MyDynBox::new_default_size();
+/// my_dynbox.set([1u64, 2, 3]);
+/// ```
+macro_rules! dynbox_for {
+	($name:ident : $trait:path; $($type:ty),+ $(,)? $(; $($options:tt)*)?) => {
+		$crate::dynbox!(
+			$name: $trait,
+			default_size = $crate::max(&[$(core::mem::size_of::<$type>()),+]),
+			align_for = [$($type),+]
+			$(, $($options)*)?
+		);
+	};
+}
+
+#[cfg(doc)]
+/// Some object-safe trait
+pub trait MyTrait {}
+
+#[cfg(doc)]
+dynbox!(MyDynBox: MyTrait);
+
+#[cfg(doc)]
+dynslots!(MySlots: MyTrait, 4);
+
+#[cfg(test)]
+mod tests {
+	extern crate std;
+
+	use core::cell::Cell;
+
+	pub trait MyTrait {
+		fn foo(&self) -> u32;
+	}
+
+	#[derive(Clone, PartialEq)]
+	struct A;
+	#[derive(Clone, Default, PartialEq)]
+	struct B(u128);
+	#[derive(Clone, Copy)]
+	struct Number(u32);
+	#[repr(align(32))]
+	struct Wide(u8);
+	struct Droppable<'a>(&'a Cell<bool>);
+
+	impl MyTrait for A {
+		fn foo(&self) -> u32 {
+			1
+		}
+	}
+	impl MyTrait for B {
+		fn foo(&self) -> u32 {
+			self.0 as u32
+		}
+	}
+	impl MyTrait for Number {
+		fn foo(&self) -> u32 {
+			self.0
+		}
+	}
+	impl MyTrait for Wide {
+		fn foo(&self) -> u32 {
+			self.0 as u32
+		}
+	}
+	impl MyTrait for Droppable<'_> {
+		fn foo(&self) -> u32 {
+			2
+		}
+	}
+	impl Drop for Droppable<'_> {
+		fn drop(&mut self) {
+			self.0.set(true);
+		}
+	}
+
+	mod nested {
+		pub trait Nested {
+			fn bar(&self) -> u32;
+		}
+	}
+
+	impl nested::Nested for B {
+		fn bar(&self) -> u32 {
+			self.0 as u32 + 1
+		}
+	}
+
+	pub trait Both: MyTrait + nested::Nested {}
+
+	pub trait Shown: core::fmt::Display {}
+	impl Shown for B {}
+	impl core::fmt::Display for B {
+		fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+			write!(f, "B({})", self.0)
+		}
+	}
+	impl Both for B {}
+
+	pub trait Inspected: core::fmt::Debug {}
+	impl Inspected for u8 {}
+
+	/// Stand-in for the `defmt` crate, which logs into a string.
+	mod defmt {
+		pub struct Formatter<'f>(pub &'f core::cell::RefCell<super::std::string::String>);
+
+		pub trait Format {
+			fn format(&self, f: Formatter<'_>);
+		}
+
+		macro_rules! write {
+			($f:expr, "{=str}", $arg:expr) => {
+				$f.0.borrow_mut().push_str($arg)
+			};
+			($f:expr, $text:literal) => {
+				$f.0.borrow_mut().push_str($text)
+			};
+		}
+		pub(crate) use write;
+	}
+
+	pub trait Logged: defmt::Format {}
+	impl defmt::Format for u8 {
+		fn format(&self, f: defmt::Formatter<'_>) {
+			f.0.borrow_mut().push_str(&std::format!("{}", self));
+		}
+	}
+	impl Logged for u8 {}
+
+	pub trait Tally {
+		fn total(&self) -> u128;
+		fn add(&mut self, amount: u128, times: u128);
+	}
+	impl Tally for B {
+		fn total(&self) -> u128 {
+			self.0
+		}
+		fn add(&mut self, amount: u128, times: u128) {
+			self.0 += amount * times;
+		}
+	}
+
+	pub trait Keyed {
+		fn dyn_sort_key(&self) -> u64;
+	}
+	impl Keyed for B {
+		fn dyn_sort_key(&self) -> u64 {
+			self.0 as u64
+		}
+	}
+
+	pub trait Compared {
+		fn value(&self) -> u128;
+		fn dyn_eq(&self, other: &dyn Compared) -> bool;
+		fn dyn_hash(&self, state: &mut dyn core::hash::Hasher);
+	}
+	impl Compared for B {
+		fn value(&self) -> u128 {
+			self.0
+		}
+		fn dyn_eq(&self, other: &dyn Compared) -> bool {
+			self.0 == other.value()
+		}
+		fn dyn_hash(&self, state: &mut dyn core::hash::Hasher) {
+			state.write_u128(self.0);
+		}
+	}
+
+	dynbox!(DynBox: MyTrait);
+	dynbox!(DefaultSizedBox: MyTrait, default_size = 32);
+	dynbox!(AlignedBox: MyTrait, align_for = [A, B, Wide]);
+	dynbox!(SmallAlignBox: MyTrait, align = 4);
+	dynbox!(ClonableBox: MyTrait, clone);
+	dynbox!(DowncastBox: MyTrait, downcast);
+	dynbox!(PinnedBox: MyTrait, pinned);
+	dynbox!(FutureBox: core::future::Future<Output = u32>, future);
+	dynbox!(PendingFutureBox: core::future::Future<Output = u32>, future, no_panic);
+	dynbox!(NoPanicBox: MyTrait, no_panic, delegate { fn foo(&self) -> u32 = 0; });
+	dynbox!(NoPanicGenerationBox: MyTrait, no_panic, generation);
+	dynbox!(ThreadBox: MyTrait, send, sync);
+	dynbox!(PathBox: nested::Nested);
+	dynbox!(BothBox: Both, upcast = [get_as_my_trait: MyTrait, get_as_nested: nested::Nested]);
+	dynbox!(OpsBox: MyTrait, ops = OpsBoxOps);
+	dynbox!(CopyBox: MyTrait, copy);
+	dynbox!(UsefulBox: MyTrait, min_useful);
+	dynbox!(GenerationBox: MyTrait, generation);
+	dynbox!(FactoryBox: MyTrait { fn foo(&self) -> u32 }, default_size = 16);
+	dynbox!(TallyBox: Tally, delegate { fn total(&self) -> u128 = 0; fn add(&mut self, amount: u128, times: u128); });
+	#[cfg(feature = "alloc")]
+	dynbox!(StableBox: MyTrait, stable_addr);
+	#[cfg(feature = "alloc")]
+	dynbox!(SpillBox: MyTrait, spill);
+	dynbox!(DisplayBox: Shown, display);
+	dynbox!(DebugBox: Inspected, debug);
+	dynbox!(LoggedBox: Logged, defmt);
+	dynbox!(NamedLoggedBox: MyTrait, defmt_type_name);
+	dynbox!(KeyedBox: Keyed, sort_key);
+	dynbox!(ComparedBox: Compared, eq_hash, downcast);
+	dynbox!(NamedBox: MyTrait, type_name);
+	dynbox!(SharedBox: MyTrait, repr_c, clone);
+	dynbox!(CheckedBox: MyTrait, checksum);
+	dynbox!(ZeroizedBox: MyTrait, zeroize);
+	dynbox!(VisitedBox: MyTrait, visit);
+	dynbox_for!(ForBox: MyTrait; A, B, Wide);
+	dynbox!(ForSyntaxBox: MyTrait for [A, B], clone);
+	dynbox!(SizedBox: MyTrait, sizes = [SizedBoxSmall = 16, SizedBoxMedium = 64]);
+
+	const _: () = assert!(DynBox::<64>::can_hold::<B>());
+
+	impl crate::Visit for B {
+		fn visit(&self, visitor: &mut dyn crate::Visitor) {
+			visitor.field("value", crate::Value::Uint(self.0 as u64));
+		}
+	}
+
+	fn address_of(value: &dyn MyTrait) -> usize {
+		value as *const dyn MyTrait as *const u8 as usize
+	}
+
+	#[test]
+	fn trait_object_pointers_are_two_words() {
+		assert!(core::mem::size_of::<*const dyn MyTrait>() == 2 * core::mem::size_of::<usize>());
+	}
+
+	#[test]
+	fn new_dynbox_is_empty() {
+		let mut dynbox = DynBox::<64>::new();
+		assert!(dynbox.empty());
+		assert!(dynbox.get().is_none());
+		assert!(dynbox.get_mut().is_none());
+	}
+
+	#[test]
+	fn set_dynbox_is_not_empty_and_clear_makes_it_empty() {
+		let a = A;
+
+		let mut dynbox = DynBox::<64>::new();
+		dynbox.set(a);
+		assert!(!dynbox.empty());
+
+		dynbox.clear();
+		assert!(dynbox.empty());
+	}
+
+	#[test]
+	fn zero_sized() {
+		let a = A;
+
+		let mut dynbox = DynBox::<64>::new();
+		assert!(dynbox.empty());
+		assert!(dynbox.get().is_none());
+		assert!(dynbox.get_mut().is_none());
+
+		dynbox.set(a);
+		assert!(!dynbox.empty());
+
+		assert!(dynbox.get().unwrap().foo() == 1);
+		assert!(dynbox.get_mut().unwrap().foo() == 1);
+	}
+
+	#[test]
+	fn repeated_store_get_cycle() {
+		let mut dynbox = DynBox::<16>::new();
+		for i in 0..100 {
+			dynbox.set(B(i));
+			assert!(!dynbox.empty());
+			assert!(dynbox.get().unwrap().foo() == i as u32);
+			assert!(dynbox.get_mut().unwrap().foo() == i as u32);
+		}
+	}
+
+	#[test]
+	fn set_smaller() {
+		let b = B(42);
+
+		let mut dynbox = DynBox::<64>::new();
+		dynbox.set(b);
+		assert!(dynbox.get().unwrap().foo() == 42);
+		assert!(dynbox.get_mut().unwrap().foo() == 42);
+
+		dynbox.clear();
+	}
+
+	#[test]
+	fn set_same_size() {
+		let b = B(42);
+
+		let mut dynbox = DynBox::<16>::new();
+		dynbox.set(b);
+		assert!(dynbox.get().unwrap().foo() == 42);
+		assert!(dynbox.get_mut().unwrap().foo() == 42);
+	}
+
+	#[test]
+	fn set_same_size_with_smaller_alignment() {
+		struct Bytes([u8; 12]);
+		struct Words([u32; 3]);
+		struct Longs([u64; 3]);
+		impl MyTrait for Bytes {
+			fn foo(&self) -> u32 {
+				self.0[11] as u32
+			}
+		}
+		impl MyTrait for Words {
+			fn foo(&self) -> u32 {
+				self.0[2]
+			}
+		}
+		impl MyTrait for Longs {
+			fn foo(&self) -> u32 {
+				self.0[2] as u32
+			}
+		}
+
+		let mut dynbox = DynBox::<12>::new();
+		dynbox.set(Bytes([7; 12]));
+		assert!(dynbox.get().unwrap().foo() == 7);
+		dynbox.set(Words([9; 3]));
+		assert!(dynbox.get().unwrap().foo() == 9);
+
+		let mut dynbox = DynBox::<24>::new();
+		dynbox.set(Longs([5; 3]));
+		assert!(dynbox.get().unwrap().foo() == 5);
+		assert!(unsafe { dynbox.as_bytes() }.unwrap() == 5u64.to_ne_bytes().repeat(3));
+	}
+
+	#[test]
+	#[should_panic(expected = "size 16 > capacity 8")]
+	fn set_too_large_reports_sizes() {
+		DynBox::<8>::new().set(B(1));
+	}
+
+	#[test]
+	#[should_panic]
+	fn set_too_large_panics() {
+		let b = B(42);
+
+		let mut dynbox = DynBox::<4>::new();
+		dynbox.set(b);
+		assert!(dynbox.get().unwrap().foo() == 42);
+		assert!(dynbox.get_mut().unwrap().foo() == 42);
+	}
+
+	#[test]
+	fn try_set_gives_back_too_large_value() {
+		let mut dynbox = DynBox::<4>::new();
+		dynbox.set(A);
+		let error = dynbox.try_set(B(42)).err().unwrap();
+		assert!(error.size() == 16 && error.capacity() == 4);
+		assert!(error.into_inner().0 == 42);
+		assert!(dynbox.get().unwrap().foo() == 1);
+
+		let mut dynbox = DynBox::<16>::new();
+		assert!(dynbox.try_set(B(42)).is_ok());
+		assert!(dynbox.get().unwrap().foo() == 42);
+	}
+
+	#[test]
+	fn set_checked_stores_fitting_value() {
+		let mut dynbox = DynBox::<16>::new();
+		dynbox.set_checked(B(42));
+		assert!(dynbox.get().unwrap().foo() == 42);
+	}
+
+	#[test]
+	fn new_is_usable_in_const_contexts() {
+		static STATIC_BOX: ThreadBox<16> = ThreadBox::new();
+		const EMPTY: DynBox<'static, 16> = DynBox::EMPTY;
+		assert!(STATIC_BOX.empty());
+		assert!(EMPTY.empty());
+	}
+
+	#[test]
+	fn replace_with_sees_previous_content() {
+		let mut dynbox = DynBox::<16>::new();
+		dynbox.replace_with(|previous| {
+			assert!(previous.is_none());
+			B(1)
+		});
+		dynbox.replace_with(|previous| B(previous.unwrap().foo() as u128 + 1));
+		assert!(dynbox.get().unwrap().foo() == 2);
+
+		let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+			dynbox.replace_with(|_| -> B { panic!("no replacement") })
+		}));
+		assert!(result.is_err());
+		assert!(dynbox.get().unwrap().foo() == 2);
+	}
+
+	#[test]
+	fn get_or_insert_with_initializes_once() {
+		let mut dynbox = DynBox::<16>::new();
+		assert!(dynbox.get_or_insert_with(|| B(1)).foo() == 1);
+		assert!(dynbox.get_or_insert_with(|| B(2)).foo() == 1);
+	}
+
+	#[test]
+	fn default_is_empty() {
+		assert!(DynBox::<16>::default().empty());
+	}
+
+	#[test]
+	fn insert_returns_stored_content() {
+		let mut dynbox = DynBox::<16>::new();
+		assert!(dynbox.insert(B(42)).foo() == 42);
+		assert!(dynbox.get().unwrap().foo() == 42);
+	}
+
+	#[test]
+	fn new_with_creates_occupied_box() {
+		struct Holder<'a> {
+			dynbox: DynBox<'a, 16>,
+		}
+		let holder = Holder {
+			dynbox: DynBox::new_with(B(42)),
+		};
+		assert!(holder.dynbox.get().unwrap().foo() == 42);
+	}
+
+	#[test]
+	fn from_value_converts_values() {
+		let boxes: [DynBox<16>; 2] = [B(1), B(2)].map(DynBox::from_value);
+		assert!(boxes[0].get().unwrap().foo() == 1);
+		assert!(boxes[1].get().unwrap().foo() == 2);
+	}
+
+	#[test]
+	fn overwrite_unchecked_stores_into_cleared_box() {
+		let mut dynbox = DynBox::<16>::new();
+		for i in 0..3 {
+			dynbox.clear();
+			unsafe { dynbox.overwrite_unchecked(B(i)) };
+			assert!(dynbox.get().unwrap().foo() == i as u32);
+		}
+	}
+
+	#[test]
+	fn set_fn_initializes_in_place() {
+		let mut dynbox = DynBox::<64>::new();
+		unsafe { dynbox.set_fn(|ptr: *mut B| ptr.write(B(77))) };
+		assert!(dynbox.get().unwrap().foo() == 77);
+	}
+
+	#[test]
+	fn transition_replaces_content_with_successor() {
+		let mut dynbox = DynBox::<16>::new();
+		assert!(!dynbox.transition(|_| Some(B(1))));
+		assert!(dynbox.empty());
+
+		dynbox.set(B(1));
+		assert!(dynbox.transition(|state| Some(B(state.foo() as u128 + 1))));
+		assert!(!dynbox.transition(|_| None::<B>));
+		assert!(dynbox.get().unwrap().foo() == 2);
+	}
+
+	#[test]
+	fn set_with_stores_returned_value() {
+		let mut dynbox = DynBox::<64>::new();
+		dynbox.set(B(1));
+		dynbox.set_with(|| B(77));
+		assert!(dynbox.get().unwrap().foo() == 77);
+	}
+
+	#[test]
+	fn emplace_with_initializes_in_place() {
+		let mut dynbox = DynBox::<64>::new();
+		dynbox.emplace_with(|slot| slot.write(B(77)));
+		assert!(dynbox.get().unwrap().foo() == 77);
+	}
+
+	#[test]
+	#[should_panic(expected = "emplace_with() must initialize the given slot")]
+	fn emplace_with_rejects_other_reference() {
+		// Zero-sized, so leaking it does not actually leak memory.
+		let other = std::boxed::Box::leak(std::boxed::Box::new(A));
+		DynBox::<64>::new().emplace_with(|_| other);
+	}
+
+	#[test]
+	fn set_from_dyn_takes_ownership() {
+		let drop_was_called = Cell::new(false);
+		let mut value = core::mem::ManuallyDrop::new(Droppable(&drop_was_called));
+		let mut dynbox = DynBox::<64>::new();
+
+		unsafe { dynbox.set_from_dyn(&mut *value) };
+		assert!(dynbox.get().unwrap().foo() == 2);
+		assert!(!drop_was_called.get());
+
+		dynbox.clear();
+		assert!(drop_was_called.get());
+	}
+
+	#[test]
+	fn set_from_dyn_round_trip() {
+		let mut value = core::mem::ManuallyDrop::new(B(42));
+		let mut dynbox = DynBox::<16>::new();
+		unsafe { dynbox.set_from_dyn(&mut *value) };
+		assert!(dynbox.get().unwrap().foo() == 42);
+	}
+
+	#[test]
+	fn set_from_bytes_and_sample_reconstitutes_value() {
+		let original = B(5);
+		let bytes = unsafe {
+			core::slice::from_raw_parts(
+				&original as *const B as *const u8,
+				core::mem::size_of::<B>(),
+			)
+		};
+		let mut dynbox = DynBox::<16>::new();
+		unsafe { dynbox.set_from_bytes_and_sample(bytes, &B(0)) };
+		assert!(dynbox.get().unwrap().foo() == 5);
+	}
+
+	#[test]
+	fn reset_to_default_stores_default_value() {
+		let mut dynbox = DynBox::<64>::new();
+		dynbox.set(B(5));
+		assert!(dynbox.get().unwrap().foo() == 5);
+
+		dynbox.reset_to_default::<B>();
+		assert!(dynbox.get().unwrap().foo() == 0);
+	}
+
+	#[test]
+	fn same_impl_as_compares_stored_types() {
+		let drop_was_called = Cell::new(false);
+		let mut first = DynBox::<64>::new();
+		let mut second = DynBox::<64>::new();
+		assert!(!first.same_impl_as(&second));
+
+		first.set(Droppable(&drop_was_called));
+		second.set(Droppable(&drop_was_called));
+		// Equal vtables for the same type are likely, but not guaranteed, and Miri
+		// hands out a new one for each use.
+		#[cfg(not(miri))]
+		assert!(first.same_impl_as(&second));
+
+		second.set(A);
+		assert!(!first.same_impl_as(&second));
+	}
+
+	#[test]
+	fn swap_with_exchanges_fitting_contents() {
+		let mut small = DynBox::<16>::new();
+		let mut large = DynBox::<64>::new();
+		small.set(A {});
+		large.set(B(42));
+		assert!(small.swap_with(&mut large));
+		assert!(small.get().unwrap().foo() == 42 && large.get().unwrap().foo() == 1);
+
+		large.clear();
+		assert!(small.swap_with(&mut large));
+		assert!(small.empty() && large.get().unwrap().foo() == 42);
+	}
+
+	#[test]
+	fn move_into_transfers_fitting_content() {
+		let drop_was_called = Cell::new(false);
+		let mut scratch = DynBox::<64>::new();
+		let mut small = DynBox::<16>::new();
+		small.set(Droppable(&drop_was_called));
+		scratch.set(B(3));
+		assert!(scratch.move_into(&mut small).is_ok());
+		assert!(drop_was_called.get());
+		assert!(scratch.empty() && small.get().unwrap().foo() == 3);
+
+		let mut tiny = DynBox::<8>::new();
+		let error = small.move_into(&mut tiny).unwrap_err();
+		assert!(error.size() == 16 && error.capacity() == 8);
+		assert!(small.get().unwrap().foo() == 3);
+		assert!(scratch.move_into(&mut small).is_ok());
+		assert!(small.empty());
+	}
+
+	#[test]
+	fn swap_exchanges_contents() {
+		let mut front = DynBox::<16>::new();
+		let mut back = DynBox::<16>::new();
+		front.set(B(5));
+		front.swap(&mut back);
+		assert!(front.empty() && back.get().unwrap().foo() == 5);
+
+		front.set(A {});
+		front.swap(&mut back);
+		assert!(front.get().unwrap().foo() == 5 && back.get().unwrap().foo() == 1);
+	}
+
+	#[test]
+	fn at_least_accepts_larger_box() {
+		fn store_widget<'a>(dynbox: &mut (impl crate::AtLeast<64> + OpsBoxOps<'a>)) {
+			dynbox.at_least().set(B(64));
+		}
+
+		let mut dynbox = OpsBox::<128>::new();
+		store_widget(&mut dynbox);
+		assert!(dynbox.get().unwrap().foo() == 64);
+	}
+
+	#[test]
+	fn scoped_set_clears_when_guard_drops() {
+		let mut dynbox = DynBox::<16>::new();
+		{
+			let guard = dynbox.set_scoped(B(5));
+			assert!(guard.foo() == 5);
+		}
+		assert!(dynbox.empty());
+	}
+
+	#[test]
+	fn proven_setter_stores_value() {
+		let mut dynbox = DynBox::<16>::new();
+		dynbox.prove::<B>().set(B(4));
+		assert!(dynbox.get().unwrap().foo() == 4);
+	}
+
+	#[test]
+	fn assume_occupied_after_set() {
+		let mut dynbox = DynBox::<16>::new();
+		dynbox.set(B(7));
+		unsafe { dynbox.assume_occupied() };
+		assert!(dynbox.get().unwrap().foo() == 7);
+	}
+
+	#[cfg(debug_assertions)]
+	#[test]
+	#[should_panic(expected = "DynBox assumed to be occupied is empty")]
+	fn assume_occupied_on_empty_box_panics() {
+		unsafe { DynBox::<16>::new().assume_occupied() };
+	}
+
+	#[test]
+	fn get_unchecked_returns_content() {
+		let mut dynbox = DynBox::<16>::new();
+		dynbox.set(B(7));
+		assert!(unsafe { dynbox.get_unchecked() }.foo() == 7);
+		assert!(unsafe { dynbox.get_unchecked_mut() }.foo() == 7);
+	}
+
+	#[cfg(debug_assertions)]
+	#[test]
+	#[should_panic(expected = "DynBox assumed to be occupied is empty")]
+	fn get_unchecked_on_empty_box_panics() {
+		unsafe { DynBox::<16>::new().get_unchecked() };
+	}
+
+	#[test]
+	fn take_box_leaves_empty_box() {
+		let mut dynbox = DynBox::<16>::new();
+		dynbox.set(B(3));
+		let taken = dynbox.take_box();
+		assert!(dynbox.empty());
+		assert!(taken.get().unwrap().foo() == 3);
+	}
+
+	#[test]
+	fn replace_box_returns_previous_box() {
+		let mut dynbox = DynBox::<16>::new();
+		dynbox.set(B(3));
+		let mut new = DynBox::<16>::new();
+		new.set(B(4));
+
+		let old = dynbox.replace_box(new);
+		assert!(dynbox.get().unwrap().foo() == 4);
+		assert!(old.get().unwrap().foo() == 3);
+	}
+
+	#[test]
+	fn could_accept_checks_content_of_other_box() {
+		let mut small = DynBox::<16>::new();
+		let mut large = DynBox::<64, 32>::new();
+		assert!(small.could_accept(&large));
+
+		small.set(B(1));
+		large.set(Wide(2));
+		assert!(large.could_accept(&small));
+		assert!(!small.could_accept(&large));
+		assert!(!DynBox::<64, 8>::new().could_accept(&small));
+	}
+
+	#[test]
+	fn swap_with_rejects_too_large_content() {
+		struct Large([u8; 32]);
+		impl MyTrait for Large {
+			fn foo(&self) -> u32 {
+				self.0[0] as u32
+			}
+		}
+
+		let mut small = DynBox::<16>::new();
+		let mut large = DynBox::<64>::new();
+		small.set(B(1));
+		large.set(Large([2; 32]));
+		assert!(!small.swap_with(&mut large));
+		assert!(small.get().unwrap().foo() == 1 && large.get().unwrap().foo() == 2);
+	}
+
+	#[cfg(debug_assertions)]
+	#[test]
+	#[should_panic(expected = "content pointer of an empty DynBox")]
+	fn content_pointer_of_empty_box_panics() {
+		let foo = DynBox::<16>::new();
+		let _ = unsafe { foo.get_ptr() };
+	}
+
+	#[test]
+	fn references_from_content_borrow_from_box() {
+		pub trait Buffer {
+			fn data(&self) -> &[u8];
+		}
+		struct Inline([u8; 8]);
+		impl Buffer for Inline {
+			fn data(&self) -> &[u8] {
+				&self.0[..4]
+			}
+		}
+		dynbox!(BufferBox: Buffer);
+
+		let mut dynbox = BufferBox::<16>::new();
+		dynbox.set(Inline([1, 2, 3, 4, 5, 6, 7, 8]));
+		let first = dynbox.get().unwrap().data();
+		let second = dynbox.get().unwrap().data();
+		assert!(first == [1, 2, 3, 4] && first == second);
+		assert!(first.as_ptr() == second.as_ptr());
+	}
+
+	#[test]
+	fn min_size_is_size_of_content() {
+		let mut dynbox = DynBox::<64>::new();
+		assert!(dynbox.min_size() == 0);
+		dynbox.set(B(1));
+		assert!(dynbox.min_size() == 16);
+	}
+
+	#[test]
+	fn payload_size_and_align_describe_content() {
+		const { assert!(DynBox::<64>::capacity() == 64) }
+		let mut dynbox = AlignedBox::<64>::new();
+		assert!(dynbox.payload_size().is_none() && dynbox.payload_align().is_none());
+		dynbox.set(Wide(1));
+		assert!(dynbox.payload_size() == Some(32) && dynbox.payload_align() == Some(32));
+	}
+
+	#[test]
+	fn option_of_box_uses_niche() {
+		assert!(core::mem::size_of::<Option<DynBox<32>>>() == core::mem::size_of::<DynBox<32>>());
+	}
+
+	#[test]
+	fn vtable_addr_identifies_type() {
+		let mut first = DynBox::<16>::new();
+		let mut second = DynBox::<32>::new();
+		assert!(first.vtable_addr().is_none());
+		first.set(B(1));
+		second.set(B(2));
+		assert!(first.vtable_addr().is_some());
+		// Likely, but not guaranteed, and Miri hands out a new vtable for each use.
+		#[cfg(not(miri))]
+		assert!(first.vtable_addr() == second.vtable_addr());
+	}
+
+	#[test]
+	fn only_same_box_aliases() {
+		let first = DynBox::<16>::new();
+		let second = DynBox::<32>::new();
+		assert!(!first.aliases(&second) && !second.aliases(&first));
+		assert!(first.aliases(&first));
+	}
+
+	#[test]
+	fn as_bytes_covers_stored_value() {
+		let mut dynbox = DynBox::<64>::new();
+		assert!(unsafe { dynbox.as_bytes() }.is_none());
+
+		dynbox.set(B(42));
+		let bytes = unsafe { dynbox.as_bytes() }.unwrap();
+		assert!(bytes.len() == 16);
+		assert!(bytes[..] == 42u128.to_ne_bytes());
+
+		dynbox.set(A);
+		assert!(unsafe { dynbox.as_bytes() }.unwrap().is_empty());
+	}
+
+	#[test]
+	fn patching_bytes_changes_stored_value() {
+		let mut dynbox = DynBox::<64>::new();
+		dynbox.set(B(42));
+
+		let bytes = unsafe { dynbox.as_bytes_mut() }.unwrap();
+		bytes.copy_from_slice(&7u128.to_ne_bytes());
+		assert!(dynbox.get().unwrap().foo() == 7);
+	}
+
+	#[test]
+	#[cfg(debug_assertions)]
+	fn clear_poisons_store_in_debug_builds() {
+		let mut dynbox = DynBox::<64>::new();
+		dynbox.set(B(42));
+
+		dynbox.clear();
+		let store = unsafe { core::slice::from_raw_parts(dynbox.store.as_ptr(), 64) };
+		assert!(store.iter().all(|&byte| byte == 0xDE));
+		assert!(dynbox.get().is_none());
+	}
+
+	#[test]
+	#[cfg(debug_assertions)]
+	fn take_poisons_store_in_debug_builds() {
+		let mut dynbox = DowncastBox::<64>::new();
+		dynbox.set(B(42));
+		assert!(dynbox.take::<B>().unwrap().0 == 42);
+		let store = unsafe { core::slice::from_raw_parts(dynbox.store.as_ptr(), 64) };
+		assert!(store.iter().all(|&byte| byte == 0xDE));
+
+		dynbox.set(B(42));
+		assert!(dynbox.take_and_replace_with::<B, _, _>(|| A {}).unwrap().0 == 42);
+		let store = unsafe { core::slice::from_raw_parts(dynbox.store.as_ptr(), 64) };
+		assert!(store.iter().all(|&byte| byte == 0xDE));
+	}
+
+	#[test]
+	fn dyn_ptr_points_to_content() {
+		let mut dynbox = DynBox::<16>::new();
+		assert!(dynbox.as_dyn_ptr().is_none() && dynbox.as_dyn_ptr_mut().is_none());
+
+		dynbox.set(B(42));
+		assert!(unsafe { (*dynbox.as_dyn_ptr().unwrap()).foo() } == 42);
+		assert!(unsafe { (*dynbox.as_dyn_ptr_mut().unwrap()).foo() } == 42);
+	}
+
+	#[test]
+	fn try_get_propagates_empty_error() {
+		fn foo_of(dynbox: &DynBox<16>) -> Result<u32, crate::EmptyError> {
+			Ok(dynbox.try_get()?.foo())
+		}
+
+		let mut dynbox = DynBox::<16>::new();
+		assert!(foo_of(&dynbox) == Err(crate::EmptyError));
+		dynbox.set(B(42));
+		assert!(foo_of(&dynbox) == Ok(42));
+		assert!(dynbox.try_get_mut().unwrap().foo() == 42);
+	}
+
+	#[test]
+	fn empty_error_is_an_error() {
+		use std::string::ToString;
+
+		let dynbox = DynBox::<16>::new();
+		let error: &dyn core::error::Error = &dynbox.try_get().err().unwrap();
+		assert!(error.to_string() == "container is empty");
+		assert!(error.source().is_none());
+	}
+
+	#[test]
+	fn expect_returns_content() {
+		let mut dynbox = DynBox::<16>::new();
+		dynbox.set(B(42));
+		assert!(dynbox.expect("no widget").foo() == 42);
+		assert!(dynbox.expect_mut("no widget").foo() == 42);
+	}
+
+	#[test]
+	#[should_panic(expected = "no widget")]
+	fn expect_panics_with_message_if_empty() {
+		DynBox::<16>::new().expect("no widget");
+	}
+
+	#[test]
+	fn occupied_box_only_for_occupied_dynbox() {
+		let mut dynbox = DynBox::<64>::new();
+		assert!(dynbox.occupied().is_none());
+
+		dynbox.set(B(42));
+		let mut occupied = dynbox.occupied().unwrap();
+		assert!(occupied.get().foo() == 42);
+		assert!(occupied.get_mut().foo() == 42);
+		assert!(!occupied.into_inner().empty());
+	}
+
+	#[test]
+	fn occupied_box_implements_as_ref() {
+		fn foo_of(value: &impl AsRef<dyn MyTrait>) -> u32 {
+			value.as_ref().foo()
+		}
+
+		let mut dynbox = DynBox::<64>::new();
+		dynbox.set(B(42));
+		let mut occupied = dynbox.occupied().unwrap();
+		assert!(foo_of(&occupied) == 42);
+		assert!(occupied.as_mut().foo() == 42);
+	}
+
+	#[test]
+	fn occupied_box_implements_borrow() {
+		use core::borrow::Borrow;
+
+		let mut dynbox = DynBox::<64>::new();
+		dynbox.set(B(42));
+		let occupied = dynbox.occupied().unwrap();
+		let content: &dyn MyTrait = occupied.borrow();
+		assert!(content.foo() == 42);
+	}
+
+	#[test]
+	fn default_size_is_used_without_size_parameter() {
+		let mut dynbox = DefaultSizedBox::new_default_size();
+		assert!(
+			core::mem::size_of::<DefaultSizedBox>() == core::mem::size_of::<DefaultSizedBox<32>>()
+		);
+
+		dynbox.set(B(42));
+		assert!(dynbox.get().unwrap().foo() == 42);
+	}
+
+	#[test]
+	#[cfg(feature = "alloc")]
+	fn stable_box_keeps_address_when_moved() {
+		let mut dynbox = StableBox::<16>::new();
+		dynbox.set(B(42));
+		let address = address_of(dynbox.get().unwrap());
+		let moved = [dynbox];
+		assert!(address_of(moved[0].get().unwrap()) == address);
+		assert!(moved[0].get().unwrap().foo() == 42);
+	}
+
+	#[test]
+	#[cfg(feature = "alloc")]
+	fn spill_box_moves_oversized_content_to_heap() {
+		let drop_was_called = Cell::new(false);
+		let mut dynbox = SpillBox::<8>::new();
+		dynbox.set(B(42));
+		let address = address_of(dynbox.get().unwrap());
+		assert!(
+			!(dynbox.store.as_ptr() as usize..dynbox.store.as_ptr() as usize + 8)
+				.contains(&address)
+		);
+		assert!(dynbox.get_mut().unwrap().foo() == 42);
+		assert!(dynbox.try_set(Wide(3)).is_ok());
+		assert!(address_of(dynbox.get().unwrap()).is_multiple_of(32));
+
+		dynbox.set(Droppable(&drop_was_called));
+		dynbox.clear();
+		assert!(drop_was_called.get());
+
+		dynbox.set(A);
+		assert!(dynbox.get().unwrap().foo() == 1);
+	}
+
+	#[test]
+	#[cfg(feature = "alloc")]
+	fn spilled_content_stays_on_heap_when_moved() {
+		let mut spilled = SpillBox::<8>::new();
+		spilled.set(B(42));
+		let address = address_of(spilled.get().unwrap());
+		let mut inline = SpillBox::<8>::new();
+		inline.set(A);
+
+		assert!(spilled.swap_with(&mut inline));
+		assert!(address_of(inline.get().unwrap()) == address);
+		assert!(spilled.get().unwrap().foo() == 1);
+
+		let mut other = SpillBox::<16>::new();
+		assert!(inline.move_into(&mut other).is_ok());
+		assert!(inline.empty());
+		assert!(address_of(other.get().unwrap()) == address);
+		assert!(other.into_option_box().unwrap().foo() == 42);
+	}
+
+	#[test]
+	#[cfg(feature = "alloc")]
+	fn exchange_returns_previous_value_boxed() {
+		let mut dynbox = DynBox::<64>::new();
+		assert!(dynbox.exchange(A {}).is_none());
+		assert!(dynbox.exchange(B(3)).unwrap().foo() == 1);
+		assert!(dynbox.get().unwrap().foo() == 3);
+	}
+
+	#[test]
+	#[cfg(feature = "alloc")]
+	fn boxes_can_be_converted_from_heap() {
+		let boxed: std::boxed::Box<dyn MyTrait> = std::boxed::Box::new(B(42));
+		let dynbox: DynBox<16> = boxed.try_into().ok().unwrap();
+		assert!(dynbox.get().unwrap().foo() == 42);
+
+		let boxed: std::boxed::Box<dyn MyTrait> = std::boxed::Box::new(B(42));
+		let result: Result<DynBox<8>, _> = boxed.try_into();
+		assert!(result.err().unwrap().foo() == 42);
+
+		let boxed: std::boxed::Box<dyn MyTrait> = std::boxed::Box::new(A {});
+		assert!(
+			DynBox::<0>::try_from(boxed)
+				.ok()
+				.unwrap()
+				.get()
+				.unwrap()
+				.foo() == 1
+		);
+	}
+
+	#[test]
+	#[cfg(feature = "alloc")]
+	fn into_option_box_moves_content_to_heap() {
+		let mut dynbox = DynBox::<64>::new();
+		dynbox.set(B(42));
+		assert!(dynbox.into_option_box().unwrap().foo() == 42);
+
+		let mut dynbox = DynBox::<64>::new();
+		dynbox.set(A);
+		assert!(dynbox.into_option_box().unwrap().foo() == 1);
+
+		let dynbox = DynBox::<64>::new();
+		assert!(dynbox.into_option_box().is_none());
+	}
+
+	#[test]
+	#[cfg(feature = "alloc")]
+	fn into_option_box_keeps_drop_responsibility() {
+		let drop_was_called = Cell::new(false);
+		let mut dynbox = DynBox::<64>::new();
+		dynbox.set(Droppable(&drop_was_called));
+
+		let boxed = dynbox.into_option_box().unwrap();
+		assert!(!drop_was_called.get());
+
+		drop(boxed);
+		assert!(drop_was_called.get());
+	}
+
+	#[test]
+	fn store_is_aligned_for_candidate_types() {
+		assert!(core::mem::align_of::<AlignedBox<64>>() == 32);
+
+		let mut dynbox = AlignedBox::<64>::new();
+		dynbox.set(A);
+		assert!(dynbox.get().unwrap().foo() == 1);
+		dynbox.set(B(42));
+		assert!(dynbox.get().unwrap().foo() == 42);
+		assert!(address_of(dynbox.get().unwrap()).is_multiple_of(16));
+		dynbox.set(Wide(7));
+		assert!(dynbox.get().unwrap().foo() == 7);
+		assert!(address_of(dynbox.get().unwrap()).is_multiple_of(32));
+	}
+
+	#[test]
+	fn store_is_aligned_regardless_of_size() {
+		let mut dynbox = DynBox::<24>::new();
+		dynbox.set(B(42));
+		assert!(address_of(dynbox.get().unwrap()).is_multiple_of(16));
+	}
+
+	#[test]
+	fn alignment_is_chosen_at_use_site() {
+		assert!(!DynBox::<64, 16>::can_hold::<Wide>());
+		assert!(DynBox::<64, 32>::can_hold::<Wide>());
+
+		let mut dynbox = DynBox::<64, 32>::new();
+		dynbox.set(Wide(42));
+		assert!(address_of(dynbox.get().unwrap()).is_multiple_of(32));
+		let mut dynbox = DynBox::<64, 8>::new();
+		dynbox.set(A {});
+		assert!(address_of(dynbox.get().unwrap()).is_multiple_of(8));
+	}
+
+	#[test]
+	#[should_panic]
+	fn set_above_chosen_alignment_panics() {
+		DynBox::<64, 8>::new().set(B(1));
+	}
+
+	#[test]
+	fn align_option_sets_default_alignment() {
+		assert!(SmallAlignBox::<8>::can_hold::<u32>());
+		assert!(!SmallAlignBox::<8>::can_hold::<u64>());
+		assert!(core::mem::size_of::<SmallAlignBox<8>>() < core::mem::size_of::<DynBox<8>>());
+	}
+
+	#[test]
+	#[should_panic(expected = "alignment 32 > 16")]
+	fn set_over_aligned_panics() {
+		DynBox::<64>::new().set(Wide(1));
+	}
+
+	#[test]
+	fn try_set_reports_over_alignment() {
+		use std::string::ToString;
+
+		let mut dynbox = DynBox::<64>::new();
+		let error = dynbox.try_set(Wide(1)).err().unwrap();
+		assert!(error.align() == 32 && error.max_align() == 16);
+		assert!(error.to_string() == "alignment 32 > 16");
+		assert!(dynbox.empty());
+	}
+
+	#[test]
+	fn store_supports_large_alignment() {
+		#[repr(align(64))]
+		struct Line(u8);
+		impl MyTrait for Line {
+			fn foo(&self) -> u32 {
+				self.0 as u32
+			}
+		}
+
+		assert!(core::mem::align_of::<DynBox<64, 64>>() == 64);
+		let mut dynbox = DynBox::<64, 64>::new();
+		dynbox.set(Line(3));
+		assert!(dynbox.get().unwrap().foo() == 3);
+		assert!(address_of(dynbox.get().unwrap()).is_multiple_of(64));
+	}
+
+	#[test]
+	fn clone_to_smaller_box() {
+		let mut dynbox = ClonableBox::<64>::new();
+		dynbox.set(B(1));
+
+		let clone = dynbox.clone_to::<16>().unwrap();
+		assert!(clone.get().unwrap().foo() == 1);
+		assert!(dynbox.get().unwrap().foo() == 1);
+		assert!(dynbox.clone_to::<8>().is_none());
+
+		let clone = clone.clone_to::<64>().unwrap();
+		assert!(clone.get().unwrap().foo() == 1);
+	}
+
+	#[test]
+	fn clone_clones_content() {
+		let mut dynbox = ClonableBox::<64>::new();
+		dynbox.set(B(1));
+		let mut clone = dynbox.clone();
+		assert!(clone.get().unwrap().foo() == 1);
+
+		clone.clone_from(&ClonableBox::new());
+		assert!(clone.empty());
+		clone.clone_from(&dynbox);
+		assert!(clone.get().unwrap().foo() == 1 && dynbox.get().unwrap().foo() == 1);
+	}
+
+	#[test]
+	fn clone_to_of_empty_box_is_empty() {
+		let dynbox = ClonableBox::<64>::new();
+		assert!(dynbox.clone_to::<8>().unwrap().empty());
+	}
+
+	#[test]
+	fn downcast_checks_stored_type() {
+		let mut dynbox = DowncastBox::<64>::new();
+		assert!(!dynbox.is::<B>());
+		assert!(dynbox.downcast_ref::<B>().is_none());
+
+		dynbox.set(B(42));
+		assert!(dynbox.is::<B>());
+		assert!(!dynbox.is::<A>());
+		assert!(dynbox.downcast_ref::<B>().unwrap().0 == 42);
+		assert!(dynbox.downcast_ref::<A>().is_none());
+	}
+
+	#[test]
+	fn downcast_mut_modifies_matching_type() {
+		let mut dynbox = DowncastBox::<16>::new();
+		dynbox.set(B(6));
+		assert!(dynbox.downcast_mut::<A>().is_none());
+		dynbox.downcast_mut::<B>().unwrap().0 = 7;
+		assert!(dynbox.get().unwrap().foo() == 7);
+	}
+
+	#[test]
+	fn take_moves_out_matching_type() {
+		let mut dynbox = DowncastBox::<16>::new();
+		dynbox.set(B(6));
+		assert!(dynbox.take::<A>().is_none());
+		assert!(!dynbox.empty());
+		assert!(dynbox.take::<B>() == Some(B(6)));
+		assert!(dynbox.empty());
+	}
+
+	#[test]
+	fn try_into_inner_moves_out_matching_type() {
+		let mut dynbox = DowncastBox::<16>::new();
+		dynbox.set(B(6));
+		let dynbox = match dynbox.try_into_inner::<A>() {
+			Ok(_) => panic!("content is not an A"),
+			Err(dynbox) => dynbox,
+		};
+		assert!(dynbox.try_into_inner::<B>().ok() == Some(B(6)));
+	}
+
+	#[test]
+	fn update_replaces_value_of_same_type() {
+		let mut dynbox = DowncastBox::<16>::new();
+		assert!(!dynbox.update(B(2)));
+		dynbox.set(B(1));
+		assert!(dynbox.update(B(2)));
+		assert!(dynbox.downcast_ref::<B>() == Some(&B(2)));
+		assert!(!dynbox.update(A));
+		assert!(dynbox.is::<B>());
+	}
+
+	#[test]
+	fn clear_of_type_only_clears_matching_boxes() {
+		let mut boxes: [DowncastBox<64>; 4] = core::array::from_fn(|_| DowncastBox::new());
+		boxes[0].set(A {});
+		boxes[1].set(B(1));
+		boxes[2].set(B(2));
+
+		assert!(DowncastBox::clear_of_type::<B>(&mut boxes) == 2);
+		assert!(boxes[0].is::<A>());
+		assert!(boxes[1].empty() && boxes[2].empty() && boxes[3].empty());
+	}
+
+	#[test]
+	fn compare_with_concrete_value() {
+		let mut dynbox = DowncastBox::<64>::new();
+		assert!(dynbox != B(42));
+
+		dynbox.set(B(42));
+		assert!(dynbox == B(42));
+		assert!(dynbox != B(7));
+		assert!(dynbox != A);
+	}
+
+	#[test]
+	fn map_transforms_content_into_new_box() {
+		let mut dynbox = DowncastBox::<64>::new();
+		dynbox.set(A {});
+		let mapped: DowncastBox<32> = dynbox.map(|_: A| B(1));
+		assert!(mapped.downcast_ref::<B>() == Some(&B(1)));
+
+		let mut dynbox = DowncastBox::<64>::new();
+		dynbox.set(B(2));
+		let mapped: DowncastBox<32> = dynbox.map(|_: A| B(1));
+		assert!(mapped.empty());
+	}
+
+	#[test]
+	fn pinned_box_is_not_unpin() {
+		// Ambiguous, and thus fails to compile, if `PinnedBox` implements `Unpin`.
+		trait AmbiguousIfUnpin<A> {
+			fn check() {}
+		}
+		impl<T: ?Sized> AmbiguousIfUnpin<()> for T {}
+		impl<T: ?Sized + Unpin> AmbiguousIfUnpin<u8> for T {}
+		<PinnedBox<16> as AmbiguousIfUnpin<_>>::check();
+	}
+
+	#[test]
+	fn send_box_moves_between_threads() {
+		let mut dynbox = ThreadBox::<16>::new();
+		dynbox.set(B(42));
+		let shared = std::sync::Arc::new(dynbox);
+		let other = shared.clone();
+		let foo = std::thread::spawn(move || other.get().unwrap().foo())
+			.join()
+			.unwrap();
+		assert!(foo == 42);
+		let dynbox = std::sync::Arc::into_inner(shared).unwrap();
+		assert!(
+			std::thread::spawn(move || dynbox.get().unwrap().foo())
+				.join()
+				.unwrap() == 42
+		);
+	}
+
+	#[test]
+	fn pinned_box_gives_pinned_access() {
+		let mut dynbox = PinnedBox::<16>::new();
+		dynbox.set(B(42));
+		let mut dynbox = core::pin::pin!(dynbox);
+		assert!(dynbox.as_mut().get_pin_mut().unwrap().foo() == 42);
+		assert!(dynbox.get().unwrap().foo() == 42);
+
+		dynbox.as_mut().set_pinned(B(7));
+		assert!(dynbox.as_mut().get_pin_mut().unwrap().foo() == 7);
+	}
+
+	#[test]
+	fn empty_no_panic_future_box_is_pending() {
+		use core::future::Future;
+		use core::task::{Context, Waker};
+
+		let dynbox = core::pin::pin!(PendingFutureBox::<16>::new());
+		let mut context = Context::from_waker(Waker::noop());
+		assert!(dynbox.poll(&mut context).is_pending());
+	}
+
+	#[test]
+	fn no_panic_box_reports_errors_without_panicking() {
+		let mut dynbox = NoPanicBox::<16>::new();
+		assert!(dynbox.foo() == 0);
+		assert!(dynbox.try_set(Wide(1)).is_err() && dynbox.empty());
+		dynbox.set(B(42));
+		assert!(dynbox.foo() == 42);
+
+		let mut boxes = [NoPanicGenerationBox::<16>::new()];
+		assert!(
+			NoPanicGenerationBox::set_at(&mut boxes, 1, B(1))
+				.err()
+				.unwrap()
+				.0 == 1
+		);
+		let handle = NoPanicGenerationBox::set_at(&mut boxes, 0, B(2))
+			.ok()
+			.unwrap();
+		assert!(
+			NoPanicGenerationBox::get_by_handle(&boxes, handle)
+				.unwrap()
+				.foo() == 2
+		);
+	}
+
+	#[test]
+	fn future_box_polls_content() {
+		use core::future::Future;
+		use core::task::{Context, Poll, Waker};
+
+		struct Countdown(u32);
+		impl Future for Countdown {
+			type Output = u32;
+			fn poll(mut self: core::pin::Pin<&mut Self>, _: &mut Context<'_>) -> Poll<u32> {
+				match self.0 {
+					0 => Poll::Ready(42),
+					_ => {
+						self.0 -= 1;
+						Poll::Pending
+					}
+				}
+			}
+		}
+
+		let mut dynbox = FutureBox::<16>::new();
+		dynbox.set(Countdown(1));
+		let mut dynbox = core::pin::pin!(dynbox);
+		let mut cx = Context::from_waker(Waker::noop());
+		assert!(dynbox.as_mut().poll(&mut cx) == Poll::Pending);
+		assert!(dynbox.as_mut().poll(&mut cx) == Poll::Ready(42));
+	}
+
+	#[test]
+	fn trait_can_be_given_by_path() {
+		let mut dynbox = PathBox::<16>::new();
+		dynbox.set(B(41));
+		assert!(dynbox.get().unwrap().bar() == 42);
+	}
+
+	#[test]
+	fn trait_can_have_generic_arguments() {
+		pub trait Handler<E> {
+			fn handle(&self, event: E) -> u32;
+		}
+		impl Handler<u32> for B {
+			fn handle(&self, event: u32) -> u32 {
+				self.0 as u32 + event
+			}
+		}
+		dynbox!(HandlerBox: Handler<u32>);
+		dynbox!(IterBox: Iterator<Item = u32>);
+
+		let mut dynbox = HandlerBox::<16>::new();
+		dynbox.set(B(40));
+		assert!(dynbox.get().unwrap().handle(2) == 42);
+
+		let mut dynbox = IterBox::<16>::new();
+		dynbox.set(1..3);
+		let iter = dynbox.get_mut().unwrap();
+		assert!(iter.next() == Some(1) && iter.next() == Some(2) && iter.next().is_none());
+	}
+
+	#[test]
+	fn cast_via_views_content_as_other_trait() {
+		let mut dynbox = BothBox::<16>::new();
+		assert!(dynbox.cast_via(|both| both as &dyn MyTrait).is_none());
+
+		dynbox.set(B(41));
+		assert!(dynbox.cast_via(|both| both as &dyn MyTrait).unwrap().foo() == 41);
+		assert!(
+			dynbox
+				.cast_via(|both| both as &dyn nested::Nested)
+				.unwrap()
+				.bar() == 42
+		);
+	}
+
+	#[test]
+	fn upcast_accessors_view_content_as_supertrait() {
+		let mut both = BothBox::<16>::new();
+		assert!(both.get_as_my_trait().is_none());
+		both.set(B(41));
+		assert!(both.get_as_nested().unwrap().bar() == 42);
+
+		let mut mine = DynBox::<16>::new();
+		mine.set(B(7));
+		let views = [both.get_as_my_trait(), mine.get()];
+		assert!(views.iter().map(|view| view.unwrap().foo()).eq([41, 7]));
+	}
+
+	#[test]
+	fn sizes_generate_aliases() {
+		assert!(SizedBoxSmall::can_hold::<[u8; 16]>() && !SizedBoxSmall::can_hold::<[u8; 17]>());
+		assert!(SizedBoxMedium::can_hold::<[u8; 64]>() && !SizedBoxMedium::can_hold::<[u8; 65]>());
+		let mut dynbox = SizedBoxMedium::new();
+		dynbox.set(B(42));
+		assert!(dynbox.get().unwrap().foo() == 42);
+	}
+
+	#[test]
+	fn get_as_unchecked_reads_stored_value() {
+		let mut dynbox = DowncastBox::<16>::new();
+		dynbox.set(B(42));
+		assert!(dynbox.is::<B>());
+		assert!(unsafe { dynbox.get_as_unchecked::<B>() }.0 == 42);
+	}
+
+	#[test]
+	fn view_as_reinterprets_prefix() {
+		#[repr(C)]
+		struct Header {
+			id: u64,
+			flags: u32,
+		}
+		impl MyTrait for Header {
+			fn foo(&self) -> u32 {
+				self.flags
+			}
+		}
+
+		let mut dynbox = DynBox::<16>::new();
+		assert!(unsafe { dynbox.view_as::<u64>() }.is_none());
+		dynbox.set(Header { id: 7, flags: 1 });
+		assert!(unsafe { dynbox.view_as::<u64>() } == Some(&7));
+		assert!(unsafe { dynbox.view_as::<[u64; 3]>() }.is_none());
+	}
+
+	#[test]
+	#[cfg(feature = "alloc")]
+	fn view_as_reinterprets_spilled_content() {
+		#[repr(C)]
+		struct Pair(u64, u64);
+		impl MyTrait for Pair {
+			fn foo(&self) -> u32 {
+				self.1 as u32
+			}
+		}
+
+		let mut dynbox = SpillBox::<8>::new();
+		dynbox.set(Pair(8, 1));
+		assert!(unsafe { dynbox.view_as::<u64>() } == Some(&8));
+	}
+
+	#[test]
+	fn take_and_replace_with_swaps_in_new_value() {
+		let mut dynbox = DowncastBox::<16>::new();
+		dynbox.set(B(1));
+		assert!(dynbox.take_and_replace_with::<A, _, _>(|| B(3)).is_none());
+		assert!(dynbox.take_and_replace_with::<B, _, _>(|| A {}) == Some(B(1)));
+		assert!(dynbox.is::<A>());
+	}
+
+	#[test]
+	fn ops_trait_is_implemented_by_box_and_test_double() {
+		use crate::DynContainer;
+
+		struct HeapBox<'a>(Option<std::boxed::Box<dyn MyTrait + 'a>>);
+		impl<'a> DynContainer<dyn MyTrait + 'a> for HeapBox<'a> {
+			fn get(&self) -> Option<&(dyn MyTrait + 'a)> {
+				self.0.as_deref()
+			}
+			fn get_mut(&mut self) -> Option<&mut (dyn MyTrait + 'a)> {
+				self.0.as_deref_mut()
+			}
+			fn clear(&mut self) {
+				self.0 = None;
+			}
+			fn empty(&self) -> bool {
+				self.0.is_none()
+			}
+		}
+		impl<'a> OpsBoxOps<'a> for HeapBox<'a> {
+			fn set<T: MyTrait + 'a>(&mut self, content: T) {
+				self.0 = Some(std::boxed::Box::new(content));
+			}
+		}
+
+		fn store_and_read<'a>(ops: &mut impl OpsBoxOps<'a>) -> u32 {
+			ops.set(B(42));
+			let foo = ops.get().unwrap().foo();
+			ops.clear();
+			assert!(ops.empty());
+			foo
+		}
+
+		assert!(store_and_read(&mut OpsBox::<16>::new()) == 42);
+		assert!(store_and_read(&mut HeapBox(None)) == 42);
+	}
+
+	#[test]
+	#[allow(clippy::clone_on_copy)]
+	fn copy_box_clones_content() {
+		let mut dynbox = CopyBox::<16>::new();
+		dynbox.set(Number(4));
+		let clone = dynbox.clone();
+		assert!(dynbox.get().unwrap().foo() == 4 && clone.get().unwrap().foo() == 4);
+	}
+
+	#[test]
+	fn copy_box_can_be_duplicated() {
+		let mut dynbox = CopyBox::<16>::new();
+		dynbox.set(Number(42));
+		let first = dynbox;
+		let second = dynbox;
+		assert!(first.get().unwrap().foo() == 42 && second.get().unwrap().foo() == 42);
+	}
 
-			/// Stores a value of some generic type which implements $trait. Panics if
-			/// T's size exceeds `SIZE`. Clears (and drops) the previous value, if
-			/// present.
-			pub fn set<T: $trait>(&mut self, content: T) {
-				if !self.empty() {
-					self.clear();
-				}
+	#[test]
+	fn display_forwards_to_content() {
+		use std::string::ToString;
 
-				let size = core::mem::size_of::<T>();
+		let mut dynbox = DisplayBox::<16>::new();
+		assert!(dynbox.to_string() == "<empty>");
+		dynbox.set(B(42));
+		assert!(dynbox.to_string() == "B(42)");
+	}
 
-				assert!(size <= SIZE);
+	#[test]
+	fn debug_forwards_to_content() {
+		use std::format;
 
-				let parts: [usize; 2] =
-					unsafe { core::mem::transmute(&content as *const dyn $trait) };
-				self.vtable = parts[1];
-				unsafe {
-					(&mut self.store as *mut _ as *mut T).copy_from(parts[0] as *mut _, 1);
-				}
-				core::mem::forget(content);
-			}
+		let mut dynbox = DebugBox::<16>::new();
+		assert!(format!("{:?}", dynbox) == "<empty>");
+		dynbox.set(7u8);
+		assert!(format!("{:?}", dynbox) == "7");
+	}
 
-			/// Makes the DynBox empty again by dropping the previous content, if any.
-			pub fn clear(&mut self) {
-				if self.vtable != 0 {
-					unsafe { core::ptr::drop_in_place(self.get_ptr_mut()) }
-					self.vtable = 0;
-				}
-			}
+	#[test]
+	fn defmt_forwards_to_content() {
+		use defmt::Format;
 
-			/// Returns whether the DynBox currently contains any value.
-			pub fn empty(&self) -> bool {
-				self.vtable == 0
-			}
+		let log = core::cell::RefCell::new(std::string::String::new());
+		let mut dynbox = LoggedBox::<16>::new();
+		dynbox.format(defmt::Formatter(&log));
+		dynbox.set(7u8);
+		dynbox.format(defmt::Formatter(&log));
+		assert!(*log.borrow() == "<empty>7");
+	}
 
-			/// Returns a `&dyn Trait` reference if not empty, or None otherwise.
-			pub fn get(&self) -> Option<&dyn $trait> {
-				if self.vtable == 0 {
-					None
-				} else {
-					Some(unsafe { &*self.get_ptr_mut() })
-				}
-			}
+	#[test]
+	fn defmt_type_name_prints_stored_type() {
+		use defmt::Format;
 
-			/// Returns a `&mut dyn Trait` reference if not empty, or None otherwise.
-			pub fn get_mut(&mut self) -> Option<&mut dyn $trait> {
-				if self.vtable == 0 {
-					None
-				} else {
-					Some(unsafe { &mut *self.get_ptr_mut() })
-				}
-			}
+		let log = core::cell::RefCell::new(std::string::String::new());
+		let mut dynbox = NamedLoggedBox::<16>::new();
+		dynbox.format(defmt::Formatter(&log));
+		dynbox.set(B(7));
+		dynbox.format(defmt::Formatter(&log));
+		assert!(*log.borrow() == std::format!("<empty>{}", core::any::type_name::<B>()));
+	}
+
+	#[test]
+	fn box_for_types_holds_each_of_them() {
+		let mut dynbox = ForBox::new_default_size();
+		dynbox.set(A);
+		assert!(dynbox.get().unwrap().foo() == 1);
+		dynbox.set(B(2));
+		assert!(dynbox.get().unwrap().foo() == 2);
+		dynbox.set(Wide(3));
+		assert!(dynbox.get().unwrap().foo() == 3);
+		assert!(address_of(dynbox.get().unwrap()).is_multiple_of(32));
+
+		let mut dynbox = ForSyntaxBox::new_default_size();
+		dynbox.set_checked(B(4));
+		assert!(dynbox.clone().get().unwrap().foo() == 4);
+		assert!(<ForSyntaxBox>::capacity() == 16);
+	}
+
+	#[test]
+	fn verify_detects_corruption() {
+		let mut dynbox = CheckedBox::<16>::new();
+		dynbox.set(B(5));
+		unsafe {
+			dynbox.seal();
+			assert!(dynbox.verify());
+			dynbox.as_bytes_mut().unwrap()[3] ^= 0x10;
+			assert!(!dynbox.verify());
+		}
+	}
+
+	#[test]
+	fn zeroized_box_wipes_content_on_clear_and_set() {
+		let mut dynbox = ZeroizedBox::<16>::new();
+		dynbox.set(B(u128::MAX));
+		dynbox.clear();
+		let store = unsafe { core::slice::from_raw_parts(dynbox.store.as_ptr(), 16) };
+		assert!(store.iter().all(|&byte| byte == 0));
 
-			unsafe fn get_ptr_mut(&self) -> *mut dyn $trait {
-				let foo: [usize; 2] = [&self.store as *const _ as usize, self.vtable];
-				return core::mem::transmute(foo);
+		dynbox.set(B(u128::MAX));
+		dynbox.set(A);
+		let store = unsafe { core::slice::from_raw_parts(dynbox.store.as_ptr(), 16) };
+		assert!(store.iter().all(|&byte| byte == 0));
+	}
+
+	#[test]
+	fn visit_reports_state_of_content() {
+		struct Names(std::vec::Vec<std::string::String>);
+		impl crate::Visitor for Names {
+			fn field(&mut self, name: &str, value: crate::Value<'_>) {
+				self.0.push(std::format!("{}={:?}", name, value));
 			}
 		}
-	};
-}
 
-#[cfg(doc)]
-/// Some object-safe trait
-pub trait MyTrait {}
+		let mut dynbox = VisitedBox::<16>::new();
+		let mut names = Names(std::vec::Vec::new());
+		assert!(!dynbox.visit(&mut names));
+		dynbox.set(B(3));
+		assert!(dynbox.visit(&mut names));
+		assert!(names.0 == ["value=Uint(3)"]);
+	}
 
-#[cfg(doc)]
-dynbox!(MyDynBox: MyTrait);
+	#[test]
+	fn type_name_of_stored_value() {
+		let mut dynbox = NamedBox::<16>::new();
+		assert!(dynbox.type_name().is_none());
+		dynbox.set(B(1));
+		assert!(dynbox.type_name().unwrap().ends_with("B"));
+		dynbox.clear();
+		assert!(dynbox.type_name().is_none());
+	}
 
-#[cfg(test)]
-mod tests {
-	use core::cell::Cell;
+	#[test]
+	fn sort_by_extracted_key() {
+		let mut boxes: [KeyedBox<16>; 3] = core::array::from_fn(|_| KeyedBox::new());
+		boxes[0].set(B(7));
+		boxes[2].set(B(3));
 
-	pub trait MyTrait {
-		fn foo(&self) -> u32;
+		boxes.sort_by_key(|dynbox| dynbox.sort_key());
+		assert!(boxes[0].sort_key() == 3 && boxes[1].sort_key() == 7);
+		assert!(boxes[2].empty());
 	}
 
-	struct A;
-	struct B(u128);
-	struct Droppable<'a>(&'a Cell<bool>);
+	#[test]
+	fn eq_and_hash_forward_to_content() {
+		use core::hash::{Hash, Hasher};
 
-	impl MyTrait for A {
-		fn foo(&self) -> u32 {
-			1
+		fn hash_of(dynbox: &ComparedBox<16>) -> u64 {
+			let mut hasher = std::collections::hash_map::DefaultHasher::new();
+			dynbox.hash(&mut hasher);
+			hasher.finish()
 		}
+
+		let mut first = ComparedBox::<16>::new();
+		let mut second = ComparedBox::<16>::new();
+		assert!(first == second && hash_of(&first) == hash_of(&second));
+		first.set(B(1));
+		assert!(first != second && first == B(1));
+		second.set(B(1));
+		assert!(first == second && hash_of(&first) == hash_of(&second));
+		second.set(B(2));
+		assert!(first != second);
 	}
-	impl MyTrait for B {
-		fn foo(&self) -> u32 {
-			self.0 as u32
+
+	#[test]
+	fn min_useful_box_can_hold_usize() {
+		let mut dynbox = UsefulBox::<{ core::mem::size_of::<usize>() }>::new();
+		dynbox.set(A {});
+		assert!(!dynbox.empty());
+	}
+
+	#[test]
+	fn split_mut_borrows_content_and_generation() {
+		pub trait Counter {
+			fn count(&self) -> u32;
+			fn increment(&mut self);
+		}
+		impl Counter for u32 {
+			fn count(&self) -> u32 {
+				*self
+			}
+			fn increment(&mut self) {
+				*self += 1;
+			}
 		}
+		dynbox!(CounterBox: Counter, generation);
+
+		let mut dynbox = CounterBox::<16>::new();
+		assert!(dynbox.split_mut().is_none());
+		dynbox.set(1u32);
+		let before = dynbox.generation();
+		let (content, generation) = dynbox.split_mut().unwrap();
+		content.increment();
+		generation.bump();
+		assert!(dynbox.get().unwrap().count() == 2);
+		assert!(dynbox.generation() == before + 1);
 	}
-	impl MyTrait for Droppable<'_> {
-		fn foo(&self) -> u32 {
-			2
+
+	pub trait SelfAware {
+		fn at_home(&self) -> bool;
+		fn on_move(&mut self);
+	}
+
+	struct Homed(usize);
+
+	impl SelfAware for Homed {
+		fn at_home(&self) -> bool {
+			self.0 == self as *const Homed as usize
+		}
+		fn on_move(&mut self) {
+			self.0 = self as *const Homed as usize;
 		}
 	}
-	impl Drop for Droppable<'_> {
-		fn drop(&mut self) {
-			self.0.set(true);
+
+	dynbox!(MovingBox: SelfAware, on_move);
+	dynbox!(UnawareBox: SelfAware);
+
+	#[test]
+	fn on_move_fixes_up_moved_content() {
+		let mut dynbox = MovingBox::<16>::new();
+		dynbox.set(Homed(0));
+		assert!(dynbox.get().unwrap().at_home());
+
+		let mut moved = std::boxed::Box::new(dynbox);
+		assert!(moved.get_mut().unwrap().at_home());
+		assert!(moved.get().unwrap().at_home());
+
+		let mut moved = std::boxed::Box::new(*moved);
+		assert!(moved.occupied().unwrap().get().at_home());
+		let mut moved = std::boxed::Box::new(*moved);
+		assert!(crate::DynContainer::get_mut(&mut *moved).unwrap().at_home());
+
+		let mut unaware = UnawareBox::<16>::new();
+		unaware.set(Homed(0));
+		assert!(!unaware.get().unwrap().at_home());
+		unaware.get_mut().unwrap().on_move();
+		let mut moved = std::boxed::Box::new(unaware);
+		assert!(!moved.get_mut().unwrap().at_home());
+	}
+
+	dynbox!(OnceBox: FnOnce() -> u32);
+
+	#[test]
+	fn call_once_consumes_closure() {
+		let name = std::string::String::from("dynbox");
+		let mut once_box = OnceBox::<32>::new();
+		once_box.set(move || name.len() as u32);
+		assert!(once_box.call_once() == Some(6));
+		assert!(once_box.empty());
+		assert!(once_box.call_once().is_none());
+	}
+
+	#[test]
+	fn stale_handle_resolves_to_none() {
+		let mut boxes: [GenerationBox<16>; 3] = core::array::from_fn(|_| GenerationBox::new());
+		let handle = GenerationBox::set_at(&mut boxes, 1, B(5));
+		assert!(GenerationBox::get_by_handle(&boxes, handle).unwrap().foo() == 5);
+
+		boxes[1].clear();
+		assert!(GenerationBox::get_by_handle(&boxes, handle).is_none());
+		boxes[1].set(B(6));
+		assert!(GenerationBox::get_by_handle(&boxes, handle).is_none());
+	}
+
+	#[test]
+	fn raw_handle_round_trips() {
+		let mut dynbox = GenerationBox::<16>::new();
+		assert!(dynbox.raw_handle().is_none());
+		dynbox.set(B(42));
+		let handle = dynbox.raw_handle().unwrap();
+		assert!(unsafe { dynbox.resolve_raw(handle) }.foo() == 42);
+	}
+
+	#[test]
+	#[cfg(debug_assertions)]
+	#[should_panic(expected = "stale RawHandle")]
+	fn stale_raw_handle_panics_in_debug_builds() {
+		let mut dynbox = GenerationBox::<16>::new();
+		dynbox.set(B(42));
+		let handle = dynbox.raw_handle().unwrap();
+		let generation = dynbox.generation();
+		dynbox.clear();
+		dynbox.set(B(43));
+		assert!(dynbox.generation() == generation + 2);
+		unsafe { dynbox.resolve_raw(handle) };
+	}
+
+	#[test]
+	fn produce_calls_method_spec() {
+		let mut dynbox = FactoryBox::new_default_size();
+		assert!(dynbox.produce().is_none());
+		dynbox.set(B(42));
+		assert!(dynbox.produce() == Some(42));
+	}
+
+	#[test]
+	fn with_passes_content_to_closure() {
+		let mut dynbox = TallyBox::<16>::new();
+		assert!(dynbox.with(|tally| tally.total()).is_none());
+		assert!(dynbox.with_mut(|tally| tally.add(1, 1)).is_none());
+
+		dynbox.set(B(1));
+		dynbox.with_mut(|tally| tally.add(2, 2)).unwrap();
+		assert!(dynbox.with(|tally| tally.total()) == Some(5));
+	}
+
+	#[test]
+	fn delegating_box_implements_trait() {
+		fn add_twice(tally: &mut dyn Tally) -> u128 {
+			tally.add(2, 3);
+			tally.add(1, 1);
+			tally.total()
 		}
+
+		let mut dynbox = TallyBox::<16>::new();
+		assert!(dynbox.total() == 0);
+		dynbox.set(B(1));
+		assert!(add_twice(&mut dynbox) == 8);
+		assert!(dynbox.get().unwrap().total() == 8);
 	}
 
-	dynbox!(DynBox: MyTrait);
+	#[test]
+	#[should_panic(expected = "called `add()` on an empty DynBox")]
+	fn delegating_box_panics_without_default() {
+		TallyBox::<16>::new().add(1, 1);
+	}
 
 	#[test]
-	fn new_dynbox_is_empty() {
-		let mut dynbox = DynBox::<64>::new();
-		assert!(dynbox.empty());
-		assert!(dynbox.get().is_none());
-		assert!(dynbox.get_mut().is_none());
+	fn mutable_method_spec_polls_future() {
+		use core::task::Poll;
+
+		pub trait MyFuture {
+			fn poll(&mut self) -> Poll<u32>;
+		}
+		struct Countdown(u32);
+		impl MyFuture for Countdown {
+			fn poll(&mut self) -> Poll<u32> {
+				if self.0 == 0 {
+					Poll::Ready(42)
+				} else {
+					self.0 -= 1;
+					Poll::Pending
+				}
+			}
+		}
+		dynbox!(FutBox: MyFuture { fn poll(&mut self) -> Poll<u32> });
+
+		let mut dynbox = FutBox::<16>::new();
+		assert!(dynbox.poll().is_none());
+		dynbox.set(Countdown(2));
+		assert!(dynbox.poll() == Some(Poll::Pending));
+		assert!(dynbox.poll() == Some(Poll::Pending));
+		assert!(dynbox.poll() == Some(Poll::Ready(42)));
 	}
 
 	#[test]
-	fn set_dynbox_is_not_empty_and_clear_makes_it_empty() {
-		let a = A;
+	fn can_hold_checks_size_and_alignment() {
+		assert!(DynBox::<16>::can_hold::<B>());
+		assert!(!DynBox::<8>::can_hold::<B>());
+		assert!(!DynBox::<64>::can_hold::<Wide>());
+		assert!(AlignedBox::<64>::can_hold::<Wide>());
+	}
 
-		let mut dynbox = DynBox::<64>::new();
-		dynbox.set(a);
+	#[test]
+	fn why_not_fit_explains_failure() {
+		assert!(DynBox::<16>::why_not_fit::<B>().is_none());
+		assert!(DynBox::<4>::why_not_fit::<B>() == Some("size 16 > capacity 4"));
+		assert!(DynBox::<64>::why_not_fit::<Wide>() == Some("alignment 32 > 16"));
+		assert!(AlignedBox::<64>::why_not_fit::<Wide>().is_none());
+	}
+
+	#[test]
+	fn assert_capacity_accepts_large_enough_box() {
+		fn process<const N: usize>(dynbox: &mut DynBox<N>) {
+			DynBox::<N>::assert_capacity::<16>();
+			dynbox.set(B(42));
+		}
+
+		let mut dynbox = DynBox::<32>::new();
+		process(&mut dynbox);
 		assert!(!dynbox.empty());
+	}
 
-		dynbox.clear();
+	#[test]
+	fn panicking_destructor_runs_once() {
+		struct CountsDrops<'a>(&'a Cell<u32>);
+		impl MyTrait for CountsDrops<'_> {
+			fn foo(&self) -> u32 {
+				4
+			}
+		}
+		impl Drop for CountsDrops<'_> {
+			fn drop(&mut self) {
+				self.0.set(self.0.get() + 1);
+				panic!("destructor panics");
+			}
+		}
+
+		let drops = Cell::new(0);
+		let mut dynbox = DynBox::<16>::new();
+		dynbox.set(CountsDrops(&drops));
+		let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| dynbox.clear()));
+		assert!(result.is_err());
 		assert!(dynbox.empty());
+		dynbox.clear();
+		drop(dynbox);
+		assert!(drops.get() == 1);
 	}
 
 	#[test]
-	fn zero_sized() {
-		let a = A;
+	fn clear_all_continues_after_panicking_destructor() {
+		struct PanicsOnDrop;
+		impl MyTrait for PanicsOnDrop {
+			fn foo(&self) -> u32 {
+				3
+			}
+		}
+		impl Drop for PanicsOnDrop {
+			fn drop(&mut self) {
+				panic!("destructor panics");
+			}
+		}
 
-		let mut dynbox = DynBox::<64>::new();
-		assert!(dynbox.empty());
-		assert!(dynbox.get().is_none());
-		assert!(dynbox.get_mut().is_none());
+		let first = Cell::new(false);
+		let last = Cell::new(false);
+		let mut boxes = [DynBox::<16>::new(), DynBox::new(), DynBox::new()];
+		boxes[0].set(Droppable(&first));
+		boxes[1].set(PanicsOnDrop);
+		boxes[2].set(Droppable(&last));
+		let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+			DynBox::clear_all(&mut boxes);
+		}));
+		assert!(result.is_err());
+		assert!(first.get() && last.get());
+		assert!(boxes.iter().all(DynBox::empty));
+	}
 
-		dynbox.set(a);
-		assert!(!dynbox.empty());
+	#[test]
+	fn fill_from_stores_into_empty_boxes() {
+		let mut boxes = [DynBox::<16>::new(), DynBox::new(), DynBox::new()];
+		let mut values = (1..=5).map(B);
+		assert!(DynBox::fill_from(&mut boxes, &mut values) == 3);
+		assert!(values.next().unwrap().0 == 4);
+		assert!(boxes.each_ref().map(|dynbox| dynbox.get().unwrap().foo()) == [1, 2, 3]);
 
-		assert!(dynbox.get().unwrap().foo() == 1);
-		assert!(dynbox.get_mut().unwrap().foo() == 1);
+		let mut boxes = [DynBox::<16>::new(), DynBox::new(), DynBox::new()];
+		boxes[0].set(A {});
+		assert!(DynBox::fill_from(&mut boxes, (7..=7).map(B)) == 1);
+		assert!(boxes[1].get().unwrap().foo() == 7);
+		assert!(boxes[2].empty());
 	}
 
 	#[test]
-	fn set_smaller() {
-		let b = B(42);
+	fn empty_array_holds_empty_boxes() {
+		let boxes = DynBox::<16>::empty_array::<4>();
+		assert!(boxes.len() == 4 && boxes.iter().all(DynBox::empty));
+	}
 
-		let mut dynbox = DynBox::<64>::new();
-		dynbox.set(b);
-		assert!(dynbox.get().unwrap().foo() == 42);
-		assert!(dynbox.get_mut().unwrap().foo() == 42);
+	#[test]
+	fn array_from_stores_each_value() {
+		let boxes: [DynBox<16>; 3] = DynBox::array_from([B(1), B(2), B(3)]);
+		assert!(boxes.each_ref().map(|dynbox| dynbox.get().unwrap().foo()) == [1, 2, 3]);
+	}
 
-		dynbox.clear();
+	#[test]
+	#[should_panic]
+	fn array_from_too_large_values_panics() {
+		let _: [DynBox<8>; 2] = DynBox::array_from([B(1), B(2)]);
 	}
 
 	#[test]
-	fn set_same_size() {
-		let b = B(42);
+	#[cfg(debug_assertions)]
+	fn corrupted_vtable_is_detected_in_debug_builds() {
+		fn corrupted(vtable: crate::Vtable) -> core::ptr::NonNull<()> {
+			vtable
+				.get()
+				.unwrap()
+				.map_addr(|addr| core::num::NonZeroUsize::new(addr.get() ^ 1).unwrap())
+		}
 
 		let mut dynbox = DynBox::<16>::new();
-		dynbox.set(b);
+		dynbox.set(B(42));
 		assert!(dynbox.get().unwrap().foo() == 42);
+
+		dynbox.vtable = crate::Vtable::occupied(corrupted(dynbox.vtable));
+		let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+			dynbox.get().unwrap().foo();
+		}));
+		assert!(result.is_err());
+		dynbox.vtable = crate::Vtable::occupied(corrupted(dynbox.vtable));
 		assert!(dynbox.get_mut().unwrap().foo() == 42);
 	}
 
 	#[test]
-	#[should_panic]
-	fn set_too_large_panics() {
-		let b = B(42);
+	fn references_can_be_stored() {
+		struct Counter(u32);
+		impl MyTrait for &mut Counter {
+			fn foo(&self) -> u32 {
+				self.0
+			}
+		}
 
-		let mut dynbox = DynBox::<4>::new();
-		dynbox.set(b);
-		assert!(dynbox.get().unwrap().foo() == 42);
-		assert!(dynbox.get_mut().unwrap().foo() == 42);
+		let mut counter = Counter(41);
+		let mut dynbox = DynBox::<16>::new();
+		dynbox.set(&mut counter);
+		assert!(dynbox.get().unwrap().foo() == 41);
+		drop(dynbox);
+		counter.0 += 1;
+		assert!(counter.0 == 42);
 	}
 
 	#[test]
@@ -221,6 +4939,99 @@ mod tests {
 		assert!(drop_was_called.get());
 	}
 
+	#[test]
+	fn consume_with_drops_after_visit() {
+		let drop_was_called = Cell::new(false);
+		let mut dynbox = DynBox::<64>::new();
+		assert!(!dynbox.consume_with(|_| unreachable!()));
+
+		dynbox.set(Droppable(&drop_was_called));
+		let mut visited = false;
+		assert!(dynbox.consume_with(|content| {
+			assert!(content.foo() == 2 && !drop_was_called.get());
+			visited = true;
+		}));
+		assert!(visited && drop_was_called.get());
+		assert!(dynbox.empty());
+	}
+
+	#[test]
+	fn repr_c_box_puts_vtable_last() {
+		let word = core::mem::size_of::<usize>();
+		let check = if cfg!(debug_assertions) { word } else { 0 };
+		assert!(core::mem::offset_of!(SharedBox<16>, store) == 0);
+		assert!(core::mem::offset_of!(SharedBox<16>, vtable) == 16 + check + word);
+		assert!(
+			core::mem::size_of::<SharedBox<16>>() == (16 + check + 2 * word).next_multiple_of(16)
+		);
+
+		let mut dynbox = SharedBox::<16>::new();
+		dynbox.set(B(3));
+		assert!(dynbox.clone().get().unwrap().foo() == 3);
+	}
+
+	#[test]
+	fn raw_parts_round_trip() {
+		let mut dynbox = DynBox::<16>::new();
+		dynbox.set(B(21));
+		let (bytes, vtable, size) = dynbox.into_raw_parts();
+		assert!(size == 16);
+		let dynbox = unsafe { DynBox::<16>::from_raw_parts(bytes, vtable, size) };
+		assert!(dynbox.get().unwrap().foo() == 21);
+
+		let (bytes, vtable, size) = DynBox::<16>::new().into_raw_parts();
+		assert!(vtable.is_null() && size == 0);
+		assert!(unsafe { DynBox::<16>::from_raw_parts(bytes, vtable, size) }.empty());
+	}
+
+	#[test]
+	fn manually_dropped_content_is_dropped_after_reconstruction() {
+		let drop_was_called = Cell::new(false);
+		let mut dynbox = DynBox::<16>::new();
+		dynbox.set(Droppable(&drop_was_called));
+
+		let (bytes, vtable, size) = dynbox.into_manually_drop();
+		assert!(!drop_was_called.get());
+		let dynbox = unsafe { DynBox::<16>::from_manually_drop(bytes, vtable, size) };
+		assert!(dynbox.get().unwrap().foo() == 2);
+		drop(dynbox);
+		assert!(drop_was_called.get());
+	}
+
+	#[test]
+	fn raw_parts_are_read_from_copied_store() {
+		let drop_was_called = Cell::new(false);
+		let mut dynbox = DynBox::<16>::new();
+		dynbox.set(Droppable(&drop_was_called));
+
+		let (data, vtable, size) = dynbox.as_raw_parts();
+		let mut message = [0u8; 17];
+		unsafe {
+			message[1..]
+				.as_mut_ptr()
+				.copy_from_nonoverlapping(data, size)
+		};
+		core::mem::forget(dynbox);
+		let dynbox = unsafe { DynBox::<16>::read_raw_parts(message[1..].as_ptr(), vtable, size) };
+		assert!(dynbox.get().unwrap().foo() == 2);
+		drop(dynbox);
+		assert!(drop_was_called.get());
+	}
+
+	#[test]
+	fn raw_parts_content_is_dropped_after_reconstruction() {
+		let drop_was_called = Cell::new(false);
+		let mut dynbox = DynBox::<16>::new();
+		dynbox.set(Droppable(&drop_was_called));
+
+		let (bytes, vtable, size) = dynbox.into_raw_parts();
+		assert!(!drop_was_called.get());
+		let dynbox = unsafe { DynBox::<16>::from_raw_parts(bytes, vtable, size) };
+		assert!(dynbox.get().unwrap().foo() == 2);
+		drop(dynbox);
+		assert!(drop_was_called.get());
+	}
+
 	#[test]
 	fn drop_is_called_on_set() {
 		let drop_was_called = Cell::new(false);
@@ -0,0 +1,73 @@
+use crate::DynContainer;
+
+/// Mutable handle to a container that is known to be occupied, allowing total
+/// access to its content of type `T`. Obtained through the generated `occupied`
+/// method or [`OccupiedBox::new`].
+pub struct OccupiedBox<'a, B: DynContainer<T> + ?Sized, T: ?Sized> {
+	// A pointer rather than `&'a mut B`, as moving the reference would invalidate
+	// `content` under Stacked Borrows.
+	inner: *mut B,
+	// Obtained from `inner` once, so that accessing it cannot fail.
+	content: *mut T,
+	_inner: core::marker::PhantomData<&'a mut B>,
+}
+
+// Like the `&mut B` and `&mut T` it consists of.
+unsafe impl<B: DynContainer<T> + Send + ?Sized, T: Send + ?Sized> Send for OccupiedBox<'_, B, T> {}
+unsafe impl<B: DynContainer<T> + Sync + ?Sized, T: Sync + ?Sized> Sync for OccupiedBox<'_, B, T> {}
+
+impl<'a, B: DynContainer<T> + ?Sized, T: ?Sized> OccupiedBox<'a, B, T> {
+	/// Returns an `OccupiedBox` if `container` holds a value, or None otherwise.
+	pub fn new(container: &'a mut B) -> Option<OccupiedBox<'a, B, T>> {
+		let inner = container as *mut B;
+		// SAFETY: `inner` comes from `container`, which is borrowed for `'a`.
+		let content = unsafe { (*inner).get_mut()? as *mut T };
+		Some(OccupiedBox {
+			inner,
+			content,
+			_inner: core::marker::PhantomData,
+		})
+	}
+
+	/// Returns a reference to the content.
+	pub fn get(&self) -> &T {
+		// SAFETY: The container stays borrowed, so the content cannot go away.
+		unsafe { &*self.content }
+	}
+
+	/// Returns a mutable reference to the content.
+	pub fn get_mut(&mut self) -> &mut T {
+		// SAFETY: As above, and `self` is borrowed mutably.
+		unsafe { &mut *self.content }
+	}
+
+	/// Gives back the underlying container.
+	pub fn into_inner(self) -> &'a mut B {
+		// SAFETY: As above.
+		unsafe { &mut *self.inner }
+	}
+}
+
+impl<B: DynContainer<T> + ?Sized, T: ?Sized> AsRef<T> for OccupiedBox<'_, B, T> {
+	fn as_ref(&self) -> &T {
+		self.get()
+	}
+}
+
+impl<B: DynContainer<T> + ?Sized, T: ?Sized> AsMut<T> for OccupiedBox<'_, B, T> {
+	fn as_mut(&mut self) -> &mut T {
+		self.get_mut()
+	}
+}
+
+impl<B: DynContainer<T> + ?Sized, T: ?Sized> core::borrow::Borrow<T> for OccupiedBox<'_, B, T> {
+	fn borrow(&self) -> &T {
+		self.get()
+	}
+}
+
+impl<B: DynContainer<T> + ?Sized, T: ?Sized> core::borrow::BorrowMut<T> for OccupiedBox<'_, B, T> {
+	fn borrow_mut(&mut self) -> &mut T {
+		self.get_mut()
+	}
+}
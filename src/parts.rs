@@ -0,0 +1,53 @@
+/// Data pointer and vtable pointer of a trait object pointer, laid out like
+/// `*mut dyn Trait`. Transmuting between the two keeps the provenance of both
+/// pointers, unlike a round trip through `[usize; 2]`, so that the content can
+/// be accessed under strict provenance.
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct DynParts {
+	pub data: *mut u8,
+	pub vtable: core::ptr::NonNull<()>,
+}
+
+/// Vtable pointer of a container's content, or the explicit empty state. The
+/// empty state is stored as address 1, where no vtable can be located as vtables
+/// are aligned to at least a `usize`, which leaves the niche at 0 free for
+/// `Option` of the container.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Vtable(core::ptr::NonNull<()>);
+
+impl Vtable {
+	/// State of a container without content.
+	pub const EMPTY: Vtable = Vtable(core::ptr::NonNull::without_provenance(
+		core::num::NonZeroUsize::MIN,
+	));
+
+	/// What `bits()` returns for the empty state.
+	pub const EMPTY_BITS: usize = core::num::NonZeroUsize::MIN.get();
+
+	/// State of a container holding content with the given vtable.
+	pub fn occupied(vtable: core::ptr::NonNull<()>) -> Vtable {
+		debug_assert!(vtable != Vtable::EMPTY.0, "vtable at the empty marker");
+		Vtable(vtable)
+	}
+
+	/// Returns the vtable pointer, or None if empty.
+	pub fn get(self) -> Option<core::ptr::NonNull<()>> {
+		if self.is_empty() {
+			None
+		} else {
+			Some(self.0)
+		}
+	}
+
+	/// Returns whether this is the empty state.
+	pub fn is_empty(self) -> bool {
+		self == Vtable::EMPTY
+	}
+
+	/// Returns the address of the vtable, or of the empty marker, for integrity
+	/// checks.
+	pub fn bits(self) -> usize {
+		self.0.as_ptr().addr()
+	}
+}
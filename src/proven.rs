@@ -0,0 +1,46 @@
+/// Setter for a container which was proven at compile time to be able to hold
+/// a `T`, so that storing one cannot panic. Obtained through the generated
+/// `prove` method, which fails to compile if `T` does not fit:
+/// ```compile_fail
+/// use dyn_box::dynbox;
+/// trait MyTrait {}
+/// impl MyTrait for u64 {}
+/// dynbox!(MyDynBox: MyTrait);
+/// let mut my_dynbox = MyDynBox::<4>::new();
+/// my_dynbox.prove::<u64>().set(42);
+/// ```
+pub struct ProvenSetter<'b, B: ?Sized, T> {
+	target: &'b mut B,
+	_content: core::marker::PhantomData<fn(T)>,
+}
+
+impl<'b, B: ?Sized, T> ProvenSetter<'b, B, T> {
+	/// # Safety
+	/// `target` must be able to hold a `T`.
+	#[doc(hidden)]
+	pub unsafe fn new(target: &'b mut B) -> ProvenSetter<'b, B, T> {
+		ProvenSetter {
+			target,
+			_content: core::marker::PhantomData,
+		}
+	}
+
+	/// Stores `content`, clearing (and dropping) the previous value, if present.
+	pub fn set(self, content: T)
+	where
+		B: SetProven<T>,
+	{
+		// SAFETY: `new()` requires the target to be able to hold a `T`.
+		unsafe { self.target.set_proven(content) }
+	}
+}
+
+/// Implemented by the generated containers for every `T` they can store.
+#[doc(hidden)]
+pub trait SetProven<T> {
+	/// Stores `content` without checking whether it fits.
+	///
+	/// # Safety
+	/// The container must be able to hold a `T`.
+	unsafe fn set_proven(&mut self, content: T);
+}
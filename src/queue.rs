@@ -0,0 +1,119 @@
+/// Producing end of a queue generated by [`dynqueue!`](crate::dynqueue), of
+/// which only one exists at a time. Obtained through the generated `split`
+/// method.
+pub struct Producer<'q, Q: ?Sized> {
+	queue: &'q Q,
+}
+
+/// Consuming end of a queue generated by [`dynqueue!`](crate::dynqueue), of
+/// which only one exists at a time. Obtained through the generated `split`
+/// method.
+pub struct Consumer<'q, Q: ?Sized> {
+	queue: &'q Q,
+}
+
+/// Pushing side of a single-producer single-consumer queue, implemented by the
+/// generated queues for every value type they accept.
+#[doc(hidden)]
+pub trait QueuePush<T> {
+	/// Appends `value`, or gives it back if the queue is full or it does not fit.
+	///
+	/// # Safety
+	/// Must not be called concurrently with itself.
+	unsafe fn push(&self, value: T) -> Result<(), T>;
+}
+
+/// Popping side of a single-producer single-consumer queue, implemented by the
+/// generated queues.
+#[doc(hidden)]
+pub trait QueuePop {
+	/// Trait object type of the queued values.
+	type Content: ?Sized;
+
+	/// Returns a pointer to the oldest value, which stays queued, or None if the
+	/// queue is empty.
+	///
+	/// # Safety
+	/// Must not be called concurrently with itself or `release_front()`.
+	unsafe fn front(&self) -> Option<*mut Self::Content>;
+
+	/// Drops the oldest value and frees its slot.
+	///
+	/// # Safety
+	/// `front()` must have returned a value since the last call, and no reference
+	/// to it may be used afterwards. Must not be called concurrently with itself
+	/// or `front()`.
+	unsafe fn release_front(&self);
+}
+
+/// Oldest value of a queue generated by [`dynqueue!`](crate::dynqueue), owned
+/// by the consumer until the guard is dropped, which drops the value and frees
+/// its slot. Obtained through [`Consumer::pop`].
+pub struct Popped<'c, Q: QueuePop + ?Sized> {
+	queue: &'c Q,
+	content: *mut Q::Content,
+}
+
+impl<Q: QueuePop + ?Sized> core::ops::Deref for Popped<'_, Q> {
+	type Target = Q::Content;
+
+	fn deref(&self) -> &Q::Content {
+		unsafe { &*self.content }
+	}
+}
+
+impl<Q: QueuePop + ?Sized> core::ops::DerefMut for Popped<'_, Q> {
+	fn deref_mut(&mut self) -> &mut Q::Content {
+		unsafe { &mut *self.content }
+	}
+}
+
+impl<Q: QueuePop + ?Sized> Drop for Popped<'_, Q> {
+	fn drop(&mut self) {
+		// The guard borrows the consumer, so it is the only user of the front.
+		unsafe { self.queue.release_front() }
+	}
+}
+
+impl<'q, Q: ?Sized> Producer<'q, Q> {
+	/// # Safety
+	/// No other `Producer` of `queue` may exist at the same time.
+	#[doc(hidden)]
+	pub unsafe fn new(queue: &'q Q) -> Producer<'q, Q> {
+		Producer { queue }
+	}
+
+	/// Appends `value` to the queue, or gives it back if the queue is full or the
+	/// value does not fit into a slot.
+	pub fn push<T>(&mut self, value: T) -> Result<(), T>
+	where
+		Q: QueuePush<T>,
+	{
+		unsafe { self.queue.push(value) }
+	}
+}
+
+impl<'q, Q: QueuePop + ?Sized> Consumer<'q, Q> {
+	/// # Safety
+	/// No other `Consumer` of `queue` may exist at the same time.
+	#[doc(hidden)]
+	pub unsafe fn new(queue: &'q Q) -> Consumer<'q, Q> {
+		Consumer { queue }
+	}
+
+	/// Calls `f` with the oldest value of the queue and drops it afterwards, or
+	/// returns None without calling `f` if the queue is empty.
+	pub fn pop_with<R>(&mut self, f: impl FnOnce(&mut Q::Content) -> R) -> Option<R> {
+		self.pop().map(|mut content| f(&mut content))
+	}
+
+	/// Removes the oldest value from the queue, returning a guard which owns it
+	/// and drops it when dropped itself, or returns None if the queue is empty.
+	pub fn pop(&mut self) -> Option<Popped<'_, Q>> {
+		let content = unsafe { self.queue.front() }?;
+		Some(Popped {
+			queue: self.queue,
+			content,
+		})
+	}
+}
@@ -0,0 +1,24 @@
+/// Raw pointer to the content of a container, together with the container's
+/// generation at the time it was obtained. Obtained through the generated
+/// `raw_handle` method of containers with the `generation` option.
+pub struct RawHandle<T: ?Sized> {
+	ptr: *mut T,
+	generation: usize,
+}
+
+impl<T: ?Sized> RawHandle<T> {
+	#[doc(hidden)]
+	pub fn new(ptr: *mut T, generation: usize) -> RawHandle<T> {
+		RawHandle { ptr, generation }
+	}
+
+	/// Returns the pointer to the content.
+	pub fn as_ptr(&self) -> *mut T {
+		self.ptr
+	}
+
+	/// Returns the generation of the container when the handle was obtained.
+	pub fn generation(&self) -> usize {
+		self.generation
+	}
+}
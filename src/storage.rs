@@ -0,0 +1,85 @@
+/// Maps an alignment value to a zero-sized marker type of that alignment.
+pub struct Align<const ALIGN: usize>;
+
+/// Implemented by [`Align`] for every supported alignment.
+pub trait Alignment {
+	/// Zero-sized type with the given alignment.
+	type Marker: Copy;
+}
+
+macro_rules! alignments {
+	($($align:literal => $marker:ident),*) => {
+		$(
+			#[derive(Clone, Copy)]
+			#[repr(align($align))]
+			pub struct $marker;
+
+			impl Alignment for Align<$align> {
+				type Marker = $marker;
+			}
+		)*
+	};
+}
+
+alignments!(
+	1 => Align1, 2 => Align2, 4 => Align4, 8 => Align8, 16 => Align16, 32 => Align32,
+	64 => Align64, 128 => Align128, 256 => Align256, 512 => Align512, 1024 => Align1024,
+	2048 => Align2048, 4096 => Align4096
+);
+
+/// Inline byte buffer of `SIZE` bytes, aligned to `ALIGN`. Its bytes start out
+/// uninitialized, so creating one is free regardless of `SIZE`.
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct Storage<const SIZE: usize, const ALIGN: usize = 16>
+where
+	Align<ALIGN>: Alignment,
+{
+	_align: [<Align<ALIGN> as Alignment>::Marker; 0],
+	bytes: core::mem::MaybeUninit<[u8; SIZE]>,
+}
+
+impl<const SIZE: usize, const ALIGN: usize> Storage<SIZE, ALIGN>
+where
+	Align<ALIGN>: Alignment,
+{
+	pub const fn new() -> Storage<SIZE, ALIGN> {
+		Storage {
+			_align: [],
+			bytes: core::mem::MaybeUninit::uninit(),
+		}
+	}
+
+	/// Returns a pointer to the start of the buffer, valid for the whole buffer.
+	pub fn as_ptr(&self) -> *const u8 {
+		self as *const Self as *const u8
+	}
+
+	/// Returns a mutable pointer to the start of the buffer, valid for the whole
+	/// buffer.
+	pub fn as_mut_ptr(&mut self) -> *mut u8 {
+		self as *mut Self as *mut u8
+	}
+}
+
+impl<const SIZE: usize, const ALIGN: usize> Default for Storage<SIZE, ALIGN>
+where
+	Align<ALIGN>: Alignment,
+{
+	fn default() -> Storage<SIZE, ALIGN> {
+		Storage::new()
+	}
+}
+
+/// Returns the largest of `values`, or 1 if there are none.
+pub const fn max(values: &[usize]) -> usize {
+	let mut max = 1;
+	let mut i = 0;
+	while i < values.len() {
+		if values[i] > max {
+			max = values[i];
+		}
+		i += 1;
+	}
+	max
+}
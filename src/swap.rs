@@ -0,0 +1,72 @@
+/// Publishing end of a double buffer generated by [`dynswap!`](crate::dynswap),
+/// of which only one exists at a time. Obtained through the generated `split`
+/// method.
+pub struct Publisher<'s, S: ?Sized> {
+	swap: &'s S,
+}
+
+/// Reading end of a double buffer generated by [`dynswap!`](crate::dynswap), of
+/// which only one exists at a time. Obtained through the generated `split`
+/// method.
+pub struct Reader<'s, S: ?Sized> {
+	swap: &'s S,
+}
+
+/// Publishing side of a double buffer, implemented by the generated double
+/// buffers for every value type they accept.
+#[doc(hidden)]
+pub trait SwapPublish<T> {
+	/// Replaces the current value with `value`, or gives it back if it does not
+	/// fit.
+	///
+	/// # Safety
+	/// Must not be called concurrently with itself.
+	unsafe fn publish(&self, value: T) -> Result<(), T>;
+}
+
+/// Reading side of a double buffer, implemented by the generated double buffers.
+#[doc(hidden)]
+pub trait SwapRead {
+	/// Trait object type of the published values.
+	type Content: ?Sized;
+
+	/// Calls `f` with the current value, or returns None if none was published.
+	///
+	/// # Safety
+	/// Must not be called concurrently with itself.
+	unsafe fn read_with<R>(&self, f: impl FnOnce(&mut Self::Content) -> R) -> Option<R>;
+}
+
+impl<'s, S: ?Sized> Publisher<'s, S> {
+	/// # Safety
+	/// No other `Publisher` of `swap` may exist at the same time.
+	#[doc(hidden)]
+	pub unsafe fn new(swap: &'s S) -> Publisher<'s, S> {
+		Publisher { swap }
+	}
+
+	/// Replaces the current value with `value`, or gives it back if it does not
+	/// fit into a slot. Waits for the reader to finish with the previous value,
+	/// which is then dropped.
+	pub fn publish<T>(&mut self, value: T) -> Result<(), T>
+	where
+		S: SwapPublish<T>,
+	{
+		unsafe { self.swap.publish(value) }
+	}
+}
+
+impl<'s, S: SwapRead + ?Sized> Reader<'s, S> {
+	/// # Safety
+	/// No other `Reader` of `swap` may exist at the same time.
+	#[doc(hidden)]
+	pub unsafe fn new(swap: &'s S) -> Reader<'s, S> {
+		Reader { swap }
+	}
+
+	/// Calls `f` with the most recently published value, or returns None without
+	/// calling `f` if none was published yet. Never waits for the publisher.
+	pub fn borrow_current<R>(&mut self, f: impl FnOnce(&mut S::Content) -> R) -> Option<R> {
+		unsafe { self.swap.read_with(f) }
+	}
+}
@@ -0,0 +1,62 @@
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::{RawWaker, RawWakerVTable, Waker};
+
+/// Flags marking which of `N` tasks were woken and are due to be polled, for
+/// task slabs generated by [`dyntasks!`](crate::dyntasks). The flags need to be
+/// `'static`, e.g. in a `static`, as the wakers of the tasks point to them and
+/// may be kept around, e.g. by interrupt handlers, longer than the tasks.
+pub struct ReadyFlags<const N: usize>([AtomicBool; N]);
+
+static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, wake, wake, drop);
+
+fn clone(flag: *const ()) -> RawWaker {
+	RawWaker::new(flag, &VTABLE)
+}
+
+fn wake(flag: *const ()) {
+	unsafe { &*(flag as *const AtomicBool) }.store(true, Ordering::Release);
+}
+
+fn drop(_: *const ()) {}
+
+impl<const N: usize> Default for ReadyFlags<N> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<const N: usize> ReadyFlags<N> {
+	/// Creates flags with no task marked as ready.
+	pub const fn new() -> ReadyFlags<N> {
+		ReadyFlags([const { AtomicBool::new(false) }; N])
+	}
+
+	/// Returns whether any task is marked as ready, e.g. to decide whether the
+	/// executor may sleep until the next interrupt.
+	pub fn any(&self) -> bool {
+		self.0.iter().any(|flag| flag.load(Ordering::Acquire))
+	}
+
+	#[doc(hidden)]
+	pub fn set(&self, slot: usize) {
+		self.0[slot].store(true, Ordering::Release);
+	}
+
+	#[doc(hidden)]
+	pub fn take(&self, slot: usize) -> bool {
+		// Without `swap()`, which targets like thumbv6m lack. Only the executor
+		// clears flags, and a wake between the load and the store is covered by
+		// the poll which follows.
+		let ready = self.0[slot].load(Ordering::Acquire);
+		if ready {
+			self.0[slot].store(false, Ordering::Relaxed);
+		}
+		ready
+	}
+
+	#[doc(hidden)]
+	pub fn waker(&'static self, slot: usize) -> Waker {
+		let flag = &self.0[slot] as *const AtomicBool as *const ();
+		unsafe { Waker::from_raw(RawWaker::new(flag, &VTABLE)) }
+	}
+}
@@ -0,0 +1,24 @@
+/// Value of a field reported to a [`Visitor`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Value<'v> {
+	Bool(bool),
+	Int(i64),
+	Uint(u64),
+	Float(f64),
+	Str(&'v str),
+	Bytes(&'v [u8]),
+}
+
+/// Receives the state of a value field by field, e.g. to write a snapshot of it
+/// to flash.
+pub trait Visitor {
+	/// Receives the field `name` with its current `value`.
+	fn field(&mut self, name: &str, value: Value<'_>);
+}
+
+/// Value whose state can be reported to a [`Visitor`], as required by the
+/// `visit` option of [`dynbox!`](crate::dynbox).
+pub trait Visit {
+	/// Reports the fields of the value to `visitor`.
+	fn visit(&self, visitor: &mut dyn Visitor);
+}